@@ -110,7 +110,7 @@ impl<T> Eq for Strong<T> {}
 
 impl<T> PartialEq for Strong<T> {
     fn eq(&self, other: &Self) -> bool {
-        self.inner.key().eq(&other.inner.key())
+        self.inner.eq(&other.inner)
     }
 }
 
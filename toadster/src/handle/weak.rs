@@ -65,7 +65,7 @@ impl<T> From<&handle::Weak<T>> for Weak<T> {
 
 impl<T> Clone for Weak<T> {
     fn clone(&self) -> Self {
-        Self::new(self.key().clone())
+        *self
     }
 }
 
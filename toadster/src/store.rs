@@ -72,6 +72,14 @@ where
     }
 }
 
+impl<'r, T: 'r> Values<'r, T> for Vec<T> {
+    type Values = std::slice::Iter<'r, T>;
+
+    fn values(&'r self) -> Self::Values {
+        self.iter()
+    }
+}
+
 pub trait ValuesMut<'r, T: 'r> {
     type ValuesMut: Iterator<Item = &'r mut T>;
 
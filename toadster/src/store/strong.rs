@@ -328,4 +328,17 @@ mod tests {
         let removed: Vec<_> = store.removed().ready().collect();
         assert_eq!(removed, vec![weak_handle]);
     }
+
+    #[test]
+    fn untyped_handles_of_different_types_should_not_be_equal() {
+        let mut a = store::Strong::new();
+        let mut b = store::Strong::new();
+
+        // Both stores are empty, so the handles have the same key.
+        let a = a.insert(0u8).into_untyped();
+        let b = b.insert(0u16).into_untyped();
+
+        assert_ne!(a, b);
+        assert_ne!(a.clone(), b.clone());
+    }
 }
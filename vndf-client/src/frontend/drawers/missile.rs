@@ -0,0 +1,31 @@
+use crate::{
+    frontend::shaders::{frag, vert},
+    game::Game,
+    graphics::elements::ScreenElement,
+    shared::world::features::missiles::Missile,
+};
+
+use super::{DrawResources, Frame};
+
+pub fn draw_missile(
+    res: &mut DrawResources,
+    frame: &mut Frame,
+    missile: &Missile,
+    game: &Game,
+) -> Option<()> {
+    let transform = ScreenElement::from_missile(missile, game, &frame.screen)?
+        .transform(&frame.screen);
+
+    res.drawables.ship.draw(
+        &res.device,
+        frame,
+        vert::simple::Uniforms {
+            transform: transform.into(),
+        },
+        frag::simple::Uniforms {
+            color: [1.0, 0.4, 0.2].into(),
+        },
+    );
+
+    Some(())
+}
@@ -1,13 +1,17 @@
 pub mod background;
 pub mod explosion;
 pub mod grid;
+pub mod missile;
 pub mod orbit;
+pub mod path;
 pub mod planet;
+pub mod projectile;
 pub mod ship;
 
 pub use self::{
     background::draw_background, explosion::draw_explosion, grid::draw_grid,
-    orbit::draw_orbit, planet::draw_planet, ship::draw_ship,
+    missile::draw_missile, orbit::draw_orbit, path::draw_path,
+    planet::draw_planet, projectile::draw_projectile, ship::draw_ship,
 };
 
 use crate::graphics::screen::Screen;
@@ -5,10 +5,10 @@ use crate::{
     },
     game::Game,
     graphics::{self, elements::WorldElement},
-    shared::world::features::orbits::Orbit,
+    shared::world::{self, features::orbits::Orbit},
 };
 
-use super::{DrawResources, Frame};
+use super::{draw_path, DrawResources, Frame};
 
 pub fn draw_orbit(
     res: &mut DrawResources,
@@ -16,7 +16,13 @@ pub fn draw_orbit(
     orbit: &Orbit,
     game: &Game,
 ) -> Option<()> {
-    let element = WorldElement::from(orbit);
+    let element = match WorldElement::from_ellipse(orbit) {
+        Some(element) => element,
+        None => {
+            draw_open_orbit(res, frame, orbit, game);
+            return Some(());
+        }
+    };
 
     let transform = element.transform(&game.state.camera, &frame.screen);
 
@@ -27,16 +33,14 @@ pub fn draw_orbit(
     ];
     let u_per_pixel = [1.0 / pixel_per_u[0], 1.0 / pixel_per_u[1]];
 
-    let orbiter_angle_abs = orbit.orbiter.pos.to_vector().angle_from_x_axis();
+    let orbiter_pos = orbit.orbiter.pos - orbit.center;
+    let orbiter_vel = orbit.orbiter.vel - orbit.center_vel;
+
+    let orbiter_angle_abs = orbiter_pos.angle_from_x_axis();
     let orbiter_angle_to_orbit =
         (orbiter_angle_abs - orbit.arg_of_periapsis).signed();
 
-    let orbiter_dir = orbit
-        .orbiter
-        .pos
-        .to_vector()
-        .angle_to(orbit.orbiter.vel)
-        .radians;
+    let orbiter_dir = orbiter_pos.angle_to(orbiter_vel).radians;
     let orbiter_dir = if orbiter_dir < 0.0 {
         -1.0
     } else if orbiter_dir > 0.0 {
@@ -61,3 +65,26 @@ pub fn draw_orbit(
 
     Some(())
 }
+
+/// Draws a parabolic or hyperbolic orbit
+///
+/// The orbit shader can only handle ellipses, so open orbits are drawn as a
+/// polyline instead.
+fn draw_open_orbit(
+    res: &mut DrawResources,
+    frame: &mut Frame,
+    orbit: &Orbit,
+    game: &Game,
+) {
+    let camera = &game.state.camera;
+
+    // Only draw as much of the orbit as can be seen.
+    let max_distance = (camera.center - orbit.center).length()
+        + camera
+            .world_size_on_screen(&frame.screen)
+            .to_vector()
+            .length();
+
+    let points = orbit.points(world::Length::new(max_distance), 256);
+    draw_path(res, frame, &points, [1.0, 1.0, 1.0, 1.0], game);
+}
@@ -0,0 +1,45 @@
+use crate::{
+    frontend::shaders::{frag, vert},
+    game::Game,
+    graphics::{self, elements::ScreenElement},
+    shared::world,
+};
+
+use super::{DrawResources, Frame};
+
+/// Draws a polyline through the given points, which are in world space
+pub fn draw_path(
+    res: &mut DrawResources,
+    frame: &mut Frame,
+    points: &[world::Pnt2],
+    color: [f32; 4],
+    game: &Game,
+) {
+    let camera = &game.state.camera;
+
+    for segment in points.windows(2) {
+        let start = camera.world_to_screen(&frame.screen, segment[0]);
+        let end = camera.world_to_screen(&frame.screen, segment[1]);
+
+        const THICKNESS: f32 = 1.5;
+        let line = end - start;
+
+        let transform = ScreenElement {
+            size: graphics::Size::new(line.length(), THICKNESS),
+            pos: start + line / 2.0,
+            angle: line.angle_from_x_axis(),
+        }
+        .transform(&frame.screen);
+
+        res.drawables.square.draw(
+            &res.device,
+            frame,
+            vert::simple::Uniforms {
+                transform: transform.into(),
+            },
+            frag::simple::Uniforms {
+                color: color.into(),
+            },
+        );
+    }
+}
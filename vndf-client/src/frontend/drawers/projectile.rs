@@ -0,0 +1,32 @@
+use crate::{
+    frontend::shaders::{frag, vert},
+    game::Game,
+    graphics::elements::ScreenElement,
+    shared::world::features::weapons::Projectile,
+};
+
+use super::{DrawResources, Frame};
+
+pub fn draw_projectile(
+    res: &mut DrawResources,
+    frame: &mut Frame,
+    projectile: &Projectile,
+    game: &Game,
+) -> Option<()> {
+    let transform =
+        ScreenElement::from_projectile(projectile, game, &frame.screen)?
+            .transform(&frame.screen);
+
+    res.drawables.square.draw(
+        &res.device,
+        frame,
+        vert::simple::Uniforms {
+            transform: transform.into(),
+        },
+        frag::simple::Uniforms {
+            color: [1.0, 0.9, 0.6].into(),
+        },
+    );
+
+    Some(())
+}
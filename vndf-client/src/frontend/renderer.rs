@@ -10,8 +10,9 @@ use crate::{
 use super::{
     drawables::{self, Drawables},
     drawers::{
-        draw_background, draw_explosion, draw_grid, draw_orbit, draw_planet,
-        draw_ship, DrawResources, Frame,
+        draw_background, draw_explosion, draw_grid, draw_missile, draw_orbit,
+        draw_path, draw_planet, draw_projectile, draw_ship, DrawResources,
+        Frame,
     },
     meshes::{self, Meshes},
     ui::{self, Ui},
@@ -132,12 +133,27 @@ impl Renderer {
         for orbit in game.state.active_orbits() {
             draw_orbit(&mut self.draw_res, &mut frame, &orbit, game);
         }
+        if let Some(trajectory) = game.state.predicted_path() {
+            draw_path(
+                &mut self.draw_res,
+                &mut frame,
+                &trajectory.points,
+                [1.0, 0.6, 0.2, 1.0],
+                game,
+            );
+        }
         for planet in game.state.data.planets.values() {
             draw_planet(&mut self.draw_res, &mut frame, planet, game);
         }
         for ship in game.state.data.ships.values() {
             draw_ship(&mut self.draw_res, &mut frame, ship, game);
         }
+        for missile in game.state.data.missiles.values() {
+            draw_missile(&mut self.draw_res, &mut frame, missile, game);
+        }
+        for projectile in game.state.data.projectiles.values() {
+            draw_projectile(&mut self.draw_res, &mut frame, projectile, game);
+        }
         for explosion in game.state.data.explosions.values() {
             draw_explosion(&mut self.draw_res, &mut frame, explosion, game);
         }
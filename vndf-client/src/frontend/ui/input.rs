@@ -27,8 +27,20 @@ pub type Cursor = Option<graphics::Pnt2>;
 
 #[derive(Clone, Copy, Debug)]
 pub enum Action {
-    AddCommand,
+    AddManeuver,
+    CancelManeuvers,
+    DiscardManeuver,
+    ExecuteManeuver,
     FtlJump,
     FtlTimeDown,
     FtlTimeUp,
+    ManeuverEarlier,
+    ManeuverLater,
+    ProgradeDown,
+    ProgradeUp,
+    RadialDown,
+    RadialUp,
+    Respawn,
+    TimeWarpDown,
+    TimeWarpUp,
 }
@@ -13,9 +13,9 @@ use crate::{
         drawers::{DrawResources, Frame},
         window::Window,
     },
-    game::{self, Game},
+    game::{self, state::ManeuverNode, Game},
     graphics::{self, screen::Screen},
-    shared::world,
+    shared::world::{self, features::players::Player},
 };
 
 use self::{
@@ -28,10 +28,20 @@ use self::{
     },
 };
 
+/// Time until a newly added maneuver node is reached, in seconds
+const MANEUVER_DELAY: world::Scalar = 600.0;
+
+/// Amount by which a maneuver node's time is adjusted, in seconds
+const MANEUVER_TIME_STEP: world::Scalar = 60.0;
+
+/// Amount by which a maneuver node's delta-v is adjusted, in m/s
+const MANEUVER_DELTA_V_STEP: world::Scalar = 10.0;
+
 pub struct Ui {
     input: Input,
     scale_factor: graphics::Scalar,
     jump_time_min: u32,
+    time_warp: world::Scalar,
 }
 
 impl Ui {
@@ -40,6 +50,7 @@ impl Ui {
             input: Input::new(),
             scale_factor: window.scale_factor(),
             jump_time_min: 30,
+            time_warp: 1.0,
         }
     }
 
@@ -95,8 +106,13 @@ impl Ui {
             frame,
         );
 
-        let ship_control =
-            ShipControl::create(res, MARGIN, game, self.jump_time_min)?;
+        let ship_control = ShipControl::create(
+            res,
+            MARGIN,
+            game,
+            self.jump_time_min,
+            self.time_warp,
+        )?;
         if let Some(ship_control) = ship_control {
             canvas.add_anchored(ship_control, Anchor::top_right(), frame);
         }
@@ -120,8 +136,23 @@ impl Ui {
 
         for action in self.input.actions.drain(..) {
             match action {
-                Action::AddCommand => {
-                    game.state.add_command();
+                Action::AddManeuver => {
+                    game.state.maneuver =
+                        Some(ManeuverNode::new(MANEUVER_DELAY));
+                }
+                Action::CancelManeuvers => {
+                    let _ = game.handle_input(game::Input::CancelManeuvers);
+                }
+                Action::DiscardManeuver => {
+                    game.state.maneuver = None;
+                }
+                Action::ExecuteManeuver => {
+                    if let Some(planned) = game.state.planned_maneuver() {
+                        let _ = game.handle_input(game::Input::Maneuver(
+                            planned.maneuver,
+                        ));
+                        game.state.maneuver = None;
+                    }
                 }
                 Action::FtlJump => {
                     let time = self.jump_time_min as world::Scalar * 60.0;
@@ -133,6 +164,51 @@ impl Ui {
                 Action::FtlTimeUp => {
                     self.jump_time_min = self.jump_time_min.saturating_add(15);
                 }
+                Action::ManeuverEarlier => {
+                    if let Some(node) = &mut game.state.maneuver {
+                        node.delay = (node.delay - MANEUVER_TIME_STEP).max(0.0);
+                    }
+                }
+                Action::ManeuverLater => {
+                    if let Some(node) = &mut game.state.maneuver {
+                        node.delay = (node.delay + MANEUVER_TIME_STEP)
+                            .min(ManeuverNode::MAX_DELAY);
+                    }
+                }
+                Action::ProgradeDown => {
+                    if let Some(node) = &mut game.state.maneuver {
+                        node.prograde -= MANEUVER_DELTA_V_STEP;
+                    }
+                }
+                Action::ProgradeUp => {
+                    if let Some(node) = &mut game.state.maneuver {
+                        node.prograde += MANEUVER_DELTA_V_STEP;
+                    }
+                }
+                Action::RadialDown => {
+                    if let Some(node) = &mut game.state.maneuver {
+                        node.radial -= MANEUVER_DELTA_V_STEP;
+                    }
+                }
+                Action::RadialUp => {
+                    if let Some(node) = &mut game.state.maneuver {
+                        node.radial += MANEUVER_DELTA_V_STEP;
+                    }
+                }
+                Action::Respawn => {
+                    let _ = game.handle_input(game::Input::Respawn);
+                }
+                Action::TimeWarpDown => {
+                    self.time_warp = (self.time_warp / 10.0).max(1.0);
+                    let _ = game
+                        .handle_input(game::Input::TimeWarp(self.time_warp));
+                }
+                Action::TimeWarpUp => {
+                    self.time_warp =
+                        (self.time_warp * 10.0).min(Player::MAX_WARP);
+                    let _ = game
+                        .handle_input(game::Input::TimeWarp(self.time_warp));
+                }
             }
         }
 
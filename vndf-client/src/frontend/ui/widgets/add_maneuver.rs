@@ -8,14 +8,14 @@ use crate::{
 use super::{text, Button};
 
 #[derive(DrawAt, Size, ProcessInputAt)]
-pub struct AddCommand(Button);
+pub struct AddManeuver(Button);
 
-impl AddCommand {
+impl AddManeuver {
     pub fn create(res: &mut DrawResources) -> Result<Self, text::CreateError> {
         let button = Button::create(
             res,
-            format!("Add command"),
-            Action::AddCommand,
+            "Add maneuver".to_string(),
+            Action::AddManeuver,
             [0.1, 0.0, 0.0, 0.95],
             [0.5, 0.0, 0.0, 0.95],
         )?;
@@ -22,9 +22,11 @@ impl ComponentStats {
                     Explosions: {}/{}\n\
                     Fuels: {}/{}\n\
                     Healths: {}/{}\n\
+                    Missiles: {}/{}\n\
                     Planets: {}/{}\n\
                     Players: {}/-\n\
                     Positions: {}/{}\n\
+                    Projectiles: {}/{}\n\
                     Ships: {}/{}\n\
                     Velocities: {}/{}",
                     diagnostics.bodies,
@@ -37,11 +39,15 @@ impl ComponentStats {
                     game.state.data.fuels.len(),
                     diagnostics.healths,
                     game.state.data.healths.len(),
+                    diagnostics.missiles,
+                    game.state.data.missiles.len(),
                     diagnostics.planets,
                     game.state.data.planets.len(),
                     diagnostics.players,
                     diagnostics.positions,
                     game.state.data.positions.len(),
+                    diagnostics.projectiles,
+                    game.state.data.projectiles.len(),
                     diagnostics.ships,
                     game.state.data.ships.len(),
                     diagnostics.velocities,
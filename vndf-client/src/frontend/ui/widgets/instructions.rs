@@ -20,12 +20,16 @@ impl Instructions {
                 Turn right - {}\n\
                 Thrust On - {}\n\
                 Thrust Off - {}\n\
+                Fire - {}\n\
+                Launch Missile - {}\n\
                 Zoom Camera - Mouse Wheel\n\
                 End game - {}",
                 game.input.config.input.left,
                 game.input.config.input.right,
                 game.input.config.input.thrust_on,
                 game.input.config.input.thrust_off,
+                game.input.config.input.fire,
+                game.input.config.input.launch_missile,
                 game.input.config.input.quit,
             ),
         )?;
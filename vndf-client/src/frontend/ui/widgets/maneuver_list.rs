@@ -0,0 +1,43 @@
+use vndf_macros::{DrawAt, ProcessInputAt, Size};
+
+use crate::{frontend::drawers::DrawResources, game::Game, graphics};
+
+use super::{text, Column, TextPanel};
+
+/// Lists the maneuvers that the server has scheduled for the own ship
+#[derive(DrawAt, ProcessInputAt, Size)]
+pub struct ManeuverList(Column);
+
+impl ManeuverList {
+    pub fn create(
+        res: &mut DrawResources,
+        margin: graphics::Scalar,
+        game: &Game,
+    ) -> Result<Option<Self>, text::CreateError> {
+        let ship = match game.state.own_ship() {
+            Some(ship) => ship,
+            None => return Ok(None),
+        };
+        let craft = match game.state.data.crafts.get(&ship.craft) {
+            Some(craft) if !craft.maneuvers.is_empty() => craft,
+            _ => return Ok(None),
+        };
+
+        let mut column = Column::create(margin / 3.0);
+        column.add(TextPanel::create(res, "Scheduled Maneuvers".to_string())?);
+        for maneuver in &craft.maneuvers {
+            let delay = maneuver.delay.max(0.0) as u64;
+            column.add(TextPanel::create(
+                res,
+                format!(
+                    "In {}:{:02}: {:.1} m/s",
+                    delay / 60,
+                    delay % 60,
+                    maneuver.delta_v.length(),
+                ),
+            )?);
+        }
+
+        Ok(Some(Self(column)))
+    }
+}
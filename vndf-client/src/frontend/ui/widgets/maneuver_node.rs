@@ -0,0 +1,65 @@
+use vndf_macros::{DrawAt, ProcessInputAt, Size};
+
+use crate::{
+    frontend::{drawers::DrawResources, ui::input::Action},
+    game::Game,
+    graphics,
+};
+
+use super::{text, Button, Column, TextPanel};
+
+/// Shows the maneuver node that is being planned, and controls to adjust it
+#[derive(DrawAt, ProcessInputAt, Size)]
+pub struct ManeuverNode(Column);
+
+impl ManeuverNode {
+    pub fn create(
+        res: &mut DrawResources,
+        margin: graphics::Scalar,
+        game: &Game,
+    ) -> Result<Option<Self>, text::CreateError> {
+        let node = match &game.state.maneuver {
+            Some(node) => node,
+            None => return Ok(None),
+        };
+
+        let mut column = Column::create(margin / 3.0);
+
+        let delay = node.delay as u64;
+        column.add(TextPanel::create(
+            res,
+            format!(
+                "Maneuver Node\n\
+                Time: {}:{:02}\n\
+                Prograde: {:.1} m/s\n\
+                Radial: {:.1} m/s",
+                delay / 60,
+                delay % 60,
+                node.prograde,
+                node.radial,
+            ),
+        )?);
+
+        let buttons = [
+            ("Later", Action::ManeuverLater),
+            ("Earlier", Action::ManeuverEarlier),
+            ("Prograde +", Action::ProgradeUp),
+            ("Prograde -", Action::ProgradeDown),
+            ("Radial +", Action::RadialUp),
+            ("Radial -", Action::RadialDown),
+            ("Execute", Action::ExecuteManeuver),
+            ("Discard", Action::DiscardManeuver),
+        ];
+        for &(text, action) in &buttons {
+            column.add(Button::create(
+                res,
+                text.to_string(),
+                action,
+                [0.1, 0.0, 0.0, 0.95],
+                [0.5, 0.0, 0.0, 0.95],
+            )?);
+        }
+
+        Ok(Some(Self(column)))
+    }
+}
@@ -0,0 +1,43 @@
+use vndf_macros::{DrawAt, ProcessInputAt, Size};
+
+use crate::{
+    frontend::{drawers::DrawResources, ui::input::Action},
+    game::Game,
+    graphics,
+};
+
+use super::{text, AddManeuver, Button, Column, ManeuverList, ManeuverNode};
+
+#[derive(DrawAt, ProcessInputAt, Size)]
+pub struct Maneuvers(Column);
+
+impl Maneuvers {
+    pub fn create(
+        res: &mut DrawResources,
+        margin: graphics::Scalar,
+        game: &Game,
+    ) -> Result<Self, text::CreateError> {
+        let mut column = Column::create(margin);
+
+        let list = ManeuverList::create(res, margin, game)?;
+        if let Some(list) = list {
+            column.add(list);
+            column.add(Button::create(
+                res,
+                "Cancel maneuvers".to_string(),
+                Action::CancelManeuvers,
+                [0.1, 0.0, 0.0, 0.95],
+                [0.5, 0.0, 0.0, 0.95],
+            )?);
+        }
+
+        let node = ManeuverNode::create(res, margin, game)?;
+        if let Some(node) = node {
+            column.add(node);
+        } else {
+            column.add(AddManeuver::create(res)?);
+        }
+
+        Ok(Self(column))
+    }
+}
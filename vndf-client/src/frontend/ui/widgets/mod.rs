@@ -1,9 +1,7 @@
-pub mod add_command;
+pub mod add_maneuver;
 pub mod button;
 pub mod canvas;
 pub mod column;
-pub mod commands;
-pub mod commands_list;
 pub mod component_stats;
 pub mod diagnostics;
 pub mod frame_time;
@@ -11,25 +9,31 @@ pub mod ftl_jump;
 pub mod ftl_time;
 pub mod input_events;
 pub mod instructions;
+pub mod maneuver_list;
+pub mod maneuver_node;
+pub mod maneuvers;
 pub mod network_stats;
 pub mod orbit_info;
 pub mod panel;
+pub mod respawn;
 pub mod scale_factor;
 pub mod ship_control;
 pub mod ship_info;
 pub mod ship_status;
 pub mod text;
 pub mod text_panel;
+pub mod time_warp;
 pub mod view_size;
 
 pub use self::{
-    add_command::AddCommand, button::Button, canvas::Canvas, column::Column,
-    commands::Commands, commands_list::CommandsList,
+    add_maneuver::AddManeuver, button::Button, canvas::Canvas, column::Column,
     component_stats::ComponentStats, diagnostics::Diagnostics,
     frame_time::FrameTime, ftl_jump::FtlJump, ftl_time::FtlTime,
     input_events::InputEvents, instructions::Instructions,
-    network_stats::NetworkStats, orbit_info::OrbitInfo, panel::Panel,
-    scale_factor::ScaleFactor, ship_control::ShipControl, ship_info::ShipInfo,
-    ship_status::ShipStatus, text::Text, text_panel::TextPanel,
+    maneuver_list::ManeuverList, maneuver_node::ManeuverNode,
+    maneuvers::Maneuvers, network_stats::NetworkStats, orbit_info::OrbitInfo,
+    panel::Panel, respawn::Respawn, scale_factor::ScaleFactor,
+    ship_control::ShipControl, ship_info::ShipInfo, ship_status::ShipStatus,
+    text::Text, text_panel::TextPanel, time_warp::TimeWarp,
     view_size::ViewSize,
 };
@@ -4,7 +4,10 @@ use crate::{
     frontend::{drawers::DrawResources, ui::widgets::Canvas},
     game::Game,
     graphics::{self, screen::Screen},
-    shared::world::{self, features::orbits::Orbit},
+    shared::world::{
+        self,
+        features::orbits::{self, Orbit},
+    },
 };
 
 use super::{text, TextPanel};
@@ -19,58 +22,81 @@ impl OrbitInfo {
         game: &Game,
         screen: &Screen,
     ) -> Result<Option<Self>, text::CreateError> {
-        if let Some((peri_text, peri_pos, apo_text, apo_pos)) =
-            Self::text_and_pos(orbit, game, screen)
-        {
-            let mut canvas = Canvas::create(0.0);
-
-            canvas.add_at(TextPanel::create(res, peri_text)?, peri_pos);
-            canvas.add_at(TextPanel::create(res, apo_text)?, apo_pos);
+        let panels = Self::text_and_pos(orbit, game, screen);
+        if panels.is_empty() {
+            return Ok(None);
+        }
 
-            return Ok(Some(Self(canvas)));
+        let mut canvas = Canvas::create(0.0);
+        for (text, pos) in panels {
+            canvas.add_at(TextPanel::create(res, text)?, pos);
         }
 
-        Ok(None)
+        Ok(Some(Self(canvas)))
     }
 
     fn text_and_pos(
         orbit: &Orbit,
         game: &Game,
         screen: &Screen,
-    ) -> Option<(String, graphics::Pnt2, String, graphics::Pnt2)> {
+    ) -> Vec<(String, graphics::Pnt2)> {
+        let mut panels = Vec::new();
+
         // If our orbit is nearly circular, the computed apses will jump around
         // like crazy. Let's make sure we have a minimum eccentricity, so they
         // become well-defined.
         if orbit.eccentricity.length() <= 0.01 {
-            return None;
+            return panels;
         }
 
         let periapsis_km = orbit.periapsis.distance / 1000.0;
-        let apoapsis_km = orbit.apoapsis.distance / 1000.0;
-
         let periapsis_above_surface_km = orbit.periapsis.from_surface / 1000.0;
-        let apoapsis_above_surface_km = orbit.apoapsis.from_surface / 1000.0;
 
-        let pericenter_text =
+        let mut pericenter_text =
             Self::text("Periapsis", periapsis_km, periapsis_above_surface_km);
-        let apocenter_text =
-            Self::text("Apoapsis", apoapsis_km, apoapsis_above_surface_km);
+
+        match &orbit.kind {
+            orbits::Kind::Ellipse { apoapsis, .. } => {
+                let apoapsis_km = apoapsis.distance / 1000.0;
+                let apoapsis_above_surface_km = apoapsis.from_surface / 1000.0;
+
+                let apocenter_text = Self::text(
+                    "Apoapsis",
+                    apoapsis_km,
+                    apoapsis_above_surface_km,
+                );
+                let apocenter_pos = game
+                    .state
+                    .camera
+                    .world_to_screen(&screen, apoapsis.position);
+
+                panels.push((apocenter_text, apocenter_pos));
+            }
+            orbits::Kind::Parabola => {
+                pericenter_text.push_str("\nescape (parabolic)");
+            }
+            orbits::Kind::Hyperbola {
+                asymptote_anomaly,
+                excess_velocity,
+                ..
+            } => {
+                pericenter_text.push_str(&format!(
+                    "\nescape (hyperbolic)\n\
+                    excess velocity: {:.0} m/s\n\
+                    asymptote: {:.1}°",
+                    excess_velocity,
+                    asymptote_anomaly.to_degrees(),
+                ));
+            }
+        }
 
         let pericenter_pos = game
             .state
             .camera
             .world_to_screen(&screen, orbit.periapsis.position);
-        let apocenter_pos = game
-            .state
-            .camera
-            .world_to_screen(&screen, orbit.apoapsis.position);
-
-        Some((
-            pericenter_text,
-            pericenter_pos,
-            apocenter_text,
-            apocenter_pos,
-        ))
+        panels.push((pericenter_text, pericenter_pos));
+
+        panels
     }
 
     fn text(
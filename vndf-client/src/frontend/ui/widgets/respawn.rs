@@ -0,0 +1,46 @@
+use vndf_macros::{DrawAt, ProcessInputAt, Size};
+
+use crate::{
+    frontend::{drawers::DrawResources, ui::input::Action},
+    game::Game,
+    graphics,
+};
+
+use super::{text, Button, Column, TextPanel};
+
+#[derive(DrawAt, ProcessInputAt, Size)]
+pub struct Respawn(Column);
+
+impl Respawn {
+    pub fn create(
+        res: &mut DrawResources,
+        margin: graphics::Scalar,
+        game: &Game,
+    ) -> Result<Option<Self>, text::CreateError> {
+        let respawn_at = match game.state.respawn_at {
+            Some(respawn_at) => respawn_at,
+            None => return Ok(None),
+        };
+        let respawn_in = respawn_at - game.state.local_time();
+
+        let mut column = Column::create(margin);
+
+        if respawn_in > 0.0 {
+            column.add(TextPanel::create(
+                res,
+                format!("Ship destroyed\nRespawn in {:.0}s", respawn_in.ceil()),
+            )?);
+        } else {
+            column.add(TextPanel::create(res, "Ship destroyed".to_string())?);
+            column.add(Button::create(
+                res,
+                "Respawn".to_string(),
+                Action::Respawn,
+                [0.1, 0.0, 0.0, 0.95],
+                [0.5, 0.0, 0.0, 0.95],
+            )?);
+        }
+
+        Ok(Some(Self(column)))
+    }
+}
@@ -4,9 +4,13 @@ use crate::{
     frontend::{drawers::DrawResources, ui::input::Action},
     game::Game,
     graphics,
+    shared::world,
 };
 
-use super::{text, Button, Column, Commands, FtlJump, FtlTime, ShipStatus};
+use super::{
+    text, Button, Column, FtlJump, FtlTime, Maneuvers, Respawn, ShipStatus,
+    TimeWarp,
+};
 
 #[derive(DrawAt, ProcessInputAt, Size)]
 pub struct ShipControl(Column);
@@ -17,9 +21,10 @@ impl ShipControl {
         margin: graphics::Scalar,
         game: &Game,
         jump_time_min: u32,
+        time_warp: world::Scalar,
     ) -> Result<Option<Self>, text::CreateError> {
         let ship_status = ShipStatus::create(res, game)?;
-        let commands = Commands::create(res, margin, game)?;
+        let maneuvers = Maneuvers::create(res, margin, game)?;
 
         let up = Button::create(
             res,
@@ -36,15 +41,35 @@ impl ShipControl {
             [0.5, 0.0, 0.0, 0.95],
         )?;
 
+        let warp_up = Button::create(
+            res,
+            "Warp +".to_string(),
+            Action::TimeWarpUp,
+            [0.1, 0.0, 0.0, 0.95],
+            [0.5, 0.0, 0.0, 0.95],
+        )?;
+        let warp_down = Button::create(
+            res,
+            "Warp -".to_string(),
+            Action::TimeWarpDown,
+            [0.1, 0.0, 0.0, 0.95],
+            [0.5, 0.0, 0.0, 0.95],
+        )?;
+
         let mut column = Column::create(margin);
 
         if let Some(ship_status) = ship_status {
             column.add(ship_status);
-            column.add(commands);
+            column.add(maneuvers);
             column.add(FtlTime::create(res, jump_time_min)?);
             column.add(up);
             column.add(down);
             column.add(FtlJump::create(res)?);
+            column.add(TimeWarp::create(res, game, time_warp)?);
+            column.add(warp_up);
+            column.add(warp_down);
+        } else if let Some(respawn) = Respawn::create(res, margin, game)? {
+            column.add(respawn);
         }
 
         Ok(Some(Self(column)))
@@ -4,7 +4,7 @@ use crate::{
     frontend::drawers::DrawResources,
     game::Game,
     graphics,
-    shared::world::features::{crafts::Fuel, health::Health},
+    shared::world::features::{crafts::Fuel, health::Health, ships::Ship},
 };
 
 use super::{text, TextPanel};
@@ -17,23 +17,24 @@ impl ShipStatus {
         res: &mut DrawResources,
         game: &Game,
     ) -> Result<Option<Self>, text::CreateError> {
-        fn components(game: &Game) -> Option<(&Fuel, &Health)> {
+        fn components(game: &Game) -> Option<(&Fuel, &Health, Ship)> {
             let ship = game.state.own_ship()?;
             let craft = game.state.data.crafts.get(&ship.craft)?;
             let fuel = game.state.data.fuels.get(&craft.fuel)?;
             let health = game.state.data.healths.get(&craft.health)?;
 
-            Some((fuel, health))
+            Some((fuel, health, ship))
         }
 
-        if let Some((fuel, health)) = components(game) {
+        if let Some((fuel, health, ship)) = components(game) {
             let text_panel = TextPanel::create(
                 res,
                 format!(
                     "Ship Status\n\
                     Structural Integrity: {:.2}\n\
-                    Fuel: {:.2}",
-                    health.value, fuel.0,
+                    Fuel: {:.2}\n\
+                    Missiles: {}",
+                    health.value, fuel.0, ship.missiles,
                 ),
             )?;
 
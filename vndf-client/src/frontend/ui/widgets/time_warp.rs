@@ -0,0 +1,28 @@
+use vndf_macros::{DrawAt, ProcessInputAt, Size};
+
+use crate::{
+    frontend::drawers::DrawResources, game::Game, graphics, shared::world,
+};
+
+use super::{text, TextPanel};
+
+#[derive(DrawAt, ProcessInputAt, Size)]
+pub struct TimeWarp(TextPanel);
+
+impl TimeWarp {
+    pub fn create(
+        res: &mut DrawResources,
+        game: &Game,
+        requested: world::Scalar,
+    ) -> Result<Self, text::CreateError> {
+        let text_panel = TextPanel::create(
+            res,
+            format!(
+                "Time Warp: {}x (requested: {}x)",
+                game.state.time_warp, requested,
+            ),
+        )?;
+
+        Ok(Self(text_panel))
+    }
+}
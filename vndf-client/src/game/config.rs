@@ -27,6 +27,24 @@ pub struct Input {
     pub thrust_on: Key,
     pub thrust_off: Key,
     pub quit: Key,
+
+    // Config files written before this was added don't have it.
+    #[serde(default = "Input::default_fire")]
+    pub fire: Key,
+
+    // Config files written before this was added don't have it.
+    #[serde(default = "Input::default_launch_missile")]
+    pub launch_missile: Key,
+}
+
+impl Input {
+    fn default_fire() -> Key {
+        Key::Keyboard(VirtualKeyCode::Space)
+    }
+
+    fn default_launch_missile() -> Key {
+        Key::Keyboard(VirtualKeyCode::M)
+    }
 }
 
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
@@ -68,6 +86,8 @@ impl Default for Config {
                 thrust_on: Key::Keyboard(VirtualKeyCode::W),
                 thrust_off: Key::Keyboard(VirtualKeyCode::S),
                 quit: Key::Keyboard(VirtualKeyCode::Escape),
+                fire: Input::default_fire(),
+                launch_missile: Input::default_launch_missile(),
             },
             color: Color {
                 r: 1.0,
@@ -6,7 +6,7 @@ use crate::{
     graphics,
     shared::{
         action::{self, Rotation},
-        world,
+        world::{self, features::crafts::Maneuver},
     },
 };
 
@@ -56,6 +56,12 @@ impl Handler {
                     k if k == self.config.input.thrust_off => {
                         events.push(action::Kind::Thrust(false))
                     }
+                    k if k == self.config.input.fire => {
+                        events.push(action::Kind::Fire(true))
+                    }
+                    k if k == self.config.input.launch_missile => {
+                        events.push(action::Kind::LaunchMissile)
+                    }
                     _ => (),
                 }
             }
@@ -66,6 +72,9 @@ impl Handler {
                 k if k == self.config.input.right => {
                     events.push(action::Kind::Rotate(Rotation::None))
                 }
+                k if k == self.config.input.fire => {
+                    events.push(action::Kind::Fire(false))
+                }
                 _ => (),
             },
             Input::MouseWheel(y) => {
@@ -74,6 +83,18 @@ impl Handler {
             Input::FtlJump(time) => {
                 events.push(action::Kind::FtlJump(time));
             }
+            Input::Maneuver(maneuver) => {
+                events.push(action::Kind::Maneuver(maneuver));
+            }
+            Input::CancelManeuvers => {
+                events.push(action::Kind::CancelManeuvers);
+            }
+            Input::TimeWarp(time_warp) => {
+                events.push(action::Kind::TimeWarp(time_warp));
+            }
+            Input::Respawn => {
+                events.push(action::Kind::Respawn);
+            }
         }
 
         Transition::None
@@ -92,6 +113,10 @@ pub enum Input {
     KeyUp(Key),
     MouseWheel(f32),
     FtlJump(world::Scalar),
+    Maneuver(Maneuver),
+    CancelManeuvers,
+    TimeWarp(world::Scalar),
+    Respawn,
 }
 
 #[must_use]
@@ -0,0 +1,140 @@
+use std::collections::{HashMap, VecDeque};
+
+use toadster::Handle;
+
+use crate::shared::{
+    data,
+    world::{
+        features::physics::{Body, Position, Velocity},
+        Pnt2, Scalar, Vec2, FRAME_TIME,
+    },
+};
+
+/// How far in the past remote entities are shown, in seconds
+///
+/// Needs to be larger than the interval the server sends snapshots at, so
+/// there usually is a snapshot on either side of the time that is shown.
+pub const DELAY: Scalar = 0.2;
+
+/// Interpolation between snapshots of remote entities
+///
+/// Instead of showing the latest state the server has sent, remote entities
+/// are shown a bit in the past, blending between the snapshots that surround
+/// that point in time. That way, corrections don't cause visible snapping.
+pub struct Interpolation {
+    positions: HashMap<Handle<Position>, History<Pnt2>>,
+    velocities: HashMap<Handle<Velocity>, History<Vec2>>,
+}
+
+impl Interpolation {
+    pub fn new() -> Self {
+        Self {
+            positions: HashMap::new(),
+            velocities: HashMap::new(),
+        }
+    }
+
+    /// Remember a component received from the server
+    pub fn record(&mut self, tick: u64, component: &data::client::Component) {
+        use data::client::Component;
+
+        match component {
+            Component::Position(handle, pos) => {
+                self.positions
+                    .entry(handle.clone())
+                    .or_insert_with(History::new)
+                    .push(tick, pos.0);
+            }
+            Component::Velocity(handle, vel) => {
+                self.velocities
+                    .entry(handle.clone())
+                    .or_insert_with(History::new)
+                    .push(tick, vel.0);
+            }
+            _ => {}
+        }
+    }
+
+    /// Move all remote entities to where they were a bit in the past
+    ///
+    /// `server_time` is the current time of the server, in seconds. `own` is
+    /// excluded, as it's predicted instead. Entities that have run out of
+    /// snapshots are left as they are, to be extrapolated.
+    pub fn apply(
+        &mut self,
+        data: &mut data::client::Components,
+        own: Option<&Body>,
+        server_time: Scalar,
+    ) -> Option<()> {
+        let tick = (server_time - DELAY) / FRAME_TIME;
+
+        self.positions
+            .retain(|handle, _| data.positions.get(handle).is_some());
+        self.velocities
+            .retain(|handle, _| data.velocities.get(handle).is_some());
+
+        for (handle, history) in &mut self.positions {
+            if Some(handle) == own.map(|body| &body.pos) {
+                continue;
+            }
+            if let Some(value) = history.at(tick, |a, b, t| a.lerp(b, t)) {
+                data.positions.get_mut(handle)?.0 = value;
+            }
+        }
+        for (handle, history) in &mut self.velocities {
+            if Some(handle) == own.map(|body| &body.vel) {
+                continue;
+            }
+            if let Some(value) = history.at(tick, |a, b, t| a.lerp(b, t)) {
+                data.velocities.get_mut(handle)?.0 = value;
+            }
+        }
+
+        Some(())
+    }
+}
+
+/// Snapshots of a single component, ordered by tick
+struct History<T>(VecDeque<(u64, T)>);
+
+impl<T: Copy> History<T> {
+    const MAX_LEN: usize = 32;
+
+    fn new() -> Self {
+        Self(VecDeque::new())
+    }
+
+    fn push(&mut self, tick: u64, value: T) {
+        // Messages can arrive out of order. Late ones are of no use.
+        if let Some(&(last, _)) = self.0.back() {
+            if tick <= last {
+                return;
+            }
+        }
+
+        self.0.push_back((tick, value));
+        if self.0.len() > Self::MAX_LEN {
+            self.0.pop_front();
+        }
+    }
+
+    /// Value at the given tick, if there are snapshots on either side of it
+    fn at(
+        &mut self,
+        tick: Scalar,
+        lerp: impl Fn(T, T, Scalar) -> T,
+    ) -> Option<T> {
+        // Snapshots before the one right before `tick` aren't needed anymore.
+        while self.0.len() > 2 && self.0[1].0 as Scalar <= tick {
+            self.0.pop_front();
+        }
+
+        let (a, b) = (self.0.front()?, self.0.get(1)?);
+        let (t_a, t_b) = (a.0 as Scalar, b.0 as Scalar);
+        if tick < t_a || tick > t_b {
+            return None;
+        }
+
+        Some(lerp(a.1, b.1, (tick - t_a) / (t_b - t_a)))
+    }
+}
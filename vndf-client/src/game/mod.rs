@@ -1,17 +1,22 @@
 pub mod camera;
 pub mod config;
 pub mod input;
+pub mod interpolation;
 pub mod net;
+pub mod prediction;
 pub mod state;
 
 pub use self::input::Input;
 
-use std::{io, net::ToSocketAddrs};
+use std::{io, net::ToSocketAddrs, thread};
 
-use log::{debug, error};
-use time::Duration;
+use log::{debug, error, info};
+use time::{Duration, Instant};
 
-use crate::shared::net::{client::Conn, msg, Error as NetError};
+use crate::shared::{
+    net::{client::Conn, delta::Decoder, msg, Error as NetError, Transport},
+    world::{self, features::players::IdentityToken},
+};
 
 use self::{
     config::Config, input::Transition, net::input::Events, state::State,
@@ -23,19 +28,45 @@ pub struct Game {
     pub events: Events,
     pub input: input::Handler,
     pub state: State,
+
+    decoder: Decoder,
+    last_ping: Option<Instant>,
+
+    /// Called with the token the server issues, so it can be stored
+    on_welcome: Box<dyn FnMut(IdentityToken)>,
 }
 
 impl Game {
-    pub fn init<A: ToSocketAddrs>(addr: A) -> Result<Self, Error> {
+    pub fn init<A: ToSocketAddrs>(
+        addr: A,
+        transport: Transport,
+        token: Option<IdentityToken>,
+        on_welcome: Box<dyn FnMut(IdentityToken)>,
+    ) -> Result<Self, Error> {
         let config = Config::load().map_err(|err| Error::Config(err))?;
-        let mut conn = Conn::connect(addr).map_err(|err| Error::Io(err))?;
+        let mut conn =
+            Conn::connect(addr, transport).map_err(|err| Error::Io(err))?;
         let events = Events::new();
         let input = input::Handler::new(config);
         let state = State::new();
 
         let color = [config.color.r, config.color.g, config.color.b];
-        conn.send(msg::FromClient::Hello { color })
-            .map_err(|err| Error::Net(err))?;
+        let hello = match token {
+            Some(token) => msg::FromClient::Reconnect {
+                version: msg::PROTOCOL_VERSION,
+                capabilities: msg::CAPABILITIES,
+                color,
+                token,
+            },
+            None => msg::FromClient::Hello {
+                version: msg::PROTOCOL_VERSION,
+                capabilities: msg::CAPABILITIES,
+                color,
+            },
+        };
+        conn.send(hello).map_err(|err| Error::Net(err))?;
+
+        handshake(&mut conn)?;
 
         Ok(Self {
             config,
@@ -43,6 +74,9 @@ impl Game {
             events,
             input,
             state,
+            decoder: Decoder::new(),
+            last_ping: None,
+            on_welcome,
         })
     }
 
@@ -50,6 +84,7 @@ impl Game {
         let trans = self.input.handle(input, &mut self.events);
 
         for event in self.events.unsent() {
+            self.state.predict(event.kind);
             self.conn
                 .send(msg::FromClient::Action(event))
                 .expect("Failed to send input event");
@@ -59,20 +94,67 @@ impl Game {
     }
 
     pub fn update(&mut self, dt: Duration) -> Result<(), ()> {
+        let ping_due = self
+            .last_ping
+            .map(|last_ping| last_ping.elapsed() >= PING_INTERVAL)
+            .unwrap_or(true);
+        if ping_due {
+            let sent = self.state.local_time();
+            if let Err(err) = self.conn.send(msg::FromClient::Ping { sent }) {
+                error!("Failed to send ping: {:?}", err);
+                return Err(());
+            }
+            self.last_ping = Some(Instant::now());
+        }
+
+        let mut acks = Vec::new();
+        let mut resyncs = Vec::new();
+
         for message in self.conn.incoming() {
             match message {
+                Ok(msg::FromServer::Accepted { .. }) => {
+                    // Already handled during the handshake.
+                }
+                Ok(msg::FromServer::Rejected(reason)) => {
+                    error!("Rejected by server: {}", reason);
+                    return Err(());
+                }
                 Ok(msg::FromServer::Ping) => {
                     // This message is just for testing purposes. Nothing to do
                     // here.
                 }
-                Ok(msg::FromServer::Welcome(id)) => {
+                Ok(msg::FromServer::Welcome { id, token }) => {
                     self.state.own_id = Some(id);
+                    (self.on_welcome)(token);
                 }
-                Ok(msg::FromServer::UpdateComponent(component)) => {
-                    debug!("Update component: {:?}", component);
-                    self.state.update_component(component);
+                Ok(msg::FromServer::Updates { tick, updates }) => {
+                    let mut failed = Vec::new();
+
+                    for update in updates {
+                        let handle = update.handle();
+                        match self.decoder.decode(tick, update) {
+                            Ok(component) => {
+                                debug!("Update component: {:?}", component);
+                                self.state.update_component(tick, component);
+                            }
+                            Err(err) => {
+                                error!("Failed to decode update: {:?}", err);
+                                self.decoder.remove(&handle);
+                                failed.push(handle);
+                            }
+                        }
+                    }
+
+                    // Acknowledging the batch would make the server use it as
+                    // a baseline, which only works if all of it arrived.
+                    if failed.is_empty() {
+                        acks.push(tick);
+                    } else {
+                        resyncs.extend(failed);
+                    }
                 }
                 Ok(msg::FromServer::RemoveComponent(handle)) => {
+                    self.decoder.remove(&handle);
                     self.state.remove_component(&handle);
                 }
                 Ok(msg::FromServer::InputHandled { seq }) => {
@@ -81,6 +163,18 @@ impl Game {
                 Ok(msg::FromServer::Diagnostics(diagnostics)) => {
                     self.state.diagnostics = Some(diagnostics);
                 }
+                Ok(msg::FromServer::TimeWarp(time_warp)) => {
+                    self.state.time_warp = time_warp;
+                }
+                Ok(msg::FromServer::Pong { sent, tick }) => {
+                    let received = self.state.local_time();
+                    let server = tick as world::Scalar * world::FRAME_TIME;
+                    self.state.clock.pong(sent, received, server);
+                }
+                Ok(msg::FromServer::ShipDestroyed { respawn_in }) => {
+                    self.state.respawn_at =
+                        Some(self.state.local_time() + respawn_in);
+                }
                 Err(err) => {
                     error!("Connection error: {:?}", err);
                     return Err(());
@@ -88,6 +182,20 @@ impl Game {
             }
         }
 
+        for tick in acks {
+            if let Err(err) = self.conn.send(msg::FromClient::Ack { tick }) {
+                error!("Failed to acknowledge updates: {:?}", err);
+                return Err(());
+            }
+        }
+        if !resyncs.is_empty() {
+            if let Err(err) = self.conn.send(msg::FromClient::Resync(resyncs)) {
+                error!("Failed to request full updates: {:?}", err);
+                return Err(());
+            }
+        }
+
+        self.state.reconcile(&self.events);
         self.state.frame_time.push(dt);
         self.events.limit();
 
@@ -97,9 +205,50 @@ impl Game {
     }
 }
 
+/// Waits for the server to accept or reject the client
+fn handshake(conn: &mut Conn) -> Result<(), Error> {
+    let start = Instant::now();
+
+    while start.elapsed() < HANDSHAKE_TIMEOUT {
+        for message in conn.incoming() {
+            match message.map_err(|err| Error::Net(err))? {
+                msg::FromServer::Accepted { capabilities } => {
+                    info!("Connected (capabilities: {:?})", capabilities);
+                    return Ok(());
+                }
+                msg::FromServer::Rejected(reason) => {
+                    return Err(Error::Rejected(reason));
+                }
+                message => {
+                    // Nothing important can arrive before the answer to the
+                    // handshake. Dropped updates haven't been acknowledged,
+                    // so they will be sent again.
+                    debug!("Ignoring message before handshake: {:?}", message);
+                }
+            }
+        }
+
+        thread::sleep(std::time::Duration::from_millis(10));
+    }
+
+    Err(Error::NoHandshake)
+}
+
+/// How often the client synchronizes with the server's clock
+const PING_INTERVAL: Duration = Duration::seconds(1);
+
+/// How long to wait for the server to answer the handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::seconds(5);
+
 #[derive(Debug)]
 pub enum Error {
     Config(config::Error),
     Io(io::Error),
     Net(NetError),
+
+    /// The server refused the connection
+    Rejected(msg::Rejection),
+
+    /// The server didn't answer the handshake in time
+    NoHandshake,
 }
@@ -45,6 +45,15 @@ impl Events {
         }
     }
 
+    /// Events that the server hasn't handled yet, in the order they were
+    /// entered
+    pub fn pending(&self) -> impl Iterator<Item = &Event> {
+        self.sent
+            .values()
+            .filter(|event| event.handled.is_none())
+            .chain(&self.unsent)
+    }
+
     pub fn unsent(&mut self) -> impl Iterator<Item = Action> + '_ {
         Unsent {
             inner: self.unsent.drain(..),
@@ -52,9 +61,17 @@ impl Events {
         }
     }
 
+    /// Forget about old events
+    ///
+    /// Events that haven't been handled yet are kept, as they are still needed
+    /// for prediction.
     pub fn limit(&mut self) {
         while self.unsent.len() + self.sent.len() > 10 {
-            let first = self.sent.keys().copied().next();
+            let first = self
+                .sent
+                .values()
+                .find(|event| event.handled.is_some())
+                .map(|event| event.inner.seq);
             if let Some(first) = first {
                 self.sent.remove(&first);
             } else {
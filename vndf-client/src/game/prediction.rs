@@ -0,0 +1,375 @@
+use time::OffsetDateTime;
+use toadster::store;
+
+use crate::{
+    game::net::input::Event,
+    shared::{
+        action, data,
+        world::{
+            self,
+            features::{
+                crafts::Craft,
+                physics::{Body, Position, Velocity},
+                planets::Planets,
+                players::PlayerId,
+                ships::Ship,
+            },
+        },
+    },
+};
+
+/// Client-side prediction for the player's own ship
+///
+/// The player's actions are applied locally right away, instead of waiting for
+/// the server to handle them. When authoritative state for the own ship
+/// arrives, it's rewound to the time it represents, and all actions that the
+/// server hasn't handled yet are replayed on top of it.
+pub struct Prediction {
+    // The latest authoritative state of the own ship
+    body: Option<Body>,
+    craft: Option<Craft>,
+    ship: Option<Ship>,
+    pos: Option<Position>,
+    vel: Option<Velocity>,
+
+    /// The tick that the latest position or velocity is from
+    tick: u64,
+
+    /// Whether authoritative state has arrived since the last reconciliation
+    changed: bool,
+
+    /// Whether that included position or velocity
+    moved: bool,
+}
+
+impl Prediction {
+    pub fn new() -> Self {
+        Self {
+            body: None,
+            craft: None,
+            ship: None,
+            pos: None,
+            vel: None,
+
+            tick: 0,
+
+            changed: false,
+            moved: false,
+        }
+    }
+
+    /// Remember a component received from the server, if it's part of the own
+    /// ship
+    ///
+    /// Needs to be called before the component is applied to `data`.
+    pub fn record(
+        &mut self,
+        tick: u64,
+        component: &data::client::Component,
+        data: &data::client::Components,
+        own_id: Option<PlayerId>,
+    ) {
+        use data::client::Component;
+
+        let own_craft = own_craft(data, own_id);
+        let own_body = own_craft.and_then(|craft| data.bodies.get(&craft.body));
+
+        match component {
+            Component::Body(handle, body)
+                if own_craft.map(|craft| &craft.body) == Some(handle) =>
+            {
+                self.body = Some(body.clone());
+            }
+            Component::Craft(handle, craft)
+                if own_ship(data, own_id).map(|ship| &ship.craft)
+                    == Some(handle) =>
+            {
+                self.craft = Some(craft.clone());
+            }
+            Component::Ship(_, ship) if is_own(ship, &data.crafts, own_id) => {
+                self.ship = Some(ship.clone());
+            }
+            Component::Position(handle, pos)
+                if own_body.map(|body| &body.pos) == Some(handle) =>
+            {
+                self.pos = Some(*pos);
+                self.tick = tick;
+                self.moved = true;
+            }
+            Component::Velocity(handle, vel)
+                if own_body.map(|body| &body.vel) == Some(handle) =>
+            {
+                self.vel = Some(*vel);
+                self.tick = tick;
+                self.moved = true;
+            }
+            _ => return,
+        }
+
+        self.changed = true;
+    }
+
+    /// Apply one of the player's own actions right away
+    pub fn predict(
+        &self,
+        kind: action::Kind,
+        data: &mut data::client::Components,
+        own_id: Option<PlayerId>,
+    ) -> Option<()> {
+        if !is_predicted(kind) {
+            return None;
+        }
+
+        let crafts = &data.crafts;
+        let ship = data
+            .ships
+            .values_mut()
+            .find(|ship| is_own(ship, crafts, own_id))?;
+        let craft = data.crafts.get_mut(&ship.craft)?;
+        let body = data.bodies.get_mut(&craft.body)?;
+
+        ship.apply_action(craft, body, kind);
+
+        Some(())
+    }
+
+    /// Rewind the own ship to its authoritative state, then replay the actions
+    /// that the server hasn't handled yet
+    ///
+    /// `server_time` is the current time of the server, in seconds. It's used
+    /// to determine how far in the past the authoritative state is. The time
+    /// warp that the server has granted applies to the replay too.
+    pub fn reconcile<'r>(
+        &mut self,
+        data: &mut data::client::Components,
+        own_id: Option<PlayerId>,
+        integrator: world::Method,
+        pending: impl IntoIterator<Item = &'r Event>,
+        server_time: Option<world::Scalar>,
+        time_warp: world::Scalar,
+    ) -> Option<()> {
+        if !self.changed {
+            return None;
+        }
+        let moved = self.moved;
+        self.changed = false;
+        self.moved = false;
+
+        let crafts = &data.crafts;
+        let ship = data
+            .ships
+            .values_mut()
+            .find(|ship| is_own(ship, crafts, own_id))?;
+        let craft = data.crafts.get_mut(&ship.craft)?;
+        let body = data.bodies.get_mut(&craft.body)?;
+
+        if let Some(authoritative) = &self.ship {
+            *ship = authoritative.clone();
+        }
+        if let Some(authoritative) = &self.craft {
+            *craft = authoritative.clone();
+        }
+        if let Some(authoritative) = &self.body {
+            // Without a new position, we can't rewind, so keep the predicted
+            // direction instead of snapping back to an outdated one.
+            let dir = body.dir;
+            *body = authoritative.clone();
+            if !moved {
+                body.dir = dir;
+            }
+        }
+
+        let mut pending = pending
+            .into_iter()
+            .filter(|event| is_predicted(event.inner.kind))
+            .peekable();
+
+        if moved {
+            if let Some(pos) = self.pos {
+                *data.positions.get_mut(&body.pos)? = pos;
+            }
+            if let Some(vel) = self.vel {
+                *data.velocities.get_mut(&body.vel)? = vel;
+            }
+
+            let craft_handle = ship.craft.clone();
+            let body_handle = craft.body.clone();
+
+            let now = OffsetDateTime::now_utc().time();
+
+            // Time relative to now, in seconds
+            let age = server_time
+                .map(|now| now - self.tick as world::Scalar * world::FRAME_TIME)
+                .unwrap_or(0.0);
+            let mut t = -age.clamp(0.0, MAX_REWIND);
+
+            while t < 0.0 {
+                let dt = world::Scalar::min(world::FRAME_TIME, -t);
+
+                while let Some(event) = pending.peek() {
+                    let entered = -(now - event.entered).as_seconds_f64();
+                    if entered > t + dt {
+                        break;
+                    }
+
+                    let craft = data.crafts.get_mut(&craft_handle)?;
+                    let body = data.bodies.get_mut(&body_handle)?;
+                    ship.apply_action(craft, body, event.inner.kind);
+
+                    pending.next();
+                }
+
+                // This mirrors the order of things in the simulation, which
+                // scales the time step by the time warp.
+                let warped = dt * time_warp;
+                let craft = data.crafts.get_mut(&craft_handle)?;
+                craft.apply_thrust(warped, &mut data.bodies, &mut data.fuels);
+                let body = data.bodies.get_mut(&body_handle)?;
+                body.update(
+                    warped,
+                    &Planets(&data.planets),
+                    integrator,
+                    &mut data.positions,
+                    &mut data.velocities,
+                );
+                ship.update(warped, &mut data.bodies, &data.crafts);
+
+                t += dt;
+            }
+        }
+
+        // Whatever is left was entered after the authoritative state, or there
+        // was nothing to rewind.
+        for event in pending {
+            let craft = data.crafts.get_mut(&ship.craft)?;
+            let body = data.bodies.get_mut(&craft.body)?;
+            ship.apply_action(craft, body, event.inner.kind);
+        }
+
+        Some(())
+    }
+}
+
+/// How far the own ship can be rewound, in seconds
+///
+/// Keeps the amount of work done per reconciliation bounded, if the connection
+/// is really bad.
+const MAX_REWIND: world::Scalar = 1.0;
+
+/// Only actions that can be applied repeatedly without changing the result are
+/// predicted. The others (like scheduling a maneuver) wait for the server.
+fn is_predicted(kind: action::Kind) -> bool {
+    matches!(kind, action::Kind::Rotate(_) | action::Kind::Thrust(_))
+}
+
+fn is_own(
+    ship: &Ship,
+    crafts: &impl store::Get<Craft>,
+    own_id: Option<PlayerId>,
+) -> bool {
+    match (crafts.get(&ship.craft), own_id) {
+        (Some(craft), Some(own_id)) => craft.owner == own_id,
+        _ => false,
+    }
+}
+
+fn own_ship(
+    data: &data::client::Components,
+    own_id: Option<PlayerId>,
+) -> Option<&Ship> {
+    data.ships
+        .values()
+        .find(|ship| is_own(ship, &data.crafts, own_id))
+}
+
+/// The craft of the own ship
+///
+/// Not just any craft the player owns. Their missiles are crafts too.
+fn own_craft(
+    data: &data::client::Components,
+    own_id: Option<PlayerId>,
+) -> Option<&Craft> {
+    own_ship(data, own_id).and_then(|ship| data.crafts.get(&ship.craft))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        game::net::input::Event,
+        shared::{
+            action,
+            data::client::{Component, Components},
+            sim::{trace::Tick, Simulation},
+            world::{self, features::players::PlayerId, Method, FRAME_TIME},
+        },
+    };
+
+    use super::{own_craft, Prediction};
+
+    #[test]
+    fn reconcile_should_replay_under_time_warp() {
+        const WARP: world::Scalar = 10.0;
+        const TICKS: u64 = 30;
+
+        let mut sim = Simulation::new(0);
+        sim.connect(1, [1.0, 1.0, 0.0]);
+        let id = sim.step().players_created[0].1;
+        sim.input(1, action::Kind::TimeWarp(WARP));
+        let authoritative = sim.step();
+        let tick = sim.state().tick();
+        assert_eq!(sim.state().time_warp(), WARP);
+
+        let mut data = Components::new();
+        let mut prediction = Prediction::new();
+        apply(&authoritative, &mut data, |_| true);
+        for component in &authoritative.updates {
+            prediction.record(tick, component, &data, Some(id));
+        }
+        let start = own_pos(&data, id);
+
+        let mut current = authoritative;
+        for _ in 0..TICKS {
+            current = sim.step();
+        }
+        let mut expected = Components::new();
+        apply(&current, &mut expected, |_| true);
+        let expected = own_pos(&expected, id);
+
+        // The client moves the planets along by itself.
+        apply(&current, &mut data, |component| {
+            matches!(component, Component::Planet(..))
+        });
+
+        let server_time = (tick + TICKS) as world::Scalar * FRAME_TIME;
+        prediction.reconcile(
+            &mut data,
+            Some(id),
+            Method::default(),
+            Vec::<Event>::new().iter(),
+            Some(server_time),
+            WARP,
+        );
+
+        let error = (own_pos(&data, id) - expected).length();
+        let distance = (expected - start).length();
+        assert!(error < distance * 0.01, "{} (of {})", error, distance);
+    }
+
+    fn apply(
+        tick: &Tick,
+        data: &mut Components,
+        filter: impl Fn(&Component) -> bool,
+    ) {
+        for component in &tick.updates {
+            if filter(component) {
+                component.clone().update(data);
+            }
+        }
+    }
+
+    fn own_pos(data: &Components, id: PlayerId) -> world::Pnt2 {
+        let craft = own_craft(data, Some(id)).unwrap();
+        let body = data.bodies.get(&craft.body).unwrap();
+        data.positions.get(&body.pos).unwrap().0
+    }
+}
@@ -3,21 +3,32 @@ use std::collections::VecDeque;
 use time::{Duration, Instant};
 
 use crate::{
-    game::{camera::Camera, input},
+    game::{
+        camera::Camera, input, interpolation::Interpolation,
+        net::input::Events, prediction::Prediction,
+    },
     shared::{
-        data,
+        action, data,
+        net::ClockSync,
         world::{
             self,
             features::{
+                crafts::Maneuver,
                 orbits::{Orbit, Orbiter},
-                planets::Planets,
+                physics::Body,
+                planets::{self, Planets},
                 players::PlayerId,
                 ships::Ship,
+                trajectory::Trajectory,
             },
         },
     },
 };
 
+// About one orbit at the altitude ships start at.
+const PATH_STEP: world::Scalar = 60.0;
+const PATH_STEPS: usize = 500;
+
 pub struct State {
     pub own_id: Option<PlayerId>,
     pub diagnostics: Option<data::server::Diagnostics>,
@@ -25,7 +36,29 @@ pub struct State {
     pub data: data::client::Components,
     pub frame_time: FrameTime,
     pub camera: Camera,
-    pub commands: Vec<String>,
+    pub maneuver: Option<ManeuverNode>,
+
+    /// The maneuver node, converted into a maneuver once per frame
+    planned: Option<PlannedManeuver>,
+
+    /// Should match the integrator that the server uses
+    pub integrator: world::Method,
+
+    /// The time warp that the server has granted
+    pub time_warp: world::Scalar,
+
+    /// The local time from which a new ship can be requested, if the own ship
+    /// was destroyed
+    pub respawn_at: Option<world::Scalar>,
+
+    pub prediction: Prediction,
+    pub interpolation: Interpolation,
+
+    /// Synchronizes with the server's clock
+    pub clock: ClockSync,
+
+    /// Reference point for the local time
+    start: Instant,
 }
 
 impl State {
@@ -37,7 +70,15 @@ impl State {
             data: data::client::Components::new(),
             frame_time: FrameTime::new(),
             camera: Camera::new(),
-            commands: Vec::new(),
+            maneuver: None,
+            planned: None,
+            integrator: world::Method::default(),
+            time_warp: 1.0,
+            respawn_at: None,
+            prediction: Prediction::new(),
+            interpolation: Interpolation::new(),
+            clock: ClockSync::new(),
+            start: Instant::now(),
         }
     }
 
@@ -46,10 +87,15 @@ impl State {
 
         self.camera.update(dt, self.own_pos(), input);
 
+        // Time warp applies to the world, but not to the camera.
+        let dt = dt * self.time_warp;
+
+        planets::update_planets(dt, &mut self.data.planets);
         for body in self.data.bodies.values_mut() {
             body.update(
                 dt,
                 &Planets(&self.data.planets),
+                self.integrator,
                 &mut self.data.positions,
                 &mut self.data.velocities,
             );
@@ -57,13 +103,43 @@ impl State {
         for craft in self.data.crafts.values_mut() {
             craft.apply_thrust(dt, &mut self.data.bodies, &mut self.data.fuels);
         }
+        for ship in self.data.ships.values_mut() {
+            ship.update(dt, &mut self.data.bodies, &self.data.crafts);
+        }
+
+        if let Some(server_time) = self.server_time() {
+            let own_body = self.own_body();
+            self.interpolation.apply(
+                &mut self.data,
+                own_body.as_ref(),
+                server_time,
+            );
+        }
         for explosion in self.data.explosions.values_mut() {
             explosion.update(dt);
         }
+
+        // The node stays at the same point of the orbit, so the time until it
+        // is reached goes down.
+        if let Some(node) = &mut self.maneuver {
+            node.delay -= dt;
+            if node.delay < 0.0 {
+                self.maneuver = None;
+            }
+        }
+
+        self.planned = self.plan_maneuver();
     }
 
-    pub fn update_component(&mut self, component: data::client::Component) {
+    pub fn update_component(
+        &mut self,
+        tick: u64,
+        component: data::client::Component,
+    ) {
         self.statistics.updates.push_back(Instant::now());
+        self.prediction
+            .record(tick, &component, &self.data, self.own_id);
+        self.interpolation.record(tick, &component);
         component.update(&mut self.data);
     }
 
@@ -72,6 +148,34 @@ impl State {
         handle.remove(&mut self.data);
     }
 
+    /// Apply one of the player's own actions, without waiting for the server
+    pub fn predict(&mut self, kind: action::Kind) {
+        self.prediction.predict(kind, &mut self.data, self.own_id);
+    }
+
+    /// Bring the own ship in line with what the server has sent
+    pub fn reconcile(&mut self, events: &Events) {
+        let server_time = self.server_time();
+        self.prediction.reconcile(
+            &mut self.data,
+            self.own_id,
+            self.integrator,
+            events.pending(),
+            server_time,
+            self.time_warp,
+        );
+    }
+
+    /// Seconds since the client started
+    pub fn local_time(&self) -> world::Scalar {
+        self.start.elapsed().as_seconds_f64()
+    }
+
+    /// The current time of the server, in seconds since its world started
+    pub fn server_time(&self) -> Option<world::Scalar> {
+        self.clock.server_time(self.local_time())
+    }
+
     pub fn own_ship(&self) -> Option<Ship> {
         for ship in self.data.ships.values() {
             let craft = self.data.crafts.get(&ship.craft)?;
@@ -84,6 +188,12 @@ impl State {
         None
     }
 
+    pub fn own_body(&self) -> Option<Body> {
+        let ship = self.own_ship()?;
+        let craft = self.data.crafts.get(&ship.craft)?;
+        self.data.bodies.get(&craft.body).cloned()
+    }
+
     pub fn own_pos(&self) -> Option<world::Pnt2> {
         self.own_ship().and_then(|ship| {
             let craft = self.data.crafts.get(&ship.craft)?;
@@ -95,7 +205,7 @@ impl State {
     }
 
     pub fn active_orbits(&self) -> impl IntoIterator<Item = Orbit> + '_ {
-        self.own_ship().and_then(move |ship| {
+        let current = self.own_ship().and_then(move |ship| {
             let craft = self.data.crafts.get(&ship.craft)?;
             let body = self.data.bodies.get(&craft.body)?;
             let pos = self.data.positions.get(&body.pos)?;
@@ -110,15 +220,137 @@ impl State {
             let orbit = Orbit::new(orbiter, &planets)?;
 
             Some(orbit)
-        })
+        });
+
+        // The orbit that results from the maneuver that is being planned
+        let planned = self.planned_maneuver().and_then(|planned| {
+            let orbiter = Orbiter {
+                pos: planned.pos,
+                vel: planned.vel + planned.maneuver.delta_v,
+            };
+            let planets = Planets(
+                Planets(&self.data.planets).after(planned.maneuver.delay),
+            );
+
+            Orbit::new(orbiter, &planets)
+        });
+
+        current.into_iter().chain(planned)
+    }
+
+    /// Predicts the path of the player's own ship
+    pub fn predicted_path(&self) -> Option<Trajectory> {
+        let ship = self.own_ship()?;
+        let craft = self.data.crafts.get(&ship.craft)?;
+        let body = self.data.bodies.get(&craft.body)?;
+        let pos = self.data.positions.get(&body.pos)?;
+        let vel = self.data.velocities.get(&body.vel)?;
+
+        let mut burns = craft.burns(body);
+        if let Some(planned) = self.planned_maneuver() {
+            burns.push(planned.maneuver.to_burn(craft.thrust / body.mass));
+        }
+
+        let trajectory = Trajectory::predict(
+            pos.0,
+            vel.0,
+            &burns,
+            &Planets(&self.data.planets),
+            self.integrator,
+            PATH_STEP,
+            PATH_STEPS,
+        );
+
+        Some(trajectory)
+    }
+
+    /// The maneuver that results from the maneuver node, as of the last update
+    pub fn planned_maneuver(&self) -> Option<&PlannedManeuver> {
+        self.planned.as_ref()
+    }
+
+    /// Converts the maneuver node into a maneuver the server can execute
+    ///
+    /// The node's delta-v is relative to the orbit around the planet that
+    /// dominates the point where the node is placed.
+    fn plan_maneuver(&self) -> Option<PlannedManeuver> {
+        const STEP: world::Scalar = 10.0;
+
+        let node = self.maneuver.as_ref()?;
+
+        let ship = self.own_ship()?;
+        let craft = self.data.crafts.get(&ship.craft)?;
+        let body = self.data.bodies.get(&craft.body)?;
+        let pos = self.data.positions.get(&body.pos)?;
+        let vel = self.data.velocities.get(&body.vel)?;
+
+        let planets = Planets(&self.data.planets);
+
+        let steps = (node.delay / STEP).ceil().max(1.0);
+        let trajectory = Trajectory::predict(
+            pos.0,
+            vel.0,
+            &craft.burns(body),
+            &planets,
+            self.integrator,
+            node.delay / steps,
+            steps as usize,
+        );
+
+        let pos = *trajectory.points.last()?;
+        let vel = trajectory.vel;
+
+        let planets = Planets(planets.after(node.delay));
+        let planet = planets.dominant_at(pos)?;
+
+        let prograde = (vel - planet.vel).normalize();
+        let radial = (pos - planet.pos).normalize();
+
+        let maneuver = Maneuver {
+            delay: node.delay,
+            delta_v: prograde * node.prograde + radial * node.radial,
+        };
+
+        Some(PlannedManeuver { pos, vel, maneuver })
     }
+}
+
+/// A maneuver that the player is planning, but hasn't executed yet
+pub struct ManeuverNode {
+    /// Time until the ship reaches the node, in seconds
+    pub delay: world::Scalar,
+
+    /// Delta-v along the direction of flight, in m/s
+    pub prograde: world::Scalar,
+
+    /// Delta-v away from the planet, in m/s
+    pub radial: world::Scalar,
+}
 
-    pub fn add_command(&mut self) {
-        self.commands
-            .push(format!("Command {}", self.commands.len() + 1));
+impl ManeuverNode {
+    /// The node can't be placed beyond the predicted path
+    pub const MAX_DELAY: world::Scalar =
+        PATH_STEP * PATH_STEPS as world::Scalar;
+
+    pub fn new(delay: world::Scalar) -> Self {
+        Self {
+            delay,
+            prograde: 0.0,
+            radial: 0.0,
+        }
     }
 }
 
+pub struct PlannedManeuver {
+    /// Predicted position of the ship at the node
+    pub pos: world::Pnt2,
+
+    /// Predicted velocity of the ship at the node, before the burn
+    pub vel: world::Vec2,
+
+    pub maneuver: Maneuver,
+}
+
 pub struct Statistics {
     pub updates: VecDeque<Instant>,
     pub removals: VecDeque<Instant>,
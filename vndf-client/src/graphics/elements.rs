@@ -9,8 +9,14 @@ use crate::{
     shared::world::{
         self,
         features::{
-            crafts::Craft, explosions::Explosion, orbits::Orbit,
-            physics::Position, planets::Planet, ships::Ship,
+            crafts::Craft,
+            explosions::Explosion,
+            missiles::Missile,
+            orbits::{self, Orbit},
+            physics::Position,
+            planets::Planet,
+            ships::Ship,
+            weapons::Projectile,
         },
     },
 };
@@ -44,6 +50,16 @@ impl ScreenElement {
         Some(Self::from_pos(pos, body.dir, size, game, screen))
     }
 
+    pub fn from_missile(
+        missile: &Missile,
+        game: &Game,
+        screen: &Screen,
+    ) -> Option<Self> {
+        let craft = game.state.data.crafts.get(&missile.craft)?;
+
+        Self::from_craft(craft, graphics::Size::new(12.0, 12.0), game, screen)
+    }
+
     pub fn from_explosion(
         explosion: &Explosion,
         game: &Game,
@@ -63,6 +79,23 @@ impl ScreenElement {
         ))
     }
 
+    pub fn from_projectile(
+        projectile: &Projectile,
+        game: &Game,
+        screen: &Screen,
+    ) -> Option<Self> {
+        let body = game.state.data.bodies.get(&projectile.body)?;
+        let pos = game.state.data.positions.get(&body.pos)?;
+
+        Some(Self::from_pos(
+            pos,
+            body.dir,
+            graphics::Size::new(6.0, 2.0),
+            game,
+            screen,
+        ))
+    }
+
     pub fn from_pos(
         pos: &Position,
         dir: world::Vec2,
@@ -118,16 +151,29 @@ impl WorldElement {
     }
 }
 
-impl From<&Orbit> for WorldElement {
-    fn from(orbit: &Orbit) -> Self {
-        let size = world::Size::from_lengths(
-            orbit.semi_major_axis * 2.0,
-            orbit.semi_minor_axis * 2.0,
-        );
-        let pos = orbit.ellipse_pos;
-        let angle = orbit.arg_of_periapsis;
-
-        Self { size, pos, angle }
+impl WorldElement {
+    /// Returns the element that an elliptical orbit is drawn into
+    ///
+    /// Returns `None`, if the orbit is not elliptical.
+    pub fn from_ellipse(orbit: &Orbit) -> Option<Self> {
+        match orbit.kind {
+            orbits::Kind::Ellipse {
+                semi_major_axis,
+                semi_minor_axis,
+                ellipse_pos,
+                ..
+            } => {
+                let size = world::Size::from_lengths(
+                    semi_major_axis * 2.0,
+                    semi_minor_axis * 2.0,
+                );
+                let pos = ellipse_pos;
+                let angle = orbit.arg_of_periapsis;
+
+                Some(Self { size, pos, angle })
+            }
+            orbits::Kind::Parabola | orbits::Kind::Hyperbola { .. } => None,
+        }
     }
 }
 
@@ -6,13 +6,30 @@ extern crate vndf_shared as shared;
 
 use std::{net::ToSocketAddrs, str::FromStr};
 
+pub use crate::shared::{
+    net::{msg::Rejection, Transport},
+    world::features::players::IdentityToken,
+};
+
 use crate::game::Game;
 
+/// Connect to the server and run the game
+///
+/// If a token is passed, the client takes over the player it was issued for.
+/// `on_welcome` is called with the token the server issues, which can be
+/// passed here the next time, to reconnect to the same player.
 pub fn start<A: ToSocketAddrs>(
     addr: A,
+    transport: Transport,
     graphics: Graphics,
+    token: Option<IdentityToken>,
+    on_welcome: impl FnMut(IdentityToken) + 'static,
 ) -> Result<(), Error> {
-    let game = Game::init(addr).map_err(Error::Game)?;
+    let game = Game::init(addr, transport, token, Box::new(on_welcome))
+        .map_err(|err| match err {
+            game::Error::Rejected(reason) => Error::Rejected(reason),
+            err => Error::Game(err),
+        })?;
 
     frontend::start(game, graphics).map_err(Error::Frontend)
 }
@@ -49,4 +66,7 @@ impl FromStr for Graphics {
 pub enum Error {
     Frontend(frontend::Error),
     Game(game::Error),
+
+    /// The server refused the connection
+    Rejected(Rejection),
 }
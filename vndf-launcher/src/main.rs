@@ -1,7 +1,18 @@
+use std::{
+    fs,
+    io::{self, prelude::*},
+    process,
+};
+
 use structopt::StructOpt;
 
 use vndf_client as client;
 
+/// Where the identity tokens issued by servers are stored
+///
+/// One line per server, with its host name and the token.
+const TOKEN_FILE: &str = "vndf-tokens.txt";
+
 #[derive(StructOpt)]
 struct Options {
     #[structopt(short, long, default_value = "auto")]
@@ -9,6 +20,10 @@ struct Options {
 
     #[structopt(short, long)]
     local: bool,
+
+    /// Transport to connect over (tcp or udp)
+    #[structopt(long, default_value = "tcp")]
+    transport: client::Transport,
 }
 
 fn main() -> Result<(), client::Error> {
@@ -24,5 +39,63 @@ fn main() -> Result<(), client::Error> {
         "reineke.hannobraun.de"
     };
 
-    client::start((addr, 34480), options.graphics)
+    let token = load_token(addr).unwrap_or_else(|err| {
+        eprintln!("Failed to load identity token: {}", err);
+        None
+    });
+
+    let result = client::start(
+        (addr, 34480),
+        options.transport,
+        options.graphics,
+        token,
+        move |token| {
+            if let Err(err) = save_token(addr, token) {
+                eprintln!("Failed to save identity token: {}", err);
+            }
+        },
+    );
+    if let Err(client::Error::Rejected(reason)) = &result {
+        eprintln!("The server refused the connection: {}", reason);
+        process::exit(1);
+    }
+
+    result
+}
+
+fn load_token(host: &str) -> io::Result<Option<client::IdentityToken>> {
+    let tokens = match fs::read_to_string(TOKEN_FILE) {
+        Ok(tokens) => tokens,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    };
+
+    let token = tokens
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            Some((fields.next()?, fields.next()?))
+        })
+        .find(|&(h, _)| h == host)
+        .and_then(|(_, token)| token.parse().ok());
+
+    Ok(token)
+}
+
+fn save_token(host: &str, token: client::IdentityToken) -> io::Result<()> {
+    let tokens = match fs::read_to_string(TOKEN_FILE) {
+        Ok(tokens) => tokens,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err),
+    };
+
+    let mut file = fs::File::create(TOKEN_FILE)?;
+    for line in tokens.lines() {
+        if line.split_whitespace().next() != Some(host) {
+            writeln!(file, "{}", line)?;
+        }
+    }
+    writeln!(file, "{} {}", host, token)?;
+
+    Ok(())
 }
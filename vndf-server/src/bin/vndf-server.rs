@@ -1,5 +1,70 @@
-use vndf_server::server::Server;
-use vndf_shared::{main_loop::main_loop, net};
+use std::{path::PathBuf, time::Duration};
+
+use log::info;
+use structopt::StructOpt;
+
+use vndf_server::{recording::Recording, server::Server};
+use vndf_shared::{
+    main_loop::main_loop,
+    net::{self, Transport},
+    world::{features::planets::System, Method},
+};
+
+#[derive(StructOpt)]
+struct Options {
+    /// Seed for the world's random number generator (random, if not given)
+    #[structopt(long)]
+    seed: Option<u64>,
+
+    /// Create the world from this star system definition, instead of the
+    /// default one
+    #[structopt(long)]
+    system: Option<PathBuf>,
+
+    /// Integrator used to move bodies (semi-implicit-euler, velocity-verlet,
+    /// yoshida4, yoshida6, or pefrl)
+    #[structopt(long, default_value = "velocity-verlet")]
+    integrator: Method,
+
+    /// How long the ship of a disconnected player stays around, in seconds.
+    /// Within that time, the player can reconnect and take it over again.
+    #[structopt(long, default_value = "60")]
+    disconnect_timeout: f64,
+
+    /// How far players can see, in meters. Clients are only told about crafts
+    /// within that range of their own.
+    #[structopt(long, default_value = "100000000")]
+    interest_range: f64,
+
+    /// Transport clients connect over (tcp or udp)
+    #[structopt(long, default_value = "tcp")]
+    transport: Transport,
+
+    /// Largest message that is accepted from clients, in bytes. Clients that
+    /// send larger messages are disconnected.
+    #[structopt(long, default_value = "1048576")]
+    max_message_size: usize,
+
+    /// Record the session to the given file
+    #[structopt(long)]
+    record: Option<PathBuf>,
+
+    /// Restore the world from this snapshot (if it exists) and save it there
+    #[structopt(long)]
+    snapshot: Option<PathBuf>,
+
+    /// Interval between saving snapshots, in seconds
+    #[structopt(long, default_value = "60")]
+    autosave_interval: u64,
+
+    /// Replay a recorded session headlessly, instead of starting a server
+    #[structopt(long)]
+    replay: Option<PathBuf>,
+
+    /// Number of ticks to simulate after the last recorded event
+    #[structopt(long, default_value = "0")]
+    replay_ticks: u64,
+}
 
 fn main() -> net::Result {
     env_logger::init_from_env(
@@ -7,6 +72,76 @@ fn main() -> net::Result {
             .default_filter_or("vndf_shared=info,vndf_server=info"),
     );
 
-    let mut server = Server::start_default()?;
+    let options = Options::from_args();
+
+    if let Some(path) = options.replay {
+        let recording =
+            Recording::load(&path).expect("Failed to load recording");
+        info!(
+            "Replaying {} (seed: {})",
+            path.display(),
+            recording.header.seed
+        );
+
+        let trace = recording
+            .replay(options.replay_ticks)
+            .expect("Failed to replay recording");
+        for tick in &trace.ticks {
+            for (player, id) in &tick.players_created {
+                info!(
+                    "Tick {}: Player {} created ({:?})",
+                    tick.tick, player, id
+                );
+            }
+            for (player, id) in &tick.players_reconnected {
+                info!(
+                    "Tick {}: Player {} reconnected ({:?})",
+                    tick.tick, player, id
+                );
+            }
+            for (player, id) in &tick.ships_destroyed {
+                info!(
+                    "Tick {}: Ship of player {} destroyed ({:?})",
+                    tick.tick, player, id
+                );
+            }
+            for (player, seq) in &tick.inputs_handled {
+                info!(
+                    "Tick {}: Input {} of {} handled",
+                    tick.tick, seq, player
+                );
+            }
+        }
+        info!("Replayed {} ticks", trace.ticks.len());
+
+        return Ok(());
+    }
+
+    let seed = options.seed.unwrap_or_else(rand::random);
+    info!("Seed: {}", seed);
+
+    let mut server = Server::start_default(seed, options.transport)?;
+    server.set_integrator(options.integrator);
+    server.set_disconnect_timeout(options.disconnect_timeout);
+    server.set_interest_range(options.interest_range);
+    server.set_max_message_size(options.max_message_size);
+    if let Some(path) = options.system {
+        let system = System::load(&path).expect("Failed to load system");
+        server.load_system(system);
+    }
+    if let Some(path) = options.snapshot {
+        if path.exists() {
+            server.restore(&path).expect("Failed to restore snapshot");
+            info!("Restored snapshot from {}", path.display());
+        }
+
+        let interval = Duration::from_secs(options.autosave_interval);
+        server.autosave(path, interval);
+    }
+    if let Some(path) = options.record {
+        server.record(&path).expect("Failed to start recording");
+        info!("Recording to {}", path.display());
+    }
+
     main_loop(|| server.update());
 }
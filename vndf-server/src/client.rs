@@ -1,58 +1,171 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     time::{Duration, Instant},
 };
 
-use vndf_shared::data;
+use vndf_shared::{
+    data::{self, diff},
+    net::{
+        delta::{self, Encoder},
+        msg::Capabilities,
+    },
+    world::Scalar,
+};
+
+/// How often position and velocity are sent to the client
+pub const SNAPSHOT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long to wait for an acknowledgement, before sending a component again
+///
+/// Batches of updates can get lost, depending on the transport.
+pub const RESEND_INTERVAL: Duration = Duration::from_millis(250);
 
 pub struct Client {
+    capabilities: Capabilities,
     data: data::client::Components,
     updates: HashMap<data::client::Handle, Instant>,
+    time_warp: Option<Scalar>,
+
+    /// The components the client needs to know about
+    interest: HashSet<data::client::Handle>,
+
+    /// Updates waiting to be sent with the next batch
+    batch: Vec<data::client::Component>,
+    encoder: Encoder,
 }
 
 impl Client {
-    pub fn new() -> Self {
+    pub fn new(capabilities: Capabilities) -> Self {
         Self {
+            capabilities,
             data: data::client::Components::new(),
             updates: HashMap::new(),
+            time_warp: None,
+            interest: HashSet::new(),
+            batch: Vec::new(),
+            encoder: Encoder::new(),
+        }
+    }
+
+    /// Returns whether the client needs to be told about the time warp
+    pub fn update_time_warp(&mut self, time_warp: Scalar) -> bool {
+        let changed = self.time_warp != Some(time_warp);
+        self.time_warp = Some(time_warp);
+        changed
+    }
+
+    /// Replace the set of components the client needs to know about
+    ///
+    /// Returns the components that are no longer of interest, which the client
+    /// needs to be told to remove. Components that become of interest are sent
+    /// in full with the next batch.
+    pub fn set_interest(
+        &mut self,
+        interest: HashSet<data::client::Handle>,
+    ) -> Vec<data::client::Handle> {
+        let lost: Vec<_> =
+            self.interest.difference(&interest).cloned().collect();
+        for handle in &lost {
+            self.remove(handle);
         }
+
+        self.interest = interest;
+
+        lost
     }
 
-    pub fn remove(&mut self, handle: &data::client::Handle) {
+    /// Forget about a component
+    ///
+    /// Returns whether the client knew about it, and needs to be told to remove
+    /// it.
+    pub fn remove(&mut self, handle: &data::client::Handle) -> bool {
         self.updates.remove(handle);
+        self.batch.retain(|component| {
+            &data::client::Handle::from_component(component) != handle
+        });
+        self.encoder.remove(handle);
         handle.remove(&mut self.data);
+
+        self.interest.remove(handle)
     }
 
-    pub fn update(&mut self, component: data::client::Component) -> bool {
-        let handle = data::client::Handle::from_component(&component);
+    /// Encode the updates that are waiting to be sent, if there are any
+    pub fn flush(
+        &mut self,
+        tick: u64,
+    ) -> Result<Option<Vec<delta::Update>>, diff::Error> {
+        if self.batch.is_empty() {
+            return Ok(None);
+        }
+
+        let batch: Vec<_> = self.batch.drain(..).collect();
 
-        let recently_updated = self
-            .updates
-            .get(&handle)
-            .map(|last_update| last_update.elapsed() < Duration::from_secs(1))
-            .unwrap_or(false);
+        if !self.capabilities.contains(Capabilities::DELTA_UPDATES) {
+            // Still needs to go through the encoder, to keep track of what the
+            // client has received.
+            self.encoder.encode(tick, batch.clone())?;
+            let updates = batch.into_iter().map(delta::Update::Full).collect();
+            return Ok(Some(updates));
+        }
+
+        self.encoder.encode(tick, batch).map(Some)
+    }
+
+    /// The client has received the batch from the given tick
+    pub fn ack(&mut self, tick: u64) {
+        self.encoder.ack(tick);
+    }
+
+    /// The client couldn't decode updates for this component
+    ///
+    /// Makes sure the full component is sent with the next batch.
+    pub fn resync(&mut self, handle: &data::client::Handle) {
+        if !self.interest.contains(handle) {
+            return;
+        }
+
+        self.updates.remove(handle);
+        self.encoder.remove(handle);
+        handle.remove(&mut self.data);
+    }
+
+    /// Queue a component for the next batch, if the client needs to know
+    pub fn update(&mut self, component: data::client::Component) {
+        let handle = data::client::Handle::from_component(&component);
+        if !self.interest.contains(&handle) {
+            return;
+        }
 
         use data::client::Component::*;
-        let is_interpolated = match component {
-            // These components are interpolated client-side.
-            Position(_, _) | Velocity(_, _) | Explosion(_, _) | Fuel(_, _) => {
-                true
-            }
-            _ => false,
+        let interval = match component {
+            // The client interpolates between these, so it needs them often
+            // enough to have a few at hand.
+            Position(_, _) | Velocity(_, _) => Some(SNAPSHOT_INTERVAL),
+
+            // These are extrapolated client-side.
+            Explosion(_, _) | Fuel(_, _) => Some(Duration::from_secs(1)),
+
+            _ => None,
         };
 
-        let data_changed = component.update(&mut self.data);
+        let data_changed = component.clone().update(&mut self.data);
 
-        let should_update = if is_interpolated {
-            data_changed && !recently_updated
-        } else {
-            data_changed
+        let should_update = match self.updates.get(&handle) {
+            Some(last_update) if data_changed => interval
+                .map(|interval| last_update.elapsed() >= interval)
+                .unwrap_or(true),
+            Some(last_update) => {
+                let interval =
+                    interval.unwrap_or(RESEND_INTERVAL).max(RESEND_INTERVAL);
+                last_update.elapsed() >= interval
+                    && !self.encoder.is_acked(&component)
+            }
+            None => true,
         };
 
         if should_update {
             self.updates.insert(handle, Instant::now());
+            self.batch.push(component);
         }
-
-        should_update
     }
 }
@@ -1,5 +1,6 @@
 pub mod client;
 pub mod net;
+pub mod recording;
 pub mod server;
 
 use vndf_shared as shared;
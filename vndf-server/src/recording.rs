@@ -0,0 +1,250 @@
+use std::{
+    fs::File,
+    io::{self, prelude::*, BufWriter},
+    net::SocketAddr,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    net,
+    server::handle_event,
+    shared::{
+        net::msg::{self, Message as _},
+        sim::{Simulation, Trace},
+        world::{
+            self,
+            features::{
+                planets::{system, System},
+                players::IdentityToken,
+            },
+            snapshot, Method, Scalar, Snapshot,
+        },
+    },
+};
+
+/// Writes everything the server receives from its clients to a file
+///
+/// Together with the seed, this is all that is needed to reproduce a session
+/// without any network. See `Recording`.
+pub struct Recorder {
+    file: BufWriter<File>,
+    buf: Vec<u8>,
+}
+
+impl Recorder {
+    pub fn create(
+        path: impl AsRef<Path>,
+        header: Header,
+    ) -> Result<Self, Error> {
+        let mut recorder = Self {
+            file: BufWriter::new(File::create(path)?),
+            buf: Vec::new(),
+        };
+
+        header.write(&mut recorder.buf)?;
+        recorder.write()?;
+
+        Ok(recorder)
+    }
+
+    /// Record an event that is handled in the update with the given number
+    pub fn record(&mut self, tick: u64, event: &Event) -> Result<(), Error> {
+        let record = Record {
+            tick,
+            event: event.clone(),
+        };
+        record.write(&mut self.buf)?;
+
+        self.write()
+    }
+
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.file.flush()?;
+        Ok(())
+    }
+
+    fn write(&mut self) -> Result<(), Error> {
+        self.file.write_all(&self.buf)?;
+        self.buf.clear();
+
+        Ok(())
+    }
+}
+
+/// A session, as written by `Recorder`
+pub struct Recording {
+    pub header: Header,
+    pub records: Vec<Record>,
+}
+
+impl Recording {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let mut buf = Vec::new();
+        File::open(path)?.read_to_end(&mut buf)?;
+
+        // The whole file is in memory already, so there's no point in limiting
+        // the size of what we read from it.
+        let max_size = buf.len();
+
+        let (header, mut offset) =
+            Header::decode(&buf, max_size)?.ok_or(Error::NoHeader)?;
+        header.system.validate()?;
+
+        // If the server crashed, the last record might be incomplete. `decode`
+        // returns `None` in that case, so we just ignore it.
+        let mut records = Vec::new();
+        while let Some((record, size)) =
+            Record::decode(&buf[offset..], max_size)?
+        {
+            records.push(record);
+            offset += size;
+        }
+
+        Ok(Self { header, records })
+    }
+
+    /// Feeds the recorded events back into the world the session started with
+    ///
+    /// That's a fresh world, unless the session started from a snapshot. The
+    /// simulation runs until the last recorded event has been handled, plus
+    /// the given number of additional ticks.
+    pub fn replay(&self, extra_ticks: u64) -> Result<Trace, Error> {
+        let mut state = match &self.header.snapshot {
+            Some(bytes) => world::State::restore(
+                Snapshot::from_bytes(bytes)?,
+                self.header.seed,
+            )?,
+            None => {
+                world::State::from_system(&self.header.system, self.header.seed)
+            }
+        };
+        state.set_integrator(self.header.integrator);
+        state.set_disconnect_timeout(self.header.disconnect_timeout);
+
+        let mut sim = Simulation::from_state(state);
+        let mut trace = Trace::new();
+
+        // A restored world doesn't start at the first tick.
+        let start = sim.state().tick();
+        let last_tick = self.records.last().map(|record| record.tick);
+        let ticks =
+            last_tick.map(|tick| tick + 1).unwrap_or(start) + extra_ticks;
+
+        let mut records = self.records.iter().peekable();
+
+        while sim.state().tick() < ticks {
+            let tick = sim.state().tick();
+
+            while let Some(record) = records.next_if(|r| r.tick == tick) {
+                handle_event(sim.state(), &record.event);
+            }
+
+            trace.ticks.push(sim.step());
+        }
+
+        Ok(trace)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Header {
+    pub seed: u64,
+    pub system: System,
+    pub integrator: Method,
+    pub disconnect_timeout: Scalar,
+
+    /// The snapshot the session started from, as it was stored in its file
+    pub snapshot: Option<Vec<u8>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Record {
+    /// The number of the update that the event was handled in
+    pub tick: u64,
+
+    pub event: Event,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub enum Event {
+    Message(SocketAddr, msg::FromClient),
+
+    /// A `Hello` or `Reconnect`
+    ///
+    /// Includes the token the server issues, if a new player is created. It
+    /// is random, so it needs to be recorded for replays.
+    Connected {
+        addr: SocketAddr,
+        color: [f32; 3],
+        token: Option<IdentityToken>,
+        new_token: IdentityToken,
+    },
+
+    Disconnected(SocketAddr),
+}
+
+/// Generates the token for new players, for `Hello` and `Reconnect` messages
+impl From<net::Event> for Event {
+    fn from(event: net::Event) -> Self {
+        match event {
+            net::Event::Message(addr, msg::FromClient::Hello { color, .. }) => {
+                Self::Connected {
+                    addr,
+                    color,
+                    token: None,
+                    new_token: IdentityToken::generate(),
+                }
+            }
+            net::Event::Message(
+                addr,
+                msg::FromClient::Reconnect { color, token, .. },
+            ) => Self::Connected {
+                addr,
+                color,
+                token: Some(token),
+                new_token: IdentityToken::generate(),
+            },
+            net::Event::Message(addr, message) => Self::Message(addr, message),
+            net::Event::Error(addr, _) => Self::Disconnected(addr),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Msg(msg::Error),
+    NoHeader,
+
+    /// The recorded snapshot can't be restored
+    Snapshot(snapshot::Error),
+
+    /// The recorded system is invalid
+    System(system::Error),
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<msg::Error> for Error {
+    fn from(err: msg::Error) -> Self {
+        Self::Msg(err)
+    }
+}
+
+impl From<system::Error> for Error {
+    fn from(err: system::Error) -> Self {
+        Self::System(err)
+    }
+}
+
+impl From<snapshot::Error> for Error {
+    fn from(err: snapshot::Error) -> Self {
+        Self::Snapshot(err)
+    }
+}
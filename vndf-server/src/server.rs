@@ -1,49 +1,75 @@
 use std::{
     collections::HashMap,
+    fs,
     net::SocketAddr,
+    path::{Path, PathBuf},
     time::{Duration, Instant},
 };
 
-use log::{debug, info};
+use log::{debug, error, info};
 
 use crate::{
     client::Client,
-    net::{Event, Network},
+    net::Network,
+    recording::{self, Event, Header, Recorder},
     shared::{
-        net::{self, msg},
+        net::{self, msg, Transport},
         world::{
             self,
             features::{
                 base::Update,
-                players::{PlayerConnected, PlayerDisconnected, PlayerInput},
+                interest,
+                planets::System,
+                players::{
+                    Player, PlayerConnected, PlayerDisconnected, PlayerInput,
+                },
             },
-            FRAME_TIME,
+            snapshot, Method, Scalar, Snapshot, FRAME_TIME,
         },
     },
 };
 
 pub struct Server {
     network: Network,
-    events: Vec<Event>,
+    events: Vec<crate::net::Event>,
+    seed: u64,
+    system: System,
+    integrator: Method,
+    disconnect_timeout: Scalar,
+    interest_range: Scalar,
     state: world::State,
+
+    /// The snapshot the world was restored from, as stored in the file
+    restored: Option<Vec<u8>>,
+
+    recorder: Option<Recorder>,
+    autosave: Option<Autosave>,
     last_update: Instant,
     clients: HashMap<SocketAddr, Client>,
 }
 
 impl Server {
-    pub fn start_default() -> net::Result<Self> {
-        Ok(Self::new(Network::start_default()?))
+    pub fn start_default(seed: u64, transport: Transport) -> net::Result<Self> {
+        Ok(Self::new(Network::start_default(transport)?, seed))
     }
 
-    pub fn start_local() -> net::Result<Self> {
-        Ok(Self::new(Network::start_local()?))
+    pub fn start_local(seed: u64, transport: Transport) -> net::Result<Self> {
+        Ok(Self::new(Network::start_local(transport)?, seed))
     }
 
-    fn new(network: Network) -> Self {
+    fn new(network: Network, seed: u64) -> Self {
         Self {
             network,
             events: Vec::new(),
-            state: world::State::new(),
+            seed,
+            system: System::default(),
+            integrator: Method::default(),
+            disconnect_timeout: Player::DEFAULT_DISCONNECT_TIMEOUT,
+            interest_range: interest::DEFAULT_RANGE,
+            state: world::State::new(seed),
+            restored: None,
+            recorder: None,
+            autosave: None,
             last_update: Instant::now(),
             clients: HashMap::new(),
         }
@@ -53,43 +79,177 @@ impl Server {
         self.network.addr()
     }
 
+    /// Replace the world with a fresh one, created from the given system
+    ///
+    /// This should be called before the first update.
+    pub fn load_system(&mut self, system: System) {
+        self.state = world::State::from_system(&system, self.seed);
+        self.state.set_integrator(self.integrator);
+        self.state.set_disconnect_timeout(self.disconnect_timeout);
+        self.system = system;
+    }
+
+    /// Select the integrator that is used to move bodies
+    pub fn set_integrator(&mut self, integrator: Method) {
+        self.state.set_integrator(integrator);
+        self.integrator = integrator;
+    }
+
+    /// Set how long a disconnected player's ship stays around, in seconds
+    ///
+    /// Within that time, the player can reconnect and take it over again.
+    /// Afterwards, the ship is removed from the world.
+    pub fn set_disconnect_timeout(&mut self, timeout: Scalar) {
+        self.state.set_disconnect_timeout(timeout);
+        self.disconnect_timeout = timeout;
+    }
+
+    /// Set how far players can see, in meters
+    ///
+    /// Clients are only told about components within that range of their own
+    /// craft.
+    pub fn set_interest_range(&mut self, range: Scalar) {
+        self.interest_range = range;
+    }
+
+    /// Set the largest message that is accepted from clients, in bytes
+    pub fn set_max_message_size(&mut self, size: usize) {
+        self.network.set_max_message_size(size);
+    }
+
+    /// Record the session to the given file
+    ///
+    /// This should be called before the first update, otherwise the recording
+    /// can't be replayed. If the world was restored from a snapshot, that is
+    /// included in the recording, so the replay starts from the same world.
+    pub fn record(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<(), recording::Error> {
+        let header = Header {
+            seed: self.seed,
+            system: self.system.clone(),
+            integrator: self.integrator,
+            disconnect_timeout: self.disconnect_timeout,
+            snapshot: self.restored.clone(),
+        };
+        self.recorder = Some(Recorder::create(path, header)?);
+        Ok(())
+    }
+
+    /// Replace the world with one restored from a snapshot
+    ///
+    /// This should be called before the first update, and before `record`.
+    pub fn restore(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<(), snapshot::Error> {
+        let bytes = fs::read(path)?;
+        let snapshot = Snapshot::from_bytes(&bytes)?;
+        self.state = world::State::restore(snapshot, self.seed)?;
+        self.restored = Some(bytes);
+        self.state.set_integrator(self.integrator);
+        self.state.set_disconnect_timeout(self.disconnect_timeout);
+        Ok(())
+    }
+
+    /// Periodically save a snapshot of the world to the given file
+    pub fn autosave(&mut self, path: impl Into<PathBuf>, interval: Duration) {
+        self.autosave = Some(Autosave {
+            path: path.into(),
+            interval,
+            last_save: Instant::now(),
+        });
+    }
+
     pub fn update(&mut self) {
         self.events.extend(self.network.events());
 
         for event in self.events.drain(..) {
-            match event {
-                Event::Message(_, msg::FromClient::Ping) => {
-                    // This message is just for testing purposes. Nothing to do
-                    // here.
+            if let crate::net::Event::Message(
+                addr,
+                msg::FromClient::Hello {
+                    version,
+                    capabilities,
+                    ..
                 }
-                Event::Message(addr, msg::FromClient::Hello { color }) => {
-                    info!("Connected: {}", addr);
+                | msg::FromClient::Reconnect {
+                    version,
+                    capabilities,
+                    ..
+                },
+            ) = &event
+            {
+                let accepted = if *version != msg::PROTOCOL_VERSION {
+                    Err(msg::Rejection::UnsupportedVersion {
+                        client: *version,
+                        server: msg::PROTOCOL_VERSION,
+                    })
+                } else if self.clients.contains_key(addr) {
+                    Err(msg::Rejection::AlreadyConnected)
+                } else {
+                    Ok(capabilities.intersection(msg::CAPABILITIES))
+                };
 
-                    self.clients.insert(addr, Client::new());
+                match accepted {
+                    Ok(capabilities) => {
+                        info!("Connected: {}", addr);
+                        self.clients.insert(*addr, Client::new(capabilities));
+                        self.network.send(
+                            *addr,
+                            msg::FromServer::Accepted { capabilities },
+                        );
+                    }
+                    Err(reason) => {
+                        // Rejected clients never make it into the world, so
+                        // there's no need to record them either.
+                        info!("Rejected {}: {}", addr, reason);
+                        self.network
+                            .send(*addr, msg::FromServer::Rejected(reason));
+                        continue;
+                    }
+                }
+            }
+
+            let event = Event::from(event);
+
+            if let Some(recorder) = &mut self.recorder {
+                if let Err(err) = recorder.record(self.state.tick(), &event) {
+                    error!("Failed to record event; stopping: {:?}", err);
+                    self.recorder = None;
+                }
+            }
 
-                    // Yes, it's a bad idea to just trust the client to provide
-                    // a color that is not the same as the background color.
-                    // It's good enough for now though.
-                    self.state
-                        .player_connected()
-                        .push(PlayerConnected { addr, color });
+            match &event {
+                Event::Message(addr, msg::FromClient::Ping { sent }) => {
+                    self.network.send(
+                        *addr,
+                        msg::FromServer::Pong {
+                            sent: *sent,
+                            tick: self.state.tick(),
+                        },
+                    );
                 }
-                Event::Message(addr, msg::FromClient::Action(action)) => {
-                    debug!("Input from {}: {:?}", addr, action);
-                    self.state
-                        .player_input()
-                        .push(PlayerInput { addr, action });
+                Event::Message(addr, msg::FromClient::Ack { tick }) => {
+                    if let Some(client) = self.clients.get_mut(addr) {
+                        client.ack(*tick);
+                    }
                 }
-                Event::Error(addr, _) => {
+                Event::Message(addr, msg::FromClient::Resync(handles)) => {
+                    if let Some(client) = self.clients.get_mut(addr) {
+                        for handle in handles {
+                            client.resync(handle);
+                        }
+                    }
+                }
+                Event::Disconnected(addr) => {
                     info!("Disconnected: {}", addr);
-
-                    self.clients.remove(&addr);
-
-                    self.state
-                        .player_disconnected()
-                        .push(PlayerDisconnected { addr });
+                    self.clients.remove(addr);
                 }
+                _ => {}
             }
+
+            handle_event(&mut self.state, &event);
         }
 
         let now = Instant::now();
@@ -102,33 +262,76 @@ impl Server {
         }
 
         for player in self.state.player_created().ready() {
-            self.network
-                .send(player.addr, msg::FromServer::Welcome(player.id));
+            let welcome = msg::FromServer::Welcome {
+                id: player.id,
+                token: player.token,
+            };
+            self.network.send(player.addr, welcome);
+        }
+        for player in self.state.player_reconnected().ready() {
+            info!("Player {:?} reconnected from {}", player.id, player.addr);
+            let welcome = msg::FromServer::Welcome {
+                id: player.id,
+                token: player.token,
+            };
+            self.network.send(player.addr, welcome);
+        }
+        for event in self.state.player_taken_over().ready() {
+            info!("Player {:?} taken over from {}", event.id, event.addr);
+            self.network.send(
+                event.addr,
+                msg::FromServer::Rejected(msg::Rejection::TakenOver),
+            );
+            self.network.remove(event.addr);
+            self.clients.remove(&event.addr);
+        }
+        for event in self.state.ship_destroyed().ready() {
+            info!("Ship of player {:?} destroyed", event.id);
+            self.network.send(
+                event.addr,
+                msg::FromServer::ShipDestroyed {
+                    respawn_in: event.respawn_in,
+                },
+            );
         }
 
         for event in self.state.removals().ready() {
-            let handle = event.handle.into();
+            let handle = event.handle;
 
             for (&addr, client) in &mut self.clients {
-                client.remove(&handle);
-                self.network.send(
-                    addr,
-                    msg::FromServer::RemoveComponent(handle.clone()),
-                );
+                if client.remove(&handle) {
+                    self.network.send(
+                        addr,
+                        msg::FromServer::RemoveComponent(handle.clone()),
+                    );
+                }
+            }
+        }
+
+        for (&addr, client) in &mut self.clients {
+            let interest = self.state.interest(addr, self.interest_range);
+            for handle in client.set_interest(interest) {
+                self.network
+                    .send(addr, msg::FromServer::RemoveComponent(handle));
             }
         }
 
         for component in self.state.updates() {
-            for (&addr, client) in &mut self.clients {
-                let should_update = client.update(component.clone());
+            for client in self.clients.values_mut() {
+                client.update(component.clone());
+            }
+        }
 
-                if should_update {
-                    self.network.send(
-                        addr,
-                        msg::FromServer::UpdateComponent(
-                            component.clone().into(),
-                        ),
-                    );
+        let tick = self.state.tick();
+        for (&addr, client) in &mut self.clients {
+            match client.flush(tick) {
+                Ok(Some(updates)) => {
+                    self.network
+                        .send(addr, msg::FromServer::Updates { tick, updates });
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    error!("Failed to encode updates for {}: {:?}", addr, err);
                 }
             }
         }
@@ -146,5 +349,78 @@ impl Server {
                 msg::FromServer::Diagnostics(self.state.diagnostics()),
             );
         }
+
+        let time_warp = self.state.time_warp();
+        for (&addr, client) in &mut self.clients {
+            if client.update_time_warp(time_warp) {
+                self.network
+                    .send(addr, msg::FromServer::TimeWarp(time_warp));
+            }
+        }
+
+        if let Some(recorder) = &mut self.recorder {
+            if let Err(err) = recorder.flush() {
+                error!("Failed to flush recording; stopping: {:?}", err);
+                self.recorder = None;
+            }
+        }
+
+        if let Some(autosave) = &mut self.autosave {
+            if autosave.last_save.elapsed() >= autosave.interval {
+                debug!("Saving snapshot to {}", autosave.path.display());
+                if let Err(err) = self.state.snapshot().save(&autosave.path) {
+                    error!("Failed to save snapshot: {:?}", err);
+                }
+                autosave.last_save = Instant::now();
+            }
+        }
+    }
+}
+
+struct Autosave {
+    path: PathBuf,
+    interval: Duration,
+    last_save: Instant,
+}
+
+/// Pushes whatever a client sent into the world
+///
+/// This is used by the server itself, as well as to replay recorded sessions.
+pub fn handle_event(state: &mut world::State, event: &Event) {
+    match *event {
+        Event::Message(_, msg::FromClient::Ping { .. })
+        | Event::Message(_, msg::FromClient::Ack { .. })
+        | Event::Message(_, msg::FromClient::Resync(_)) => {
+            // Handled by the server directly. Doesn't affect the world.
+        }
+        Event::Message(_, msg::FromClient::Hello { .. })
+        | Event::Message(_, msg::FromClient::Reconnect { .. }) => {
+            // Converted into `Event::Connected`, when received.
+        }
+        Event::Connected {
+            addr,
+            color,
+            token,
+            new_token,
+        } => {
+            // Yes, it's a bad idea to just trust the client to provide a color
+            // that is not the same as the background color. It's good enough
+            // for now though.
+            state.player_connected().push(PlayerConnected {
+                addr,
+                color,
+                token,
+                new_token,
+            });
+        }
+        Event::Message(addr, msg::FromClient::Action(action)) => {
+            debug!("Input from {}: {:?}", addr, action);
+            state.player_input().push(PlayerInput { addr, action });
+        }
+        Event::Disconnected(addr) => {
+            state
+                .player_disconnected()
+                .push(PlayerDisconnected { addr });
+        }
     }
 }
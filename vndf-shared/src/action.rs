@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::world::Scalar;
+use crate::world::{features::crafts::Maneuver, Scalar};
 
 #[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
 pub struct Action {
@@ -12,7 +12,18 @@ pub struct Action {
 pub enum Kind {
     Rotate(Rotation),
     Thrust(bool),
+    Fire(bool),
+
+    /// Launch a missile at the nearest enemy ship
+    LaunchMissile,
+
     FtlJump(Scalar),
+    Maneuver(Maneuver),
+    CancelManeuvers,
+    TimeWarp(Scalar),
+
+    /// Request a new ship, after the previous one was destroyed
+    Respawn,
 }
 
 #[derive(Clone, Copy, Debug, Deserialize, Serialize, Eq, PartialEq)]
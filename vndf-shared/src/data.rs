@@ -1,13 +1,17 @@
-use toadster::handle;
+pub mod diff;
+
+use toadster::{handle, Handle};
 
 use crate::world::features::{
     crafts::{Craft, Fuel},
     explosions::Explosion,
     health::Health,
+    missiles::Missile,
     physics::{Body, Position, Velocity},
     planets::Planet,
     players::Player,
     ships::Ship,
+    weapons::Projectile,
 };
 
 /// Update component of a specific type from a collection of component stores
@@ -20,6 +24,11 @@ pub trait Remove<T> {
     fn remove(&mut self, handle: impl Into<handle::Weak<T>>);
 }
 
+/// Map a handle to the component it referred to, before a snapshot was restored
+pub trait Remap<T> {
+    fn remap(&self, handle: &Handle<T>) -> Option<Handle<T>>;
+}
+
 macro_rules! components {
     (
         mod $module:ident($store_type:ident) {
@@ -40,6 +49,7 @@ macro_rules! components {
             };
 
             use super::{
+                Remap,
                 Remove,
                 Update,
             };
@@ -63,7 +73,7 @@ macro_rules! components {
             }
 
             components!(@gen_specific, $store_type,
-                $($store_name,)*
+                $($store_name, $component_ty;)*
             );
 
             $(
@@ -161,6 +171,22 @@ macro_rules! components {
                     }
                 }
 
+                pub fn remap<R>(&self, handles: &R) -> Option<Self>
+                    where R: $(Remap<$component_ty> +)*
+                {
+                    match self {
+                        $(
+                            Self::$component_ty(handle) => {
+                                <R as Remap<$component_ty>>::remap(
+                                    handles,
+                                    handle,
+                                )
+                                .map(Self::$component_ty)
+                            }
+                        )*
+                    }
+                }
+
                 pub fn remove<T>(&self, components: &mut T)
                     where T: $(Remove<$component_ty> +)*
                 {
@@ -245,14 +271,92 @@ macro_rules! components {
     };
 
     (@gen_specific, Weak,
-        $($store_name:ident,)*
+        $($store_name:ident, $component_ty:ident;)*
     ) => {
-        // Aside from `Update`/`Remove`, which is handled elsewhere, there is no
-        // code that needs to be generated only for weak stores.
+        // Generate code only needed for weak stores, aside from
+        // `Update`/`Remove`, which is handled elsewhere.
+
+        /// The fields of a component that changed, compared to a baseline
+        #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+        pub enum Delta {
+            $(
+                $component_ty(
+                    toadster::Handle<$component_ty>,
+                    crate::data::diff::Fields,
+                ),
+            )*
+        }
+
+        impl Component {
+            /// Computes the difference to a baseline
+            ///
+            /// Returns `Ok(None)`, if the baseline is not a previous version of
+            /// the same component.
+            pub fn diff(&self, base: &Self)
+                -> Result<Option<Delta>, crate::data::diff::Error>
+            {
+                use crate::data::diff::Diff as _;
+
+                match (self, base) {
+                    $(
+                        (
+                            Self::$component_ty(handle, value),
+                            Self::$component_ty(base_handle, base),
+                        )
+                            if handle == base_handle =>
+                        {
+                            let fields = value.diff(base)?;
+                            Ok(Some(Delta::$component_ty(
+                                handle.clone(),
+                                fields,
+                            )))
+                        }
+                    )*
+                    _ => Ok(None),
+                }
+            }
+
+            /// Applies a difference that was computed against this component
+            ///
+            /// Returns `Ok(None)`, if the delta doesn't refer to this
+            /// component.
+            pub fn patch(&self, delta: &Delta)
+                -> Result<Option<Self>, crate::data::diff::Error>
+            {
+                use crate::data::diff::Diff as _;
+
+                match (self, delta) {
+                    $(
+                        (
+                            Self::$component_ty(handle, value),
+                            Delta::$component_ty(delta_handle, fields),
+                        )
+                            if handle == delta_handle =>
+                        {
+                            let mut value = value.clone();
+                            value.patch(fields)?;
+                            Ok(Some(Self::$component_ty(handle.clone(), value)))
+                        }
+                    )*
+                    _ => Ok(None),
+                }
+            }
+        }
+
+        impl Handle {
+            pub fn from_delta(delta: &Delta) -> Self {
+                match delta {
+                    $(
+                        Delta::$component_ty(handle, _) =>
+                            Self::$component_ty(handle.clone()),
+                    )*
+                }
+            }
+        }
     };
 
     (@gen_specific, Strong,
-        $($store_name:ident,)*
+        $($store_name:ident, $component_ty:ident;)*
     ) => {
         // Generate code only needed for strong stores.
 
@@ -260,35 +364,112 @@ macro_rules! components {
             pub fn apply_changes(&mut self) {
                 $(self.$store_name.apply_changes();)*
             }
+
+            pub fn snapshot(&self) -> Snapshot {
+                Snapshot {
+                    $(
+                        $store_name: self.$store_name
+                            .iter()
+                            .map(|(handle, value)| (handle, value.clone()))
+                            .collect(),
+                    )*
+                }
+            }
+
+            /// Restore components from a snapshot
+            ///
+            /// The components get new handles when they're inserted, so all
+            /// handles referring to them need to be remapped. The returned
+            /// `HandleMap` can be used to do the same for any handles that live
+            /// outside of the components.
+            ///
+            /// Returns `None`, if a component refers to a component that is not
+            /// part of the snapshot.
+            pub fn restore(snapshot: Snapshot) -> Option<(Self, HandleMap)> {
+                let mut components = Self::new();
+                let mut handles = HandleMap {
+                    $($store_name: std::collections::HashMap::new(),)*
+                };
+
+                $(
+                    for (handle, value) in snapshot.$store_name {
+                        let new = components.$store_name.insert(value);
+                        handles.$store_name.insert(handle, new);
+                    }
+                )*
+                $(
+                    for value in components.$store_name.values_mut() {
+                        value.remap(&handles)?;
+                    }
+                )*
+
+                Some((components, handles))
+            }
+        }
+
+        /// All components, with the handles they had when the snapshot was
+        /// taken
+        #[derive(Debug, Deserialize, Serialize)]
+        pub struct Snapshot {
+            $(
+                pub $store_name:
+                    Vec<(handle::Weak<$component_ty>, $component_ty)>,
+            )*
+        }
+
+        /// Maps the handles in a `Snapshot` to the restored components
+        pub struct HandleMap {
+            $(
+                pub $store_name: std::collections::HashMap<
+                    handle::Weak<$component_ty>,
+                    handle::Strong<$component_ty>,
+                >,
+            )*
         }
+
+        $(
+            impl Remap<$component_ty> for HandleMap {
+                fn remap(&self, handle: &toadster::Handle<$component_ty>)
+                    -> Option<toadster::Handle<$component_ty>>
+                {
+                    self.$store_name
+                        .get(&handle.weak())
+                        .map(|handle| handle.clone().into())
+                }
+            }
+        )*
     };
 }
 
 components!(
     mod server(Strong) {
-        bodies,     Body;
-        crafts,     Craft;
-        explosions, Explosion;
-        fuels,      Fuel;
-        healths,    Health;
-        planets,    Planet;
-        players,    Player;
-        positions,  Position;
-        ships,      Ship;
-        velocities, Velocity;
+        bodies,      Body;
+        crafts,      Craft;
+        explosions,  Explosion;
+        fuels,       Fuel;
+        healths,     Health;
+        missiles,    Missile;
+        planets,     Planet;
+        players,     Player;
+        positions,   Position;
+        projectiles, Projectile;
+        ships,       Ship;
+        velocities,  Velocity;
     }
 );
 
 components!(
     mod client(Weak) {
-        bodies,     Body;
-        crafts,     Craft;
-        explosions, Explosion;
-        fuels,      Fuel;
-        healths,    Health;
-        planets,    Planet;
-        positions,  Position;
-        ships,      Ship;
-        velocities, Velocity;
+        bodies,      Body;
+        crafts,      Craft;
+        explosions,  Explosion;
+        fuels,       Fuel;
+        healths,     Health;
+        missiles,    Missile;
+        planets,     Planet;
+        positions,   Position;
+        projectiles, Projectile;
+        ships,       Ship;
+        velocities,  Velocity;
     }
 );
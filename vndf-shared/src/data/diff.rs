@@ -0,0 +1,92 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+pub type Error = postcard::Error;
+
+/// Field-level differences between two values of a component
+pub trait Diff {
+    /// Returns the fields of `self` that differ from `base`
+    fn diff(&self, base: &Self) -> Result<Fields, Error>;
+
+    /// Overwrites the fields of `self` with the ones in `fields`
+    fn patch(&mut self, fields: &Fields) -> Result<(), Error>;
+}
+
+/// The fields of a component that changed, each serialized on its own
+///
+/// Fields are identified by their position in the struct.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Fields(Vec<(u8, Vec<u8>)>);
+
+impl Fields {
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn push<T>(
+        &mut self,
+        index: u8,
+        value: &T,
+        base: &T,
+    ) -> Result<(), Error>
+    where
+        T: Serialize + PartialEq,
+    {
+        if value != base {
+            self.0.push((index, postcard::to_stdvec(value)?));
+        }
+        Ok(())
+    }
+
+    pub fn apply<T>(&self, index: u8, value: &mut T) -> Result<(), Error>
+    where
+        T: DeserializeOwned,
+    {
+        for (i, bytes) in &self.0 {
+            if *i == index {
+                *value = postcard::from_bytes(bytes)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Implements `Diff` for a component, given the names of all of its fields
+///
+/// Needs to be called where the component is defined, so private fields can be
+/// accessed.
+macro_rules! diff {
+    ($ty:ident { $($field:tt),* $(,)? }) => {
+        impl $crate::data::diff::Diff for $ty {
+            fn diff(&self, base: &Self)
+                -> Result<$crate::data::diff::Fields, $crate::data::diff::Error>
+            {
+                let mut fields = $crate::data::diff::Fields::new();
+                let mut index = 0..;
+                $(
+                    fields.push(
+                        index.next().unwrap(),
+                        &self.$field,
+                        &base.$field,
+                    )?;
+                )*
+                Ok(fields)
+            }
+
+            fn patch(&mut self, fields: &$crate::data::diff::Fields)
+                -> Result<(), $crate::data::diff::Error>
+            {
+                let mut index = 0..;
+                $(
+                    fields.apply(index.next().unwrap(), &mut self.$field)?;
+                )*
+                Ok(())
+            }
+        }
+    };
+}
+
+pub(crate) use diff;
@@ -2,4 +2,5 @@ pub mod action;
 pub mod data;
 pub mod main_loop;
 pub mod net;
+pub mod sim;
 pub mod world;
@@ -0,0 +1,124 @@
+use std::collections::VecDeque;
+
+use crate::world::Scalar;
+
+/// Estimates round-trip time and the offset to the server's clock
+///
+/// The client sends pings that carry its local time. The server answers each
+/// with its own time, and the client feeds the results in here. All times are
+/// in seconds.
+pub struct ClockSync {
+    samples: VecDeque<Sample>,
+}
+
+impl ClockSync {
+    /// Number of samples the estimate is based on
+    pub const MAX_SAMPLES: usize = 16;
+
+    pub fn new() -> Self {
+        Self {
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Add the result of a ping
+    ///
+    /// `sent` and `received` are the local times the ping was sent and the
+    /// answer was received. `server` is the server's time when it answered.
+    pub fn pong(&mut self, sent: Scalar, received: Scalar, server: Scalar) {
+        let rtt = received - sent;
+        if rtt < 0.0 {
+            return;
+        }
+
+        // Assume the answer took half the round trip to arrive.
+        let offset = server + rtt / 2.0 - received;
+
+        self.samples.push_back(Sample { rtt, offset });
+        if self.samples.len() > Self::MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Average round-trip time
+    pub fn rtt(&self) -> Option<Scalar> {
+        if self.samples.is_empty() {
+            return None;
+        }
+
+        let sum: Scalar = self.samples.iter().map(|sample| sample.rtt).sum();
+        Some(sum / self.samples.len() as Scalar)
+    }
+
+    /// Difference between the server's clock and the local one
+    ///
+    /// Taken from the sample with the shortest round trip, as that one was
+    /// least affected by delays in either direction.
+    pub fn offset(&self) -> Option<Scalar> {
+        self.samples
+            .iter()
+            .min_by(|a, b| a.rtt.partial_cmp(&b.rtt).unwrap())
+            .map(|sample| sample.offset)
+    }
+
+    /// Convert a local time into the server's time
+    pub fn server_time(&self, local: Scalar) -> Option<Scalar> {
+        self.offset().map(|offset| local + offset)
+    }
+}
+
+impl Default for ClockSync {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct Sample {
+    rtt: Scalar,
+    offset: Scalar,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ClockSync;
+
+    #[test]
+    fn it_should_estimate_rtt_and_offset() {
+        let mut clock = ClockSync::new();
+        assert_eq!(clock.rtt(), None);
+        assert_eq!(clock.offset(), None);
+
+        // Server is 100 seconds ahead, 100 ms each way.
+        clock.pong(1.0, 1.2, 101.1);
+        clock.pong(2.0, 2.2, 102.1);
+
+        assert!((clock.rtt().unwrap() - 0.2).abs() < 1e-9);
+        assert!((clock.offset().unwrap() - 100.0).abs() < 1e-9);
+        assert!((clock.server_time(3.0).unwrap() - 103.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn it_should_prefer_samples_with_a_short_round_trip() {
+        let mut clock = ClockSync::new();
+
+        // The answer got delayed on its way back, which would throw off the
+        // offset by 0.5 s.
+        clock.pong(1.0, 2.2, 101.1);
+        clock.pong(2.0, 2.2, 102.1);
+
+        assert!((clock.offset().unwrap() - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn it_should_forget_old_samples() {
+        let mut clock = ClockSync::new();
+
+        clock.pong(0.0, 0.0, 50.0);
+        for i in 0..ClockSync::MAX_SAMPLES {
+            let t = i as f64 + 1.0;
+            clock.pong(t, t + 0.2, t + 100.1);
+        }
+
+        assert!((clock.offset().unwrap() - 100.0).abs() < 1e-9);
+    }
+}
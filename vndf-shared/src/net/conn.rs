@@ -1,175 +1,530 @@
 use std::{
+    collections::VecDeque,
+    fmt,
     io::{self, prelude::*},
     iter,
-    net::{SocketAddr, TcpStream, ToSocketAddrs},
-    sync::mpsc::{
-        channel, Receiver, RecvError, SendError, Sender, TryRecvError,
-    },
-    thread,
+    marker::PhantomData,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs},
+    str::FromStr,
+    sync::Arc,
+    time::Instant,
+};
+
+use log::{debug, trace};
+use mio::{
+    net::{TcpStream, UdpSocket},
+    Poll, PollOpt, Ready, Token,
+};
+
+use crate::net::{
+    self,
+    msg::{self, Channel, Delivery},
+    udp::{self, Channels, Packet},
+    Message,
 };
 
-use log::{debug, error, trace};
+/// Size of the send queue, beyond which the peer is considered congested
+///
+/// While the peer is congested, messages that are allowed to get lost are
+/// dropped instead of queued.
+pub const CONGESTION_THRESHOLD: usize = 64 * 1024;
 
-use crate::net::{self, Message};
+/// Size of the send queue, beyond which the connection fails
+pub const MAX_QUEUED: usize = 1024 * 1024;
 
+/// The protocol a connection runs over
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Transport {
+    Tcp,
+    Udp,
+}
+
+impl FromStr for Transport {
+    type Err = UnknownTransport;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tcp" => Ok(Self::Tcp),
+            "udp" => Ok(Self::Udp),
+            s => Err(UnknownTransport(s.to_owned())),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct UnknownTransport(pub String);
+
+impl fmt::Display for UnknownTransport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unknown transport: {}", self.0)
+    }
+}
+
+/// A connection to a peer
+///
+/// Never blocks and doesn't spawn any threads. All I/O happens when the
+/// connection is asked for incoming messages, or sends a message.
 pub struct Conn<In, Out> {
-    rx: Receiver<Option<In>>,
-    tx: Sender<Out>,
+    socket: Socket,
+    received: VecDeque<In>,
+    max_message_size: usize,
+
+    /// The error that closed the connection, if not yet reported
+    error: Option<net::Error>,
+    closed: bool,
 
     pub local_addr: SocketAddr,
     pub peer_addr: SocketAddr,
+
+    _out: PhantomData<Out>,
+}
+
+enum Socket {
+    Tcp {
+        stream: TcpStream,
+        read_buf: Vec<u8>,
+        write_buf: Vec<u8>,
+    },
+    Udp {
+        socket: Arc<UdpSocket>,
+        channels: Channels,
+
+        /// Whether the connection reads from the socket itself
+        ///
+        /// If not, the socket is shared with other connections and a
+        /// `Listener` passes on the packets.
+        owned: bool,
+    },
 }
 
 impl<In, Out> Conn<In, Out>
 where
-    In: Message + 'static,
-    Out: Message + 'static,
+    In: Message,
+    Out: Message + Delivery,
 {
-    pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
-        let stream = TcpStream::connect(addr)?;
+    pub fn connect<A: ToSocketAddrs>(
+        addr: A,
+        transport: Transport,
+    ) -> io::Result<Self> {
+        match transport {
+            Transport::Tcp => {
+                let stream = std::net::TcpStream::connect(addr)?;
+                Self::from_tcp(TcpStream::from_stream(stream)?)
+            }
+            Transport::Udp => {
+                let peer_addr =
+                    addr.to_socket_addrs()?.next().ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "No address to connect to",
+                        )
+                    })?;
+                let bind_addr: SocketAddr = if peer_addr.is_ipv4() {
+                    (Ipv4Addr::UNSPECIFIED, 0).into()
+                } else {
+                    (Ipv6Addr::UNSPECIFIED, 0).into()
+                };
+
+                let socket = std::net::UdpSocket::bind(bind_addr)?;
+                socket.connect(peer_addr)?;
+                let socket = UdpSocket::from_socket(socket)?;
+
+                // Only valid after connecting, as the socket isn't bound to a
+                // specific address before that.
+                let local_addr = socket.local_addr()?;
 
-        Self::from_stream(stream, false)
+                let socket = Socket::Udp {
+                    socket: Arc::new(socket),
+                    channels: Channels::new(),
+                    owned: true,
+                };
+                Ok(Self::new(socket, local_addr, peer_addr))
+            }
+        }
     }
 
-    pub fn from_stream(stream: TcpStream, quiet: bool) -> io::Result<Self> {
+    pub(crate) fn from_tcp(stream: TcpStream) -> io::Result<Self> {
         stream.set_nodelay(true)?;
 
         let local_addr = stream.local_addr()?;
         let peer_addr = stream.peer_addr()?;
 
-        let (in_tx, in_rx) = channel();
-        let (out_tx, out_rx) = channel();
-
-        let stream_send = stream.try_clone()?;
-        let stream_receive = stream;
+        let socket = Socket::Tcp {
+            stream,
+            read_buf: Vec::new(),
+            write_buf: Vec::new(),
+        };
+        Ok(Self::new(socket, local_addr, peer_addr))
+    }
 
-        thread::spawn(move || {
-            if let Err(err) = send(stream_send, out_rx) {
-                if quiet {
-                    debug!("Send error ({}): {:?}", peer_addr, err);
-                } else {
-                    error!("Send error ({}): {:?}", peer_addr, err);
-                }
-            }
-        });
+    /// Create a connection on a UDP socket that is shared with others
+    pub(crate) fn from_udp(
+        socket: Arc<UdpSocket>,
+        peer_addr: SocketAddr,
+    ) -> io::Result<Self> {
+        let local_addr = socket.local_addr()?;
 
-        thread::spawn(move || {
-            if let Err(err) = receive(stream_receive, in_tx) {
-                if quiet {
-                    debug!("Receive error ({}) : {:?}", peer_addr, err);
-                } else {
-                    error!("Receive error ({}) : {:?}", peer_addr, err);
-                }
-            }
-        });
+        let socket = Socket::Udp {
+            socket,
+            channels: Channels::new(),
+            owned: false,
+        };
+        Ok(Self::new(socket, local_addr, peer_addr))
+    }
 
-        Ok(Self {
-            rx: in_rx,
-            tx: out_tx,
+    fn new(
+        socket: Socket,
+        local_addr: SocketAddr,
+        peer_addr: SocketAddr,
+    ) -> Self {
+        Self {
+            socket,
+            received: VecDeque::new(),
+            max_message_size: msg::DEFAULT_MAX_SIZE,
+            error: None,
+            closed: false,
             local_addr,
             peer_addr,
-        })
+            _out: PhantomData,
+        }
+    }
+
+    /// Set the largest message that is accepted from the peer, in bytes
+    ///
+    /// The connection fails, if the peer sends a larger message.
+    pub fn set_max_message_size(&mut self, size: usize) {
+        self.max_message_size = size;
     }
 
     pub fn incoming<'s>(
         &'s mut self,
     ) -> impl Iterator<Item = net::Result<In>> + 's {
+        self.pump();
+
+        let mut failed = false;
         iter::from_fn(move || {
-            loop {
-                match self.rx.try_recv() {
-                    Ok(Some(event)) => {
-                        return Some(Ok(event));
-                    }
-                    Ok(None) => {
-                        // Just a ping from the receive thread. Real messages
-                        // might be coming after it.
-                        continue;
-                    }
-                    Err(TryRecvError::Empty) => {
-                        return None;
-                    }
-                    Err(TryRecvError::Disconnected) => {
-                        return Some(Err(net::Error::ThreadFailed));
-                    }
-                }
+            if failed {
+                return None;
             }
+
+            let next = self.take().or_else(|| {
+                if self.closed {
+                    Some(Err(net::Error::Disconnected))
+                } else {
+                    None
+                }
+            });
+
+            failed = matches!(next, Some(Err(_)));
+            next
         })
     }
 
     pub fn send(&mut self, message: Out) -> net::Result {
         debug!("Sending message: {:?}", message);
 
-        self.tx.send(message)?;
+        if self.closed {
+            return Err(net::Error::Disconnected);
+        }
 
-        Ok(())
+        let result = self.queue(message);
+        if result.is_err() {
+            // No need to report the error again, as the caller gets it now.
+            self.closed = true;
+        }
+        result
     }
 
     pub fn disconnect(self) {
-        // Nothing to do. The threads will end once our ends of the channels are
-        // dropped. When the threads end, the streams will be dropped and the
-        // connection will be closed.
+        // Nothing to do. The socket is closed when the connection is dropped.
     }
-}
 
-fn receive<T>(mut stream: TcpStream, in_chan: Sender<Option<T>>) -> net::Result
-where
-    T: Message,
-{
-    let mut buf = Vec::new();
+    /// Register the connection's socket for readiness events
+    ///
+    /// Only needed for TCP. UDP sockets are registered by whoever owns them.
+    pub(crate) fn register(&self, poll: &Poll, token: Token) -> io::Result<()> {
+        if let Socket::Tcp { stream, .. } = &self.socket {
+            poll.register(
+                stream,
+                token,
+                Ready::readable() | Ready::writable(),
+                PollOpt::edge(),
+            )?;
+        }
+        Ok(())
+    }
 
-    loop {
-        trace!("Starting receive loop");
+    pub(crate) fn deregister(&self, poll: &Poll) -> io::Result<()> {
+        if let Socket::Tcp { stream, .. } = &self.socket {
+            poll.deregister(stream)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Do whatever I/O is possible without blocking
+    pub(crate) fn pump(&mut self) {
+        if self.closed {
+            return;
+        }
+        if let Err(err) = self.try_pump() {
+            self.fail(err);
+        }
+    }
+
+    /// Handle a packet that a `Listener` received for this connection
+    pub(crate) fn receive_packet(&mut self, packet: Packet) {
+        if self.closed {
+            return;
+        }
+        if let Err(err) = self.try_receive_packet(packet) {
+            self.fail(err);
+        }
+    }
+
+    /// Returns the next received message, or the error that closed the
+    /// connection
+    pub(crate) fn take(&mut self) -> Option<net::Result<In>> {
+        if let Some(message) = self.received.pop_front() {
+            return Some(Ok(message));
+        }
+        self.error.take().map(Err)
+    }
+
+    fn fail(&mut self, err: net::Error) {
+        trace!("Connection to {} failed: {:?}", self.peer_addr, err);
 
-        let mut tmp = [0; 1024];
+        if !self.closed {
+            self.closed = true;
+            self.error = Some(err);
+        }
+    }
 
-        let read = stream.read(&mut tmp)?;
-        let read = &tmp[..read];
+    fn try_pump(&mut self) -> net::Result {
+        if let Socket::Tcp {
+            stream,
+            read_buf,
+            write_buf,
+        } = &mut self.socket
+        {
+            let eof = read(stream, read_buf)?;
+            while let Some(message) = In::read(read_buf, self.max_message_size)?
+            {
+                self.received.push_back(message);
+            }
+            if eof {
+                return Err(net::Error::Disconnected);
+            }
 
-        buf.extend(read);
+            write(stream, write_buf)?;
+            return Ok(());
+        }
 
-        let mut message = None;
+        if let Socket::Udp {
+            socket,
+            owned: true,
+            ..
+        } = &self.socket
+        {
+            let socket = socket.clone();
+            let mut buf = vec![0; udp::MAX_PACKET];
 
-        loop {
-            // We need to try this at least once, no matter if we have a message
-            // or not. Otherwise, we might never notice, if the other end has
-            // hung up.
-            if let Err(SendError(_)) = in_chan.send(message) {
-                // Other end has hung up. No need to keep this up.
-                return Ok(());
+            loop {
+                let len = match socket.recv(&mut buf) {
+                    Ok(len) => len,
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                        break;
+                    }
+                    Err(err) => return Err(err.into()),
+                };
+
+                match Packet::decode(&buf[..len]) {
+                    Ok(packet) => self.try_receive_packet(packet)?,
+                    Err(err) => debug!("Invalid packet: {:?}", err),
+                }
             }
+        }
+
+        self.try_maintain()
+    }
+
+    /// Resend what got lost and check whether the peer is still there
+    ///
+    /// Only does something for UDP.
+    pub(crate) fn maintain(&mut self) {
+        if self.closed {
+            return;
+        }
+        if let Err(err) = self.try_maintain() {
+            self.fail(err);
+        }
+    }
 
-            message = T::read(&mut buf)?;
+    fn try_maintain(&mut self) -> net::Result {
+        if let Socket::Udp { channels, .. } = &mut self.socket {
+            let now = Instant::now();
+            if channels.timed_out(now) {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "Peer stopped responding",
+                )
+                .into());
+            }
 
-            if message.is_none() {
-                // No more message to read. No need to keep sending crap through
-                // the channel.
-                break;
+            for packet in channels.poll(now) {
+                self.send_packet(&packet)?;
             }
         }
+
+        Ok(())
     }
-}
 
-fn send<T>(mut stream: TcpStream, out_chan: Receiver<T>) -> net::Result
-where
-    T: Message,
-{
-    let mut buf = Vec::new();
+    fn try_receive_packet(&mut self, packet: Packet) -> net::Result {
+        trace!("Received from {}: {:?}", self.peer_addr, packet);
 
-    loop {
-        trace!("Starting send loop: {:?}", buf);
+        let received = match &mut self.socket {
+            Socket::Udp { channels, .. } => channels.receive(packet),
+            Socket::Tcp { .. } => return Ok(()),
+        };
+
+        if let Some(reply) = received.reply {
+            self.send_packet(&reply)?;
+        }
+        for mut message in received.messages {
+            match In::read(&mut message, self.max_message_size)? {
+                Some(message) => self.received.push_back(message),
+                None => {
+                    // Messages are only delivered once all of their fragments
+                    // have arrived, so this one must have been truncated.
+                    let err = postcard::Error::DeserializeUnexpectedEnd;
+                    return Err(msg::Error::from(err).into());
+                }
+            }
+        }
+        if received.disconnected {
+            return Err(net::Error::Disconnected);
+        }
+
+        Ok(())
+    }
+
+    fn queue(&mut self, message: Out) -> net::Result {
+        let channel = message.channel();
 
-        stream.write_all(&buf)?;
-        buf.clear();
+        let mut buf = Vec::new();
+        message.write(&mut buf)?;
 
-        match out_chan.recv() {
-            Ok(message) => {
-                message.write(&mut buf)?;
+        match &mut self.socket {
+            Socket::Tcp {
+                stream, write_buf, ..
+            } => {
+                if write_buf.len() >= MAX_QUEUED {
+                    return Err(net::Error::Congested);
+                }
+                if write_buf.len() >= CONGESTION_THRESHOLD
+                    && channel == Channel::UnreliableSequenced
+                {
+                    debug!("Dropping message to congested peer");
+                    return Ok(());
+                }
+
+                write_buf.extend(buf);
+                write(stream, write_buf)?;
             }
-            Err(RecvError) => {
-                // This means the other end has hung up. No need to continue
-                // here.
-                return Ok(());
+            Socket::Udp { channels, .. } => {
+                let packets = channels.send(channel, buf)?;
+                if channels.unacked() > udp::MAX_UNACKED {
+                    return Err(net::Error::Congested);
+                }
+
+                for packet in packets {
+                    self.send_packet(&packet)?;
+                }
             }
         }
+
+        Ok(())
     }
+
+    fn send_packet(&self, packet: &Packet) -> net::Result {
+        trace!("Sending to {}: {:?}", self.peer_addr, packet);
+
+        if let Socket::Udp { socket, owned, .. } = &self.socket {
+            let packet = packet.encode()?;
+            let result = if *owned {
+                socket.send(&packet)
+            } else {
+                socket.send_to(&packet, &self.peer_addr)
+            };
+
+            match result {
+                Ok(_) => {}
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    // The packet is lost. If it needs to arrive, it will be
+                    // sent again.
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<In, Out> Drop for Conn<In, Out> {
+    fn drop(&mut self) {
+        // Let the other side know, but don't bother making sure it gets the
+        // message. It will time out eventually, if it doesn't.
+        if let Socket::Udp { socket, owned, .. } = &self.socket {
+            if self.closed {
+                return;
+            }
+            if let Ok(packet) = Packet::Disconnect.encode() {
+                let _ = if *owned {
+                    socket.send(&packet)
+                } else {
+                    socket.send_to(&packet, &self.peer_addr)
+                };
+            }
+        }
+    }
+}
+
+/// Read everything that's available
+///
+/// Returns whether the other end has closed the connection.
+fn read(stream: &mut TcpStream, buf: &mut Vec<u8>) -> io::Result<bool> {
+    let mut tmp = [0; 4096];
+
+    loop {
+        match stream.read(&mut tmp) {
+            Ok(0) => return Ok(true),
+            Ok(read) => buf.extend(&tmp[..read]),
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                return Ok(false)
+            }
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Write as much as possible without blocking
+fn write(stream: &mut TcpStream, buf: &mut Vec<u8>) -> io::Result<()> {
+    while !buf.is_empty() {
+        match stream.write(buf) {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(written) => {
+                buf.drain(..written);
+            }
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(())
 }
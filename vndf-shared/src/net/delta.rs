@@ -0,0 +1,320 @@
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::data::{self, diff};
+
+/// A component update, as sent over the network
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub enum Update {
+    /// The whole component, for when there's no baseline to compare to
+    Full(data::client::Component),
+
+    /// The fields that changed, compared to the version of the component that
+    /// was sent at the tick `base`
+    Delta {
+        base: u64,
+        delta: data::client::Delta,
+    },
+}
+
+impl Update {
+    /// The component this update is for
+    pub fn handle(&self) -> data::client::Handle {
+        match self {
+            Self::Full(component) => {
+                data::client::Handle::from_component(component)
+            }
+            Self::Delta { delta, .. } => {
+                data::client::Handle::from_delta(delta)
+            }
+        }
+    }
+}
+
+/// Encodes the updates for a single client
+///
+/// Updates are encoded against the latest version of the component that the
+/// client has acknowledged receiving.
+#[derive(Default)]
+pub struct Encoder {
+    acked: HashMap<data::client::Handle, (u64, data::client::Component)>,
+    unacked: VecDeque<(u64, Vec<data::client::Component>)>,
+}
+
+impl Encoder {
+    /// Number of batches that are kept around, waiting for acknowledgement
+    pub const MAX_UNACKED: usize = 64;
+
+    /// Number of ticks after which an acknowledged version is no longer used
+    /// as a baseline
+    ///
+    /// The client only keeps so many versions of each component around. If
+    /// acknowledgements don't come through for a while, the full component is
+    /// sent instead.
+    pub const MAX_BASELINE_AGE: u64 = 64;
+
+    pub fn new() -> Self {
+        Self {
+            acked: HashMap::new(),
+            unacked: VecDeque::new(),
+        }
+    }
+
+    /// Encode a batch of components that are sent at the given tick
+    pub fn encode(
+        &mut self,
+        tick: u64,
+        components: Vec<data::client::Component>,
+    ) -> Result<Vec<Update>, diff::Error> {
+        let mut updates = Vec::new();
+
+        for component in &components {
+            let handle = data::client::Handle::from_component(component);
+
+            let delta = match self.acked.get(&handle) {
+                Some((base, base_value))
+                    if tick - base <= Self::MAX_BASELINE_AGE =>
+                {
+                    component
+                        .diff(base_value)?
+                        .map(|delta| Update::Delta { base: *base, delta })
+                }
+                _ => None,
+            };
+
+            let update =
+                delta.unwrap_or_else(|| Update::Full(component.clone()));
+            updates.push(update);
+        }
+
+        self.unacked.push_back((tick, components));
+        if self.unacked.len() > Self::MAX_UNACKED {
+            self.unacked.pop_front();
+        }
+
+        Ok(updates)
+    }
+
+    /// Mark the batch from the given tick as received by the client
+    ///
+    /// Older batches that haven't been acknowledged by now are assumed lost.
+    pub fn ack(&mut self, tick: u64) {
+        while let Some((batch_tick, components)) = self.unacked.pop_front() {
+            if batch_tick > tick {
+                self.unacked.push_front((batch_tick, components));
+                break;
+            }
+            if batch_tick < tick {
+                continue;
+            }
+
+            for component in components {
+                let handle = data::client::Handle::from_component(&component);
+                self.acked.insert(handle, (batch_tick, component));
+            }
+        }
+    }
+
+    /// Whether the client is known to have this version of the component
+    pub fn is_acked(&self, component: &data::client::Component) -> bool {
+        let handle = data::client::Handle::from_component(component);
+        self.acked
+            .get(&handle)
+            .map(|(_, acked)| acked == component)
+            .unwrap_or(false)
+    }
+
+    pub fn remove(&mut self, handle: &data::client::Handle) {
+        self.acked.remove(handle);
+        for (_, components) in &mut self.unacked {
+            components.retain(|component| {
+                &data::client::Handle::from_component(component) != handle
+            });
+        }
+    }
+}
+
+/// Decodes updates on the client
+///
+/// Keeps the recently received versions of each component, as the server
+/// might use any of them as a baseline.
+#[derive(Default)]
+pub struct Decoder {
+    received:
+        HashMap<data::client::Handle, VecDeque<(u64, data::client::Component)>>,
+}
+
+impl Decoder {
+    /// Number of versions that are kept per component
+    ///
+    /// At most one version is sent per tick, so this covers every baseline the
+    /// encoder might still use.
+    pub const HISTORY: usize = Encoder::MAX_BASELINE_AGE as usize + 1;
+
+    pub fn new() -> Self {
+        Self {
+            received: HashMap::new(),
+        }
+    }
+
+    /// Decode an update that was received as part of the batch for `tick`
+    pub fn decode(
+        &mut self,
+        tick: u64,
+        update: Update,
+    ) -> Result<data::client::Component, Error> {
+        let component = match update {
+            Update::Full(component) => component,
+            Update::Delta { base, delta } => {
+                let handle = data::client::Handle::from_delta(&delta);
+                self.received
+                    .get(&handle)
+                    .and_then(|history| {
+                        history.iter().find(|(tick, _)| *tick == base)
+                    })
+                    .ok_or(Error::MissingBaseline(handle, base))
+                    .and_then(|(_, base_value)| {
+                        base_value
+                            .patch(&delta)?
+                            .ok_or(Error::MismatchedBaseline(base))
+                    })?
+            }
+        };
+
+        let history = self
+            .received
+            .entry(data::client::Handle::from_component(&component))
+            .or_default();
+        history.push_back((tick, component.clone()));
+        if history.len() > Self::HISTORY {
+            history.pop_front();
+        }
+
+        Ok(component)
+    }
+
+    pub fn remove(&mut self, handle: &data::client::Handle) {
+        self.received.remove(handle);
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Diff(diff::Error),
+
+    /// The baseline the update refers to is not known (anymore)
+    MissingBaseline(data::client::Handle, u64),
+
+    /// The baseline is not a version of the updated component
+    MismatchedBaseline(u64),
+}
+
+impl From<diff::Error> for Error {
+    fn from(err: diff::Error) -> Self {
+        Self::Diff(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use toadster::store;
+
+    use crate::{
+        data::client::Component,
+        world::{
+            features::physics::{Body, Position, Velocity},
+            Pnt2,
+        },
+    };
+
+    use super::{Decoder, Encoder, Update};
+
+    #[test]
+    fn it_should_send_deltas_once_a_baseline_is_acknowledged() {
+        let mut positions = store::Strong::new();
+        let mut velocities = store::Strong::new();
+        let mut bodies = store::Strong::new();
+
+        let pos = positions.insert(Position::new());
+        let vel = velocities.insert(Velocity::new());
+        let body = Body::new(pos, vel);
+        let handle = bodies.insert(body.clone());
+
+        let mut encoder = Encoder::new();
+        let mut decoder = Decoder::new();
+
+        let first = Component::Body(handle.clone().into(), body.clone());
+        let updates = encoder.encode(1, vec![first.clone()]).unwrap();
+        assert!(matches!(updates[0], Update::Full(_)));
+        for update in updates {
+            assert_eq!(decoder.decode(1, update).unwrap(), first);
+        }
+
+        encoder.ack(1);
+
+        let mut changed = body;
+        changed.mass = 2.0;
+        let second = Component::Body(handle.into(), changed);
+        let updates = encoder.encode(2, vec![second.clone()]).unwrap();
+        assert!(matches!(updates[0], Update::Delta { base: 1, .. }));
+
+        let full = postcard::to_stdvec(&Update::Full(second.clone())).unwrap();
+        let delta = postcard::to_stdvec(&updates[0]).unwrap();
+        assert!(delta.len() < full.len());
+
+        for update in updates {
+            assert_eq!(decoder.decode(2, update).unwrap(), second);
+        }
+    }
+
+    #[test]
+    fn it_should_only_use_acknowledged_baselines() {
+        let mut positions = store::Strong::new();
+        let handle = positions.insert(Position::new());
+
+        let mut encoder = Encoder::new();
+
+        let component = |x| {
+            Component::Position(
+                handle.clone().into(),
+                Position(Pnt2::new(x, 0.0)),
+            )
+        };
+
+        encoder.encode(1, vec![component(1.0)]).unwrap();
+        encoder.encode(2, vec![component(2.0)]).unwrap();
+        encoder.ack(1);
+
+        // The batch from tick 2 was not acknowledged, so tick 1 stays the
+        // baseline.
+        let updates = encoder.encode(3, vec![component(3.0)]).unwrap();
+        assert!(matches!(updates[0], Update::Delta { base: 1, .. }));
+    }
+
+    #[test]
+    fn it_should_send_full_components_once_the_baseline_is_too_old() {
+        let mut positions = store::Strong::new();
+        let handle = positions.insert(Position::new());
+
+        let mut encoder = Encoder::new();
+
+        let component = |x| {
+            Component::Position(
+                handle.clone().into(),
+                Position(Pnt2::new(x, 0.0)),
+            )
+        };
+
+        encoder.encode(1, vec![component(1.0)]).unwrap();
+        encoder.ack(1);
+
+        let tick = 1 + Encoder::MAX_BASELINE_AGE;
+        let updates = encoder.encode(tick, vec![component(2.0)]).unwrap();
+        assert!(matches!(updates[0], Update::Delta { base: 1, .. }));
+
+        // The client might have dropped the baseline from its history by now.
+        let updates = encoder.encode(tick + 1, vec![component(3.0)]).unwrap();
+        assert!(matches!(updates[0], Update::Full(_)));
+    }
+}
@@ -0,0 +1,329 @@
+use std::{
+    collections::HashMap,
+    io,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use log::{debug, error};
+use mio::{
+    net::{TcpListener, UdpSocket},
+    Events, Poll, PollOpt, Ready, Token,
+};
+
+use crate::net::{
+    self,
+    msg::{self, Delivery},
+    udp::{self, Packet},
+    Conn, Message, Transport,
+};
+
+/// How often UDP connections check for resends and timeouts
+const MAINTENANCE_INTERVAL: Duration = Duration::from_millis(10);
+
+const LISTENER: Token = Token(0);
+
+/// Accepts connections and services all of them from a single thread
+///
+/// Never blocks. `poll` needs to be called regularly to accept connections,
+/// and to receive and send data.
+pub struct Listener<In, Out> {
+    poll: Poll,
+    events: Events,
+    socket: ListenSocket,
+
+    conns: HashMap<Token, Conn<In, Out>>,
+    tokens: HashMap<SocketAddr, Token>,
+    next_token: usize,
+
+    max_message_size: usize,
+    last_maintenance: Instant,
+}
+
+enum ListenSocket {
+    Tcp(TcpListener),
+
+    /// Shared by all connections. A packet from an address that isn't known
+    /// yet starts a new connection, if it carries a handshake.
+    Udp(Arc<UdpSocket>),
+}
+
+impl<In, Out> Listener<In, Out>
+where
+    In: Message + Delivery,
+    Out: Message + Delivery,
+{
+    pub fn bind(addr: SocketAddr, transport: Transport) -> io::Result<Self> {
+        let poll = Poll::new()?;
+
+        let socket = match transport {
+            Transport::Tcp => {
+                let listener = TcpListener::bind(&addr)?;
+                poll.register(
+                    &listener,
+                    LISTENER,
+                    Ready::readable(),
+                    PollOpt::edge(),
+                )?;
+                ListenSocket::Tcp(listener)
+            }
+            Transport::Udp => {
+                let socket = UdpSocket::bind(&addr)?;
+                poll.register(
+                    &socket,
+                    LISTENER,
+                    Ready::readable(),
+                    PollOpt::edge(),
+                )?;
+                ListenSocket::Udp(Arc::new(socket))
+            }
+        };
+
+        Ok(Self {
+            poll,
+            events: Events::with_capacity(1024),
+            socket,
+            conns: HashMap::new(),
+            tokens: HashMap::new(),
+            next_token: 1,
+            max_message_size: msg::DEFAULT_MAX_SIZE,
+            last_maintenance: Instant::now(),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        match &self.socket {
+            ListenSocket::Tcp(listener) => listener.local_addr(),
+            ListenSocket::Udp(socket) => socket.local_addr(),
+        }
+    }
+
+    /// Set the largest message that is accepted from peers, in bytes
+    ///
+    /// Applies to existing connections and to those that are yet to be
+    /// accepted.
+    pub fn set_max_message_size(&mut self, size: usize) {
+        self.max_message_size = size;
+        for conn in self.conns.values_mut() {
+            conn.set_max_message_size(size);
+        }
+    }
+
+    /// Do all pending I/O and return what was received
+    ///
+    /// Connections that fail are removed after their error is returned.
+    pub fn poll(&mut self) -> Vec<(SocketAddr, net::Result<In>)> {
+        let mut ready = Vec::new();
+
+        if let Err(err) =
+            self.poll.poll(&mut self.events, Some(Duration::new(0, 0)))
+        {
+            error!("Error polling sockets: {:?}", err);
+            return Vec::new();
+        }
+        let tokens: Vec<_> =
+            self.events.iter().map(|event| event.token()).collect();
+
+        for token in tokens {
+            if token == LISTENER {
+                match &self.socket {
+                    ListenSocket::Tcp(_) => self.accept(),
+                    ListenSocket::Udp(_) => ready.extend(self.receive()),
+                }
+            } else {
+                ready.push(token);
+            }
+        }
+
+        for token in &ready {
+            if let Some(conn) = self.conns.get_mut(token) {
+                conn.pump();
+            }
+        }
+
+        let now = Instant::now();
+        if now.duration_since(self.last_maintenance) >= MAINTENANCE_INTERVAL {
+            self.last_maintenance = now;
+
+            for (&token, conn) in &mut self.conns {
+                conn.maintain();
+                if conn.is_closed() {
+                    ready.push(token);
+                }
+            }
+        }
+
+        let mut results = Vec::new();
+        for token in ready {
+            let conn = match self.conns.get_mut(&token) {
+                Some(conn) => conn,
+                None => continue,
+            };
+
+            while let Some(result) = conn.take() {
+                results.push((conn.peer_addr, result));
+            }
+
+            if conn.is_closed() {
+                let addr = conn.peer_addr;
+                self.remove(addr);
+            }
+        }
+
+        results
+    }
+
+    /// Send a message to a peer
+    ///
+    /// Does nothing, if there's no connection to that peer.
+    pub fn send(&mut self, addr: SocketAddr, message: Out) -> net::Result {
+        let conns = &mut self.conns;
+        let conn = self
+            .tokens
+            .get(&addr)
+            .and_then(|token| conns.get_mut(token));
+
+        match conn {
+            Some(conn) => conn.send(message),
+            None => Ok(()),
+        }
+    }
+
+    /// Close the connection to a peer
+    pub fn remove(&mut self, addr: SocketAddr) {
+        let token = match self.tokens.remove(&addr) {
+            Some(token) => token,
+            None => return,
+        };
+
+        if let Some(conn) = self.conns.remove(&token) {
+            if let Err(err) = conn.deregister(&self.poll) {
+                debug!("Error deregistering {}: {:?}", addr, err);
+            }
+        }
+    }
+
+    fn accept(&mut self) {
+        loop {
+            let result = match &self.socket {
+                ListenSocket::Tcp(listener) => listener.accept(),
+                ListenSocket::Udp(_) => return,
+            };
+            let stream = match result {
+                Ok((stream, _)) => stream,
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    return;
+                }
+                Err(err) => {
+                    error!("Error accepting connection: {:?}", err);
+                    return;
+                }
+            };
+
+            let token = self.next_token();
+            let conn = Conn::from_tcp(stream).and_then(|conn| {
+                conn.register(&self.poll, token)?;
+                Ok(conn)
+            });
+            match conn {
+                Ok(conn) => self.insert(token, conn),
+                Err(err) => error!("Error accepting connection: {:?}", err),
+            }
+        }
+    }
+
+    /// Receive packets and pass them on to their connections
+    ///
+    /// Returns the tokens of the connections that received something.
+    fn receive(&mut self) -> Vec<Token> {
+        let socket = match &self.socket {
+            ListenSocket::Udp(socket) => socket.clone(),
+            ListenSocket::Tcp(_) => return Vec::new(),
+        };
+
+        let mut ready = Vec::new();
+        let mut buf = vec![0; udp::MAX_PACKET];
+
+        loop {
+            let (len, addr) = match socket.recv_from(&mut buf) {
+                Ok(received) => received,
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    return ready;
+                }
+                Err(err) => {
+                    error!("Error receiving packet: {:?}", err);
+                    return ready;
+                }
+            };
+
+            let packet = match Packet::decode(&buf[..len]) {
+                Ok(packet) => packet,
+                Err(err) => {
+                    debug!("Invalid packet from {}: {:?}", addr, err);
+                    continue;
+                }
+            };
+
+            let token = match self.tokens.get(&addr) {
+                Some(&token) => token,
+                None => {
+                    // Anyone can send us packets, so don't spend anything on
+                    // a peer before it has introduced itself.
+                    if !is_handshake::<In>(&packet) {
+                        debug!("Ignoring packet from unknown peer {}", addr);
+                        continue;
+                    }
+
+                    let token = self.next_token();
+                    match Conn::from_udp(socket.clone(), addr) {
+                        Ok(conn) => self.insert(token, conn),
+                        Err(err) => {
+                            error!("Error accepting connection: {:?}", err);
+                            continue;
+                        }
+                    }
+                    token
+                }
+            };
+
+            if let Some(conn) = self.conns.get_mut(&token) {
+                conn.receive_packet(packet);
+                ready.push(token);
+            }
+        }
+    }
+
+    fn insert(&mut self, token: Token, mut conn: Conn<In, Out>) {
+        conn.set_max_message_size(self.max_message_size);
+        self.tokens.insert(conn.peer_addr, token);
+        self.conns.insert(token, conn);
+    }
+
+    fn next_token(&mut self) -> Token {
+        let token = Token(self.next_token);
+        self.next_token += 1;
+        token
+    }
+}
+
+/// Whether the packet opens a connection
+///
+/// That's the case, if it carries the first reliable message, and that's a
+/// handshake. The message's size is only limited by the packet here. The
+/// connection checks it against the maximum, once it's created.
+fn is_handshake<In>(packet: &Packet) -> bool
+where
+    In: Message + Delivery,
+{
+    match packet {
+        Packet::Reliable { seq: 0, message } => {
+            In::read(&mut message.clone(), udp::MAX_PACKET)
+                .ok()
+                .flatten()
+                .map(|message| message.is_handshake())
+                .unwrap_or(false)
+        }
+        _ => false,
+    }
+}
@@ -1,10 +1,16 @@
 pub mod client;
+pub mod clock;
 pub mod conn;
+pub mod delta;
+pub mod listener;
 pub mod msg;
 pub mod result;
+pub mod udp;
 
 pub use self::{
-    conn::Conn,
+    clock::ClockSync,
+    conn::{Conn, Transport},
+    listener::Listener,
     msg::Message,
     result::{Error, Result},
 };
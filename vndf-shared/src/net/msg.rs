@@ -1,67 +1,323 @@
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
 
 use postcard;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-use crate::{action::Action, data, world::features::players::PlayerId};
+use crate::{
+    action::Action,
+    data,
+    net::delta,
+    world::{
+        features::players::{IdentityToken, PlayerId},
+        Scalar,
+    },
+};
 
+/// Version of the protocol
+///
+/// Needs to be increased with every change that makes messages incompatible.
+pub const PROTOCOL_VERSION: u32 = 7;
+
+/// The optional protocol features this build supports
+pub const CAPABILITIES: Capabilities = Capabilities::DELTA_UPDATES;
+
+/// Default for the largest message that is accepted from a peer, in bytes
+pub const DEFAULT_MAX_SIZE: usize = 1024 * 1024;
+
+/// Size of the length prefix in front of each message
+const PREFIX_SIZE: usize = 4;
+
+/// A value that can be sent over the network
+///
+/// Each message is framed by its length, as a little-endian `u32`.
 pub trait Message: Send + Debug + DeserializeOwned + Serialize {
     fn write(&self, buf: &mut Vec<u8>) -> Result<(), Error> {
-        let mut buf2 = [0; 1024];
+        let serialized = postcard::to_stdvec(self)?;
+        let len = serialized.len() as u32;
 
-        let serialized = postcard::to_slice(self, &mut buf2)?;
-        buf.extend(serialized.iter());
+        buf.extend(&len.to_le_bytes());
+        buf.extend(serialized);
 
         Ok(())
     }
 
-    fn read(buf: &mut Vec<u8>) -> Result<Option<Self>, Error> {
-        let (message, num_bytes) = match take_from_bytes(&buf) {
-            Ok((message, num_bytes)) => (Some(message), num_bytes),
-            Err(Error::DeserializeUnexpectedEnd) => (None, 0),
-            Err(err) => return Err(err),
-        };
+    /// Read a message from the front of the buffer
+    ///
+    /// Returns `None`, if the buffer doesn't contain a full message yet. Fails
+    /// without waiting for the rest of the message, if it's going to be larger
+    /// than `max_size`.
+    fn read(buf: &mut Vec<u8>, max_size: usize) -> Result<Option<Self>, Error> {
+        match Self::decode(buf, max_size)? {
+            Some((message, size)) => {
+                buf.drain(..size);
+                Ok(Some(message))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Decode a message from the front of the buffer, without removing it
+    ///
+    /// Like `read`, but also returns the number of bytes the message took up.
+    /// Useful to walk through a large buffer, without moving its contents.
+    fn decode(
+        buf: &[u8],
+        max_size: usize,
+    ) -> Result<Option<(Self, usize)>, Error> {
+        if buf.len() < PREFIX_SIZE {
+            return Ok(None);
+        }
+
+        let mut prefix = [0; PREFIX_SIZE];
+        prefix.copy_from_slice(&buf[..PREFIX_SIZE]);
+        let len = u32::from_le_bytes(prefix) as usize;
+
+        if len > max_size {
+            return Err(Error::TooLarge { len, max_size });
+        }
+        if buf.len() < PREFIX_SIZE + len {
+            return Ok(None);
+        }
 
-        buf.drain(..num_bytes);
+        let message = postcard::from_bytes(&buf[PREFIX_SIZE..][..len])?;
 
-        Ok(message)
+        Ok(Some((message, PREFIX_SIZE + len)))
     }
 }
 
 impl<T> Message for T where T: Send + Debug + DeserializeOwned + Serialize {}
 
-#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+/// How a message needs to be delivered
+///
+/// Only makes a difference for transports that can lose packets.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Channel {
+    /// Arrives exactly once, in the order it was sent
+    ReliableOrdered,
+
+    /// Might get lost, and is dropped if it arrives after a newer one
+    UnreliableSequenced,
+}
+
+pub trait Delivery {
+    fn channel(&self) -> Channel;
+
+    /// Whether the message can open a connection
+    ///
+    /// Only makes a difference for transports without connections of their
+    /// own, which only accept a peer once it sent such a message.
+    fn is_handshake(&self) -> bool {
+        false
+    }
+}
+
+/// Messages from the client to the server
+///
+/// The handshake comes first and must never change, so a server can decode it
+/// and reject clients that speak another version of the protocol.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 pub enum FromClient {
-    Ping,
-    Hello { color: [f32; 3] },
+    Hello {
+        version: u32,
+        capabilities: Capabilities,
+        color: [f32; 3],
+    },
+
+    /// Carries the client's local time, which the server sends back
+    Ping {
+        sent: Scalar,
+    },
     Action(Action),
+
+    /// Acknowledges the batch of updates from the given tick
+    Ack {
+        tick: u64,
+    },
+
+    /// Like `Hello`, but takes over the player the token was issued for
+    ///
+    /// Falls back to creating a new player, if the token is unknown or has
+    /// expired. Comes after the original messages, so servers that don't know
+    /// about it fail to decode it, instead of mistaking it for something else.
+    Reconnect {
+        version: u32,
+        capabilities: Capabilities,
+        color: [f32; 3],
+        token: IdentityToken,
+    },
+
+    /// Asks for these components to be sent in full
+    ///
+    /// Sent when updates for them can't be decoded.
+    Resync(Vec<data::client::Handle>),
 }
 
+/// Messages from the server to the client
+///
+/// The answers to the handshake come first and must never change, so any
+/// client can decode them.
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 pub enum FromServer {
+    /// The handshake was successful
+    ///
+    /// Carries the capabilities that both sides support.
+    Accepted {
+        capabilities: Capabilities,
+    },
+    Rejected(Rejection),
+
     Ping,
-    Welcome(PlayerId),
-    UpdateComponent(data::client::Component),
+
+    /// Tells the client which player it controls
+    ///
+    /// The token can be used to reconnect to the same player later.
+    Welcome {
+        id: PlayerId,
+        token: IdentityToken,
+    },
+    /// All updates from one tick of the world
+    Updates {
+        tick: u64,
+        updates: Vec<delta::Update>,
+    },
     RemoveComponent(data::client::Handle),
-    InputHandled { seq: u64 },
+    InputHandled {
+        seq: u64,
+    },
     Diagnostics(data::server::Diagnostics),
+    TimeWarp(Scalar),
+
+    /// Answers a ping, with the tick the world was at
+    Pong {
+        sent: Scalar,
+        tick: u64,
+    },
+
+    /// The player's ship was destroyed
+    ///
+    /// A new one can be requested with `action::Kind::Respawn`, once
+    /// `respawn_in` seconds have passed.
+    ShipDestroyed {
+        respawn_in: Scalar,
+    },
 }
 
-fn take_from_bytes<'de, T>(buf: &'de [u8]) -> Result<(T, usize), Error>
-where
-    T: Deserialize<'de>,
-{
-    let (value, rest) = postcard::take_from_bytes(buf)?;
-    Ok((value, buf.len() - rest.len()))
+/// Optional protocol features, as a set of flags
+///
+/// Unknown flags are ignored, so a peer can announce features the other side
+/// doesn't know about.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct Capabilities(pub u64);
+
+impl Capabilities {
+    pub const NONE: Self = Self(0);
+
+    /// The client can decode updates that refer to an earlier version
+    pub const DELTA_UPDATES: Self = Self(0b1);
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+}
+
+/// Why the server refused a client
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub enum Rejection {
+    /// The client speaks a different version of the protocol
+    UnsupportedVersion { client: u32, server: u32 },
+
+    /// A player is already connected from the same address
+    AlreadyConnected,
+
+    /// Another client took over the player, using its identity token
+    TakenOver,
 }
 
-pub type Error = postcard::Error;
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnsupportedVersion { client, server } => write!(
+                f,
+                "Incompatible protocol version (client: {}, server: {})",
+                client, server
+            ),
+            Self::AlreadyConnected => {
+                write!(f, "Already connected from this address")
+            }
+            Self::TakenOver => {
+                write!(f, "Another client took over this player")
+            }
+        }
+    }
+}
+
+impl Delivery for FromClient {
+    fn channel(&self) -> Channel {
+        match self {
+            // A late ping would just mess up the clock synchronization, and a
+            // lost acknowledgement means the server falls back to an older
+            // baseline.
+            Self::Ping { .. } | Self::Ack { .. } => {
+                Channel::UnreliableSequenced
+            }
+            Self::Hello { .. }
+            | Self::Action(_)
+            | Self::Reconnect { .. }
+            | Self::Resync(_) => Channel::ReliableOrdered,
+        }
+    }
+
+    fn is_handshake(&self) -> bool {
+        matches!(self, Self::Hello { .. } | Self::Reconnect { .. })
+    }
+}
+
+impl Delivery for FromServer {
+    fn channel(&self) -> Channel {
+        match self {
+            // Updates are resent until the client acknowledges them, and only
+            // the latest diagnostics matter.
+            Self::Updates { .. } | Self::Diagnostics(_) | Self::Pong { .. } => {
+                Channel::UnreliableSequenced
+            }
+            Self::Accepted { .. }
+            | Self::Rejected(_)
+            | Self::Ping
+            | Self::Welcome { .. }
+            | Self::RemoveComponent(_)
+            | Self::InputHandled { .. }
+            | Self::TimeWarp(_)
+            | Self::ShipDestroyed { .. } => Channel::ReliableOrdered,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    Postcard(postcard::Error),
+
+    /// The message is larger than the configured maximum
+    TooLarge {
+        len: usize,
+        max_size: usize,
+    },
+}
+
+impl From<postcard::Error> for Error {
+    fn from(err: postcard::Error) -> Self {
+        Self::Postcard(err)
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use serde::{Deserialize, Serialize};
 
-    use super::Message as _;
+    use super::{Error, Message as _, DEFAULT_MAX_SIZE};
 
     #[derive(Clone, Copy, Debug, Deserialize, Serialize, Eq, PartialEq)]
     pub struct Ping(u64);
@@ -80,21 +336,70 @@ mod tests {
             .write(&mut buf)
             .expect("Failed to serialize message");
 
-        let deserialized_1 =
-            Ping::read(&mut buf).expect("Failed to deserialize message");
-        let deserialized_2 =
-            Ping::read(&mut buf).expect("Failed to deserialize message");
+        let deserialized_1 = Ping::read(&mut buf, DEFAULT_MAX_SIZE)
+            .expect("Failed to deserialize message");
+        let deserialized_2 = Ping::read(&mut buf, DEFAULT_MAX_SIZE)
+            .expect("Failed to deserialize message");
 
         assert_eq!(deserialized_1, Some(original_1));
         assert_eq!(deserialized_2, Some(original_2));
         assert_eq!(buf.len(), 0)
     }
 
+    #[test]
+    fn it_should_decode_messages_without_consuming_them() {
+        let mut buf = Vec::new();
+        Ping(1)
+            .write(&mut buf)
+            .expect("Failed to serialize message");
+        Ping(2)
+            .write(&mut buf)
+            .expect("Failed to serialize message");
+
+        let (deserialized_1, size) = Ping::decode(&buf, DEFAULT_MAX_SIZE)
+            .expect("Failed to deserialize message")
+            .unwrap();
+        let (deserialized_2, _) = Ping::decode(&buf[size..], DEFAULT_MAX_SIZE)
+            .expect("Failed to deserialize message")
+            .unwrap();
+
+        assert_eq!(deserialized_1, Ping(1));
+        assert_eq!(deserialized_2, Ping(2));
+        assert_eq!(size, buf.len() / 2);
+    }
+
     #[test]
     fn it_should_return_none_if_buffer_is_empty() {
         let mut buf = Vec::new();
-        let deserialized =
-            Ping::read(&mut buf).expect("Failed to deserialize message");
+        let deserialized = Ping::read(&mut buf, DEFAULT_MAX_SIZE)
+            .expect("Failed to deserialize message");
         assert_eq!(deserialized, None);
     }
+
+    #[test]
+    fn it_should_wait_for_the_rest_of_a_message() {
+        let mut buf = Vec::new();
+        Ping(1)
+            .write(&mut buf)
+            .expect("Failed to serialize message");
+
+        let mut partial = buf[..buf.len() - 1].to_vec();
+        let deserialized = Ping::read(&mut partial, DEFAULT_MAX_SIZE)
+            .expect("Failed to deserialize message");
+        assert_eq!(deserialized, None);
+    }
+
+    #[test]
+    fn it_should_reject_messages_larger_than_the_maximum() {
+        let mut buf = Vec::new();
+        vec![0u8; 64]
+            .write(&mut buf)
+            .expect("Failed to serialize message");
+
+        // Just the length prefix is enough to know the message is too large.
+        buf.truncate(4);
+
+        let result = Vec::<u8>::read(&mut buf, 32);
+        assert!(matches!(result, Err(Error::TooLarge { max_size: 32, .. })));
+    }
 }
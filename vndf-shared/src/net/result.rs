@@ -1,4 +1,4 @@
-use std::{io, sync::mpsc};
+use std::io;
 
 use crate::net::msg;
 
@@ -9,12 +9,11 @@ pub enum Error {
     Io(io::Error),
     Msg(msg::Error),
 
-    /// Another thread failed
-    ///
-    /// We don't have any more information, we just know that the thread has
-    /// dropped its end of the channel we've been using to communicate with it.
-    /// The thread should have logged an error though.
-    ThreadFailed,
+    /// The peer has closed the connection
+    Disconnected,
+
+    /// The peer doesn't keep up with the messages sent to it
+    Congested,
 }
 
 impl Eq for Error {}
@@ -24,6 +23,8 @@ impl PartialEq for Error {
         match (self, rhs) {
             (Error::Io(s), Error::Io(o)) => s.kind() == o.kind(),
             (Error::Msg(s), Error::Msg(o)) => s == o,
+            (Error::Disconnected, Error::Disconnected) => true,
+            (Error::Congested, Error::Congested) => true,
             _ => false,
         }
     }
@@ -40,9 +41,3 @@ impl From<msg::Error> for Error {
         Self::Msg(err)
     }
 }
-
-impl<T> From<mpsc::SendError<T>> for Error {
-    fn from(_: mpsc::SendError<T>) -> Self {
-        Self::ThreadFailed
-    }
-}
@@ -0,0 +1,652 @@
+//! Transport on top of UDP, with a thin reliability layer
+//!
+//! Every message is sent in its own datagram, on one of two channels (see
+//! `msg::Channel`). Reliable messages are resent until they are acknowledged,
+//! and delivered in order. Unreliable messages are delivered only if they are
+//! newer than any unreliable message that arrived before them, so a lost
+//! packet never holds up the ones behind it.
+//!
+//! Messages that don't fit into a single datagram are split into fragments,
+//! which are put back together on the receiving side. If any fragment of an
+//! unreliable message gets lost, the whole message is lost.
+
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    io, mem,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::net::{
+    self,
+    msg::{self, Channel},
+};
+
+/// Largest packet that is sent or accepted
+pub const MAX_PACKET: usize = 65_000;
+
+/// Largest part of a message that is sent in a single packet
+///
+/// Leaves room for the rest of the packet.
+pub const MAX_FRAGMENT: usize = 64_000;
+
+/// Number of fragments a message can be split into
+pub const MAX_FRAGMENTS: usize = 32;
+
+/// Largest message that can be sent
+pub const MAX_MESSAGE: usize = MAX_FRAGMENT * MAX_FRAGMENTS;
+
+/// Number of reliable messages, starting with the next one to be delivered,
+/// whose fragments are buffered
+///
+/// Fragments of later messages are dropped, and arrive again when they are
+/// resent.
+pub const MAX_PARTIAL: u64 = 16;
+
+/// Time after which an unacknowledged reliable message is sent again
+pub const RESEND_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Time without sending anything, after which a keep-alive is sent
+pub const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Time without receiving anything, after which the peer is considered gone
+pub const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Number of unacknowledged reliable messages, beyond which the connection
+/// fails
+pub const MAX_UNACKED: usize = 1024;
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub enum Packet {
+    Reliable {
+        seq: u64,
+        message: Vec<u8>,
+    },
+    Unreliable {
+        seq: u64,
+        message: Vec<u8>,
+    },
+
+    /// Acknowledges all reliable messages before `next`
+    ///
+    /// Also sent as a keep-alive.
+    Ack {
+        next: u64,
+    },
+
+    /// The sender is closing the connection
+    Disconnect,
+
+    /// Part of a reliable message that doesn't fit into a single packet
+    ///
+    /// All fragments of a message share its sequence number, and are resent
+    /// together until the message is acknowledged.
+    ReliableFragment {
+        seq: u64,
+        index: u32,
+        count: u32,
+        fragment: Vec<u8>,
+    },
+
+    /// Part of an unreliable message that doesn't fit into a single packet
+    UnreliableFragment {
+        seq: u64,
+        index: u32,
+        count: u32,
+        fragment: Vec<u8>,
+    },
+
+    /// Like `Ack`, but also acknowledges a single fragment of a message
+    ///
+    /// Fragments that have arrived don't need to be resent. Otherwise, the
+    /// same fragments might get lost every time, if the message is larger
+    /// than the receiver's socket buffer.
+    FragmentAck {
+        next: u64,
+        seq: u64,
+        index: u32,
+    },
+}
+
+impl Packet {
+    pub(crate) fn encode(&self) -> net::Result<Vec<u8>> {
+        let packet = postcard::to_stdvec(self).map_err(msg::Error::from)?;
+        if packet.len() > MAX_PACKET {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Packet too large",
+            )
+            .into());
+        }
+        Ok(packet)
+    }
+
+    pub(crate) fn decode(packet: &[u8]) -> net::Result<Self> {
+        Ok(postcard::from_bytes(packet).map_err(msg::Error::from)?)
+    }
+}
+
+/// The reliability layer of a single connection
+///
+/// Doesn't do any I/O itself. It just tells the caller which packets to send,
+/// and which messages were received.
+pub struct Channels {
+    next_reliable: u64,
+    next_unreliable: u64,
+
+    /// Reliable messages that were sent, but not acknowledged yet
+    unacked: VecDeque<Unacked>,
+
+    /// The next reliable message to deliver
+    expected: u64,
+
+    /// Reliable messages that arrived ahead of `expected`
+    buffered: BTreeMap<u64, Vec<u8>>,
+
+    /// Reliable messages of which only some fragments have arrived
+    partial_reliable: BTreeMap<u64, Partial>,
+
+    latest_unreliable: Option<u64>,
+
+    /// The newest unreliable message of which only some fragments have
+    /// arrived
+    partial_unreliable: Option<(u64, Partial)>,
+
+    last_sent: Instant,
+    last_received: Instant,
+}
+
+impl Channels {
+    pub fn new() -> Self {
+        let now = Instant::now();
+
+        Self {
+            next_reliable: 0,
+            next_unreliable: 0,
+            unacked: VecDeque::new(),
+            expected: 0,
+            buffered: BTreeMap::new(),
+            partial_reliable: BTreeMap::new(),
+            latest_unreliable: None,
+            partial_unreliable: None,
+            last_sent: now,
+            last_received: now,
+        }
+    }
+
+    /// Wrap a serialized message into packets
+    ///
+    /// Returns a single packet, unless the message needs to be split into
+    /// fragments. Fails, if the message is larger than `MAX_MESSAGE`.
+    pub fn send(
+        &mut self,
+        channel: Channel,
+        message: Vec<u8>,
+    ) -> io::Result<Vec<Packet>> {
+        if message.len() > MAX_MESSAGE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Message too large",
+            ));
+        }
+
+        self.last_sent = Instant::now();
+
+        let packets = match channel {
+            Channel::ReliableOrdered => {
+                let seq = self.next_reliable;
+                self.next_reliable += 1;
+
+                self.unacked.push_back(Unacked {
+                    seq,
+                    message: message.clone(),
+                    sent: self.last_sent,
+                    fragments: HashSet::new(),
+                });
+                reliable(seq, message)
+            }
+            Channel::UnreliableSequenced => {
+                let seq = self.next_unreliable;
+                self.next_unreliable += 1;
+
+                fragment(message, |index, count, fragment| {
+                    Packet::UnreliableFragment {
+                        seq,
+                        index,
+                        count,
+                        fragment,
+                    }
+                })
+                .unwrap_or_else(|message| {
+                    vec![Packet::Unreliable { seq, message }]
+                })
+            }
+        };
+
+        Ok(packets)
+    }
+
+    /// Handle a received packet
+    pub fn receive(&mut self, packet: Packet) -> Received {
+        self.last_received = Instant::now();
+
+        let mut received = Received {
+            messages: Vec::new(),
+            reply: None,
+            disconnected: false,
+        };
+
+        match packet {
+            Packet::Reliable { seq, message } => {
+                if seq >= self.expected {
+                    self.buffered.insert(seq, message);
+                }
+                self.deliver_reliable(&mut received);
+            }
+            Packet::ReliableFragment {
+                seq,
+                index,
+                count,
+                fragment,
+            } => {
+                // Limits how much memory a peer can make us use.
+                let window = self.expected + MAX_PARTIAL;
+                let buffered = seq >= self.expected && seq < window;
+                if buffered {
+                    let message = self
+                        .partial_reliable
+                        .entry(seq)
+                        .or_insert_with(|| Partial::new(count))
+                        .add(index, count, fragment);
+                    if let Some(message) = message {
+                        self.partial_reliable.remove(&seq);
+                        self.buffered.insert(seq, message);
+                    }
+                }
+                self.deliver_reliable(&mut received);
+
+                if buffered && seq >= self.expected {
+                    received.reply = Some(Packet::FragmentAck {
+                        next: self.expected,
+                        seq,
+                        index,
+                    });
+                }
+            }
+            Packet::Unreliable { seq, message } => {
+                if self.is_newer_unreliable(seq) {
+                    self.latest_unreliable = Some(seq);
+                    received.messages.push(message);
+                }
+            }
+            Packet::UnreliableFragment {
+                seq,
+                index,
+                count,
+                fragment,
+            } => {
+                if self.is_newer_unreliable(seq) {
+                    // Only the newest message is of interest, so any older one
+                    // that is still incomplete can be dropped.
+                    let partial = match &mut self.partial_unreliable {
+                        Some((partial_seq, partial)) if *partial_seq == seq => {
+                            partial
+                        }
+                        Some((partial_seq, _)) if *partial_seq > seq => {
+                            return received;
+                        }
+                        partial => {
+                            &mut partial.insert((seq, Partial::new(count))).1
+                        }
+                    };
+
+                    if let Some(message) = partial.add(index, count, fragment) {
+                        self.partial_unreliable = None;
+                        self.latest_unreliable = Some(seq);
+                        received.messages.push(message);
+                    }
+                }
+            }
+            Packet::Ack { next } => {
+                self.acknowledge(next);
+            }
+            Packet::FragmentAck { next, seq, index } => {
+                self.acknowledge(next);
+
+                let unacked =
+                    self.unacked.iter_mut().find(|unacked| unacked.seq == seq);
+                if let Some(unacked) = unacked {
+                    unacked.fragments.insert(index);
+                }
+            }
+            Packet::Disconnect => {
+                received.disconnected = true;
+            }
+        }
+
+        received
+    }
+
+    /// Returns the packets that need to be sent again, or to keep the
+    /// connection alive
+    pub fn poll(&mut self, now: Instant) -> Vec<Packet> {
+        let mut packets = Vec::new();
+
+        for unacked in &mut self.unacked {
+            if now.duration_since(unacked.sent) >= RESEND_INTERVAL {
+                unacked.sent = now;
+
+                let fragments = &unacked.fragments;
+                let resent = reliable(unacked.seq, unacked.message.clone())
+                    .into_iter()
+                    .filter(|packet| match packet {
+                        Packet::ReliableFragment { index, .. } => {
+                            !fragments.contains(index)
+                        }
+                        _ => true,
+                    });
+                packets.extend(resent);
+            }
+        }
+
+        if packets.is_empty()
+            && now.duration_since(self.last_sent) >= KEEPALIVE_INTERVAL
+        {
+            packets.push(Packet::Ack {
+                next: self.expected,
+            });
+        }
+        if !packets.is_empty() {
+            self.last_sent = now;
+        }
+
+        packets
+    }
+
+    /// Number of reliable messages that are waiting for acknowledgement
+    pub fn unacked(&self) -> usize {
+        self.unacked.len()
+    }
+
+    /// Whether nothing has been received for too long
+    pub fn timed_out(&self, now: Instant) -> bool {
+        now.duration_since(self.last_received) >= TIMEOUT
+    }
+
+    /// Forget about all reliable messages before `next`
+    fn acknowledge(&mut self, next: u64) {
+        while let Some(unacked) = self.unacked.front() {
+            if unacked.seq >= next {
+                break;
+            }
+            self.unacked.pop_front();
+        }
+    }
+
+    fn deliver_reliable(&mut self, received: &mut Received) {
+        while let Some(message) = self.buffered.remove(&self.expected) {
+            received.messages.push(message);
+            self.expected += 1;
+        }
+        self.partial_reliable = self.partial_reliable.split_off(&self.expected);
+
+        // Also acknowledge duplicates. The sender is obviously still waiting
+        // for an acknowledgement.
+        received.reply = Some(Packet::Ack {
+            next: self.expected,
+        });
+    }
+
+    fn is_newer_unreliable(&self, seq: u64) -> bool {
+        self.latest_unreliable
+            .map(|latest| seq > latest)
+            .unwrap_or(true)
+    }
+}
+
+impl Default for Channels {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A reliable message that was sent, but not acknowledged yet
+struct Unacked {
+    seq: u64,
+    message: Vec<u8>,
+    sent: Instant,
+
+    /// The fragments that are known to have arrived
+    fragments: HashSet<u32>,
+}
+
+/// The fragments of a message that have arrived so far
+struct Partial {
+    fragments: Vec<Option<Vec<u8>>>,
+    missing: usize,
+}
+
+impl Partial {
+    fn new(count: u32) -> Self {
+        // Invalid counts are rejected when adding fragments.
+        let count = (count as usize).min(MAX_FRAGMENTS);
+
+        Self {
+            fragments: vec![None; count],
+            missing: count,
+        }
+    }
+
+    /// Add a fragment, returning the whole message once it's complete
+    ///
+    /// Ignores fragments that don't fit the ones that arrived before.
+    fn add(
+        &mut self,
+        index: u32,
+        count: u32,
+        fragment: Vec<u8>,
+    ) -> Option<Vec<u8>> {
+        if count as usize != self.fragments.len() {
+            return None;
+        }
+
+        let slot = self.fragments.get_mut(index as usize)?;
+        if slot.is_none() {
+            *slot = Some(fragment);
+            self.missing -= 1;
+        }
+        if self.missing > 0 {
+            return None;
+        }
+
+        let message = mem::take(&mut self.fragments)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .collect();
+        Some(message)
+    }
+}
+
+/// The packets for a reliable message
+fn reliable(seq: u64, message: Vec<u8>) -> Vec<Packet> {
+    fragment(message, |index, count, fragment| Packet::ReliableFragment {
+        seq,
+        index,
+        count,
+        fragment,
+    })
+    .unwrap_or_else(|message| vec![Packet::Reliable { seq, message }])
+}
+
+/// Split a message into fragment packets, if it doesn't fit into one packet
+///
+/// Hands the message back, if it fits.
+fn fragment(
+    message: Vec<u8>,
+    packet: impl Fn(u32, u32, Vec<u8>) -> Packet,
+) -> Result<Vec<Packet>, Vec<u8>> {
+    if message.len() <= MAX_FRAGMENT {
+        return Err(message);
+    }
+
+    let count = message.len().div_ceil(MAX_FRAGMENT) as u32;
+    let packets = message
+        .chunks(MAX_FRAGMENT)
+        .enumerate()
+        .map(|(index, fragment)| packet(index as u32, count, fragment.to_vec()))
+        .collect();
+
+    Ok(packets)
+}
+
+pub struct Received {
+    /// Serialized messages, ready to be delivered
+    pub messages: Vec<Vec<u8>>,
+
+    /// A packet that needs to be sent back
+    pub reply: Option<Packet>,
+
+    /// The peer has closed the connection
+    pub disconnected: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use crate::net::msg::Channel;
+
+    use super::{Channels, Packet, MAX_FRAGMENT, MAX_PACKET, RESEND_INTERVAL};
+
+    #[test]
+    fn reliable_messages_should_be_delivered_in_order_and_once() {
+        let mut sender = Channels::new();
+        let mut receiver = Channels::new();
+
+        let first = send(&mut sender, Channel::ReliableOrdered, vec![1]);
+        let second = send(&mut sender, Channel::ReliableOrdered, vec![2]);
+
+        // The first packet is lost, the second one arrives.
+        let received = receiver.receive(second.clone());
+        assert!(received.messages.is_empty());
+
+        let resent = sender.poll(Instant::now() + RESEND_INTERVAL);
+        assert_eq!(resent, vec![first, second.clone()]);
+
+        let mut delivered = Vec::new();
+        let mut acks = Vec::new();
+        for packet in resent {
+            let received = receiver.receive(packet);
+            delivered.extend(received.messages);
+            acks.extend(received.reply);
+        }
+        assert_eq!(delivered, vec![vec![1], vec![2]]);
+
+        for ack in acks {
+            sender.receive(ack);
+        }
+        let resent = sender.poll(Instant::now() + RESEND_INTERVAL);
+        assert!(!resent.contains(&second));
+
+        // A late duplicate must not be delivered again.
+        assert!(receiver.receive(second).messages.is_empty());
+    }
+
+    #[test]
+    fn unreliable_messages_should_be_dropped_if_outdated() {
+        let mut sender = Channels::new();
+        let mut receiver = Channels::new();
+
+        let first = send(&mut sender, Channel::UnreliableSequenced, vec![1]);
+        let second = send(&mut sender, Channel::UnreliableSequenced, vec![2]);
+
+        assert_eq!(receiver.receive(second).messages, vec![vec![2]]);
+        assert!(receiver.receive(first).messages.is_empty());
+
+        let resent = sender.poll(Instant::now() + RESEND_INTERVAL);
+        assert!(!resent
+            .iter()
+            .any(|packet| matches!(packet, Packet::Unreliable { .. })));
+    }
+
+    #[test]
+    fn large_messages_should_be_fragmented_and_reassembled() {
+        let mut sender = Channels::new();
+        let mut receiver = Channels::new();
+
+        let message: Vec<u8> =
+            (0..MAX_FRAGMENT * 2 + 1).map(|i| i as u8).collect();
+
+        for &channel in
+            &[Channel::ReliableOrdered, Channel::UnreliableSequenced]
+        {
+            let mut packets = sender.send(channel, message.clone()).unwrap();
+            assert_eq!(packets.len(), 3);
+            for packet in &packets {
+                assert!(packet.encode().unwrap().len() <= MAX_PACKET);
+            }
+
+            // Fragments can arrive in any order.
+            packets.reverse();
+            let mut delivered = Vec::new();
+            for packet in packets {
+                delivered.extend(receiver.receive(packet).messages);
+            }
+            assert_eq!(delivered, vec![message.clone()]);
+        }
+    }
+
+    #[test]
+    fn incomplete_unreliable_messages_should_be_dropped() {
+        let mut sender = Channels::new();
+        let mut receiver = Channels::new();
+
+        let message = vec![0; MAX_FRAGMENT + 1];
+        let first = sender
+            .send(Channel::UnreliableSequenced, message.clone())
+            .unwrap();
+        let second = sender
+            .send(Channel::UnreliableSequenced, message.clone())
+            .unwrap();
+
+        // Only part of the first message arrives before the second one.
+        assert!(receiver.receive(first[0].clone()).messages.is_empty());
+        for packet in second {
+            receiver.receive(packet);
+        }
+        assert!(receiver.receive(first[1].clone()).messages.is_empty());
+    }
+
+    #[test]
+    fn only_lost_fragments_should_be_sent_again() {
+        let mut sender = Channels::new();
+        let mut receiver = Channels::new();
+
+        let message = vec![0; MAX_FRAGMENT * 2 + 1];
+        let packets = sender
+            .send(Channel::ReliableOrdered, message.clone())
+            .unwrap();
+
+        // The last fragment is lost.
+        for packet in &packets[..2] {
+            let received = receiver.receive(packet.clone());
+            sender.receive(received.reply.unwrap());
+        }
+
+        let resent = sender.poll(Instant::now() + RESEND_INTERVAL);
+        assert_eq!(resent, vec![packets[2].clone()]);
+        assert_eq!(receiver.receive(resent[0].clone()).messages, vec![message]);
+    }
+
+    fn send(
+        channels: &mut Channels,
+        channel: Channel,
+        message: Vec<u8>,
+    ) -> Packet {
+        let mut packets = channels.send(channel, message).unwrap();
+        assert_eq!(packets.len(), 1);
+        packets.remove(0)
+    }
+}
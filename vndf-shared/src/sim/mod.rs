@@ -0,0 +1,5 @@
+pub mod script;
+pub mod simulation;
+pub mod trace;
+
+pub use self::{script::Script, simulation::Simulation, trace::Trace};
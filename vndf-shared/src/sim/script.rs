@@ -0,0 +1,107 @@
+use std::{
+    fs::File,
+    io::{self, prelude::*},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::action;
+
+use super::{Simulation, Trace};
+
+/// A timeline of player events that can be played back in a `Simulation`
+///
+/// Scripts are meant to be written by hand, so they are loaded from TOML.
+/// Players are identified by a number, which the simulation turns into a
+/// (fake) network address.
+///
+/// Example:
+/// ```toml
+/// ticks = 120
+/// seed  = 7
+///
+/// [[events]]
+/// tick   = 0
+/// player = 1
+/// kind   = { Connect = { color = [1.0, 1.0, 0.0] } }
+///
+/// [[events]]
+/// tick   = 10
+/// player = 1
+/// kind   = { Input = { Thrust = true } }
+/// ```
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct Script {
+    /// The number of ticks to simulate
+    pub ticks: u64,
+
+    /// The seed for the world's random number generator
+    #[serde(default)]
+    pub seed: u64,
+
+    #[serde(default)]
+    pub events: Vec<Event>,
+}
+
+impl Script {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let mut s = String::new();
+        File::open(path)?.read_to_string(&mut s)?;
+
+        Self::from_toml(&s)
+    }
+
+    pub fn from_toml(s: &str) -> Result<Self, Error> {
+        let script = toml::from_str(s)?;
+        Ok(script)
+    }
+
+    pub fn run(&self) -> Trace {
+        Simulation::new(self.seed).run(self)
+    }
+
+    /// Returns the events for the given tick, in the order they were defined
+    pub fn events_at(&self, tick: u64) -> impl Iterator<Item = &Event> + '_ {
+        self.events.iter().filter(move |event| event.tick == tick)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct Event {
+    pub tick: u64,
+    pub player: u16,
+    pub kind: EventKind,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub enum EventKind {
+    Connect {
+        color: [f32; 3],
+    },
+
+    /// Connect again, presenting the token the player was issued before
+    Reconnect {
+        color: [f32; 3],
+    },
+    Disconnect,
+    Input(action::Kind),
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Toml(toml::de::Error),
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for Error {
+    fn from(err: toml::de::Error) -> Self {
+        Self::Toml(err)
+    }
+}
@@ -0,0 +1,179 @@
+use std::{
+    collections::HashMap,
+    net::{Ipv6Addr, SocketAddr},
+};
+
+use crate::{
+    action::{self, Action},
+    world::{
+        self,
+        features::{
+            base::Update,
+            players::{
+                IdentityToken, PlayerConnected, PlayerDisconnected, PlayerInput,
+            },
+        },
+        FRAME_TIME,
+    },
+};
+
+use super::{
+    script::{EventKind, Script},
+    trace::{self, Trace},
+};
+
+/// Drives a `world::State` without any networking
+///
+/// Pushes the same events into the world that the server would, and collects
+/// its output into a `Trace`, which can then be inspected.
+pub struct Simulation {
+    state: world::State,
+    next_seq: u64,
+
+    /// The tokens that were issued to each player
+    tokens: HashMap<u16, IdentityToken>,
+
+    /// Tokens only need to be unpredictable on a real server. Here, they're
+    /// counted up instead, to keep traces reproducible.
+    next_token: u64,
+}
+
+impl Simulation {
+    pub fn new(seed: u64) -> Self {
+        Self::from_state(world::State::new(seed))
+    }
+
+    /// Continue the simulation of an existing world (e.g. a restored one)
+    pub fn from_state(state: world::State) -> Self {
+        Self {
+            state,
+            next_seq: 0,
+            tokens: HashMap::new(),
+            next_token: 0,
+        }
+    }
+
+    /// Continue with another world, like a restarted server would
+    ///
+    /// The players keep the tokens that were issued to them, so they can
+    /// reconnect.
+    pub fn restart(&mut self, state: world::State) {
+        self.state = state;
+    }
+
+    /// Returns the fake network address used for the given player
+    pub fn address(player: u16) -> SocketAddr {
+        SocketAddr::new(Ipv6Addr::LOCALHOST.into(), player)
+    }
+
+    pub fn state(&mut self) -> &mut world::State {
+        &mut self.state
+    }
+
+    pub fn connect(&mut self, player: u16, color: [f32; 3]) {
+        let addr = Self::address(player);
+        let new_token = self.issue_token();
+        self.state.player_connected().push(PlayerConnected {
+            addr,
+            color,
+            token: None,
+            new_token,
+        });
+    }
+
+    /// Connect with the token that was last issued to the player
+    ///
+    /// Connects as a new player, if no token was issued yet.
+    pub fn reconnect(&mut self, player: u16, color: [f32; 3]) {
+        let addr = Self::address(player);
+        let token = self.tokens.get(&player).copied();
+        let new_token = self.issue_token();
+        self.state.player_connected().push(PlayerConnected {
+            addr,
+            color,
+            token,
+            new_token,
+        });
+    }
+
+    fn issue_token(&mut self) -> IdentityToken {
+        self.next_token += 1;
+        IdentityToken(self.next_token)
+    }
+
+    pub fn disconnect(&mut self, player: u16) {
+        let addr = Self::address(player);
+        self.state
+            .player_disconnected()
+            .push(PlayerDisconnected { addr });
+    }
+
+    pub fn input(&mut self, player: u16, kind: action::Kind) {
+        let addr = Self::address(player);
+        let action = Action {
+            seq: self.next_seq,
+            kind,
+        };
+        self.next_seq += 1;
+
+        self.state.player_input().push(PlayerInput { addr, action });
+    }
+
+    /// Advances the simulation by one tick
+    ///
+    /// Any events pushed since the last step are handled in this one.
+    pub fn step(&mut self) -> trace::Tick {
+        let mut tick = trace::Tick::new(self.state.tick());
+
+        self.state.update().push(Update { dt: FRAME_TIME });
+        self.state.dispatch();
+
+        for event in self.state.player_created().ready() {
+            tick.players_created.push((event.addr.port(), event.id));
+            tick.tokens_issued.push((event.addr.port(), event.token));
+            self.tokens.insert(event.addr.port(), event.token);
+        }
+        for event in self.state.player_reconnected().ready() {
+            tick.players_reconnected.push((event.addr.port(), event.id));
+        }
+        for event in self.state.ship_destroyed().ready() {
+            tick.ships_destroyed.push((event.addr.port(), event.id));
+        }
+        for event in self.state.removals().ready() {
+            tick.removals.push(event.handle);
+        }
+        tick.updates.extend(self.state.updates());
+        for event in self.state.input_handled().ready() {
+            tick.inputs_handled.push((event.addr.port(), event.seq));
+        }
+
+        tick
+    }
+
+    pub fn run(mut self, script: &Script) -> Trace {
+        let mut trace = Trace::new();
+
+        for tick in 0..script.ticks {
+            for event in script.events_at(tick) {
+                match event.kind {
+                    EventKind::Connect { color } => {
+                        self.connect(event.player, color);
+                    }
+                    EventKind::Reconnect { color } => {
+                        self.reconnect(event.player, color);
+                    }
+                    EventKind::Disconnect => {
+                        self.disconnect(event.player);
+                    }
+                    EventKind::Input(kind) => {
+                        self.input(event.player, kind);
+                    }
+                }
+            }
+
+            trace.ticks.push(self.step());
+        }
+
+        trace
+    }
+}
@@ -0,0 +1,80 @@
+use crate::{
+    data,
+    world::features::players::{IdentityToken, PlayerId},
+};
+
+/// The output of a `Simulation`, tick by tick
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Trace {
+    pub ticks: Vec<Tick>,
+}
+
+impl Trace {
+    pub fn new() -> Self {
+        Self { ticks: Vec::new() }
+    }
+
+    /// Returns the id the world assigned to the given player, if any
+    pub fn player_id(&self, player: u16) -> Option<PlayerId> {
+        self.ticks
+            .iter()
+            .flat_map(|tick| &tick.players_created)
+            .find(|(p, _)| *p == player)
+            .map(|&(_, id)| id)
+    }
+
+    /// Returns the token that was issued to the given player, if any
+    pub fn token(&self, player: u16) -> Option<IdentityToken> {
+        self.ticks
+            .iter()
+            .flat_map(|tick| &tick.tokens_issued)
+            .find(|(p, _)| *p == player)
+            .map(|&(_, token)| token)
+    }
+
+    pub fn last(&self) -> Option<&Tick> {
+        self.ticks.last()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tick {
+    pub tick: u64,
+
+    /// Players created during this tick, as `(player, id)`
+    pub players_created: Vec<(u16, PlayerId)>,
+
+    /// Tokens issued to the players created during this tick, as
+    /// `(player, token)`
+    pub tokens_issued: Vec<(u16, IdentityToken)>,
+
+    /// Players that took over an existing player, as `(player, id)`
+    pub players_reconnected: Vec<(u16, PlayerId)>,
+
+    /// Players whose ship was destroyed during this tick, as `(player, id)`
+    pub ships_destroyed: Vec<(u16, PlayerId)>,
+
+    /// All components in the world, after this tick was dispatched
+    pub updates: Vec<data::client::Component>,
+
+    /// All components removed during this tick
+    pub removals: Vec<data::client::Handle>,
+
+    /// Inputs that were handled during this tick, as `(player, seq)`
+    pub inputs_handled: Vec<(u16, u64)>,
+}
+
+impl Tick {
+    pub fn new(tick: u64) -> Self {
+        Self {
+            tick,
+            players_created: Vec::new(),
+            tokens_issued: Vec::new(),
+            players_reconnected: Vec::new(),
+            ships_destroyed: Vec::new(),
+            updates: Vec::new(),
+            removals: Vec::new(),
+            inputs_handled: Vec::new(),
+        }
+    }
+}
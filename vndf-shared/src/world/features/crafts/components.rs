@@ -1,11 +1,15 @@
 use serde::{Deserialize, Serialize};
 use toadster::{store, Handle};
 
-use crate::world::{
-    health::Health,
-    math::{Scalar, Vec2},
-    physics::Body,
-    players::PlayerId,
+use crate::{
+    data::{self, Remap as _},
+    world::{
+        features::trajectory::Burn,
+        health::Health,
+        math::{Scalar, Vec2},
+        physics::Body,
+        players::PlayerId,
+    },
 };
 
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
@@ -17,9 +21,43 @@ pub struct Craft {
     pub engine_on: bool,
     pub thrust: Scalar,
     pub owner: PlayerId,
+
+    /// Planned burns, ordered by the time they start
+    pub maneuvers: Vec<Maneuver>,
+
+    /// Whether the weapon fires whenever it's ready
+    pub firing: bool,
+
+    /// Number of shots left
+    pub ammo: u32,
+
+    /// Time until the weapon is ready to fire again, in seconds
+    pub cooldown: Scalar,
 }
 
+data::diff::diff!(Craft {
+    body,
+    fuel,
+    health,
+    engine_on,
+    thrust,
+    owner,
+    maneuvers,
+    firing,
+    ammo,
+    cooldown
+});
+
 impl Craft {
+    /// Time between two shots, in seconds
+    pub const FIRE_INTERVAL: Scalar = 0.5;
+
+    /// Maximum number of maneuvers that can be planned at once
+    ///
+    /// The maneuvers are sent to every client that can see the craft, so their
+    /// number needs to be limited.
+    pub const MAX_MANEUVERS: usize = 16;
+
     pub fn to_weak(&self) -> Self {
         Self {
             body: self.body.as_weak(),
@@ -28,7 +66,60 @@ impl Craft {
             engine_on: self.engine_on.clone(),
             thrust: self.thrust.clone(),
             owner: self.owner.clone(),
+            maneuvers: self.maneuvers.clone(),
+            firing: self.firing,
+            ammo: self.ammo,
+            cooldown: self.cooldown,
+        }
+    }
+
+    pub fn remap(&mut self, handles: &data::server::HandleMap) -> Option<()> {
+        self.body = handles.remap(&self.body)?;
+        self.fuel = handles.remap(&self.fuel)?;
+        self.health = handles.remap(&self.health)?;
+        Some(())
+    }
+
+    /// Plan a maneuver
+    ///
+    /// Ignores invalid maneuvers, and any beyond `MAX_MANEUVERS`.
+    pub fn schedule(&mut self, maneuver: Maneuver) {
+        if !maneuver.is_valid() || self.maneuvers.len() >= Self::MAX_MANEUVERS {
+            return;
+        }
+
+        let i = self
+            .maneuvers
+            .iter()
+            .take_while(|m| m.delay <= maneuver.delay)
+            .count();
+        self.maneuvers.insert(i, maneuver);
+    }
+
+    /// The planned maneuvers, as burns that can be used for prediction
+    pub fn burns(&self, body: &Body) -> Vec<Burn> {
+        let acc = self.thrust / body.mass;
+        self.maneuvers
+            .iter()
+            .map(|maneuver| maneuver.to_burn(acc))
+            .collect()
+    }
+
+    /// Count down the weapon's cooldown, and fire, if possible
+    ///
+    /// Returns whether a shot was fired. Creating the projectile is up to the
+    /// caller.
+    pub fn update_weapon(&mut self, dt: Scalar) -> bool {
+        self.cooldown = Scalar::max(self.cooldown - dt, 0.0);
+
+        if !self.firing || self.cooldown > 0.0 || self.ammo == 0 {
+            return false;
         }
+
+        self.ammo -= 1;
+        self.cooldown = Self::FIRE_INTERVAL;
+
+        true
     }
 
     pub fn apply_thrust(
@@ -40,27 +131,101 @@ impl Craft {
         let body = bodies.get_mut(&self.body)?;
         let fuel = fuels.get_mut(&self.fuel)?;
 
-        let force = if self.engine_on && fuel.0 > 0.0 {
-            let max_fuel_used = self.thrust * dt;
+        // During an FTL jump, the body covers more time per step than the rest
+        // of the world, and so does the craft.
+        let dt = dt * body.time_factor;
+
+        for maneuver in &mut self.maneuvers {
+            maneuver.delay -= dt;
+        }
+
+        // A maneuver that is due overrides manual control, until the planned
+        // change in velocity has been achieved.
+        let maneuver = self.maneuvers.first_mut().filter(|m| m.delay <= 0.0);
+        let (engine_on, max_thrust) = match &maneuver {
+            Some(maneuver) => {
+                body.dir = maneuver.delta_v.normalize();
+
+                let remaining = maneuver.delta_v.length() * body.mass / dt;
+                (true, Scalar::min(self.thrust, remaining))
+            }
+            None => (self.engine_on, self.thrust),
+        };
+
+        let force = if engine_on && fuel.0 > 0.0 {
+            let max_fuel_used = max_thrust * dt;
             let fuel_used = Scalar::min(max_fuel_used, fuel.0);
 
             fuel.0 -= fuel_used;
-            body.dir.normalize() * self.thrust * fuel_used / max_fuel_used
+            body.dir.normalize() * max_thrust * fuel_used / max_fuel_used
         } else {
             Vec2::zero()
         };
 
         body.acc += force / body.mass;
 
+        if let Some(maneuver) = maneuver {
+            let achieved = force.length() / body.mass * dt;
+            let remaining = maneuver.delta_v.length() - achieved;
+
+            // Without fuel, the maneuver can never be completed.
+            if remaining <= Maneuver::TOLERANCE || fuel.0 <= 0.0 {
+                self.maneuvers.remove(0);
+            } else {
+                maneuver.delta_v = maneuver.delta_v.normalize() * remaining;
+            }
+        }
+
         Some(())
     }
 }
 
+/// A burn that is executed automatically at a planned time
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub struct Maneuver {
+    /// Time until the burn starts, in seconds
+    pub delay: Scalar,
+
+    /// The planned change in velocity, in world coordinates
+    pub delta_v: Vec2,
+}
+
+impl Maneuver {
+    /// Remaining change in velocity below which a maneuver is complete
+    pub const TOLERANCE: Scalar = 1e-6;
+
+    /// How far ahead a maneuver can be planned, in seconds
+    pub const MAX_DELAY: Scalar = 86_400.0;
+
+    pub fn is_valid(&self) -> bool {
+        self.delay.is_finite()
+            && self.delay <= Self::MAX_DELAY
+            && self.delta_v.x.is_finite()
+            && self.delta_v.y.is_finite()
+            && self.delta_v.length() > Self::TOLERANCE
+    }
+
+    /// Converts the maneuver into a burn, given the craft's acceleration
+    pub fn to_burn(&self, acc: Scalar) -> Burn {
+        Burn {
+            start: self.delay,
+            duration: self.delta_v.length() / acc,
+            acc: self.delta_v.normalize() * acc,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
 pub struct Fuel(pub Scalar);
 
+data::diff::diff!(Fuel { 0 });
+
 impl Fuel {
     pub fn to_weak(&self) -> Self {
         Self(self.0.clone())
     }
+
+    pub fn remap(&mut self, _: &data::server::HandleMap) -> Option<()> {
+        Some(())
+    }
 }
@@ -3,10 +3,13 @@ use std::ops::{Deref, DerefMut};
 use serde::{Deserialize, Serialize};
 use toadster::Handle;
 
-use crate::world::{
-    health::Health,
-    math::Scalar,
-    physics::{Position, Velocity},
+use crate::{
+    data::{self, Remap as _},
+    world::{
+        health::Health,
+        math::Scalar,
+        physics::{Position, Velocity},
+    },
 };
 
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
@@ -18,6 +21,13 @@ pub struct Explosion {
     pub strength_left: Scalar,
 }
 
+data::diff::diff!(Explosion {
+    pos,
+    vel,
+    strength_total,
+    strength_left
+});
+
 impl Explosion {
     pub fn new(
         pos: impl Into<Handle<Position>>,
@@ -42,6 +52,12 @@ impl Explosion {
         }
     }
 
+    pub fn remap(&mut self, handles: &data::server::HandleMap) -> Option<()> {
+        self.pos = handles.remap(&self.pos)?;
+        self.vel = handles.remap(&self.vel)?;
+        Some(())
+    }
+
     pub fn damage_nearby<'r, P, H>(
         &self,
         pos: &Position,
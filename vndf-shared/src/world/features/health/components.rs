@@ -7,7 +7,7 @@ use toadster::{
 };
 
 use crate::{
-    data,
+    data::{self, Remap as _},
     world::{math::Scalar, physics::Body},
 };
 
@@ -19,6 +19,12 @@ pub struct Health {
     pub value: Scalar,
 }
 
+data::diff::diff!(Health {
+    parent,
+    body,
+    value
+});
+
 impl Health {
     pub fn new(body: impl Into<Handle<Body>>, value: Scalar) -> Self {
         Health {
@@ -45,6 +51,19 @@ impl Health {
         }
     }
 
+    pub fn remap(&mut self, handles: &data::server::HandleMap) -> Option<()> {
+        // The parent is only referred to by a weak handle, so it might already
+        // be gone. If that's the case, this component is going to be removed
+        // soon anyway.
+        self.parent = self
+            .parent
+            .as_ref()
+            .and_then(|parent| parent.remap(handles))
+            .map(|parent| parent.as_weak());
+        self.body = handles.remap(&self.body)?;
+        Some(())
+    }
+
     pub fn parent(self) -> Option<data::client::Handle> {
         self.parent
     }
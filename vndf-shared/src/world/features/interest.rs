@@ -0,0 +1,105 @@
+use std::collections::HashSet;
+
+use toadster::Handle;
+
+use crate::{
+    data,
+    world::{math::Scalar, physics::Position, players::PlayerId},
+};
+
+/// How far a player can see by default, in meters
+pub const DEFAULT_RANGE: Scalar = 100_000_000.0;
+
+/// Determines the components a player needs to know about
+///
+/// That's all planets, the player's own crafts, and all crafts, explosions, and
+/// projectiles within `range` of their ship. Without a ship, the player only
+/// gets to know about the planets.
+///
+/// All returned handles are weak, like the ones in component updates.
+pub fn interest(
+    player: PlayerId,
+    range: Scalar,
+    data: &data::server::Components,
+) -> HashSet<data::client::Handle> {
+    use data::client::Handle as H;
+
+    let mut interest: HashSet<_> = data
+        .planets
+        .iter()
+        .map(|(handle, _)| H::Planet(handle.into()))
+        .collect();
+
+    // Missiles are crafts too, so look for the one that belongs to a ship.
+    let own_pos = data
+        .ships
+        .values()
+        .filter_map(|ship| data.crafts.get(&ship.craft))
+        .find(|craft| craft.owner == player)
+        .and_then(|craft| data.bodies.get(&craft.body))
+        .and_then(|body| data.positions.get(&body.pos))
+        .map(|pos| pos.0);
+    let own_pos = match own_pos {
+        Some(pos) => pos,
+        None => return interest,
+    };
+
+    let in_range = |pos: &Handle<Position>| {
+        data.positions
+            .get(pos)
+            .map(|pos| (pos.0 - own_pos).length() <= range)
+            .unwrap_or(false)
+    };
+
+    for (handle, craft) in data.crafts.iter() {
+        let body = match data.bodies.get(&craft.body) {
+            Some(body) => body,
+            None => continue,
+        };
+        if craft.owner != player && !in_range(&body.pos) {
+            continue;
+        }
+
+        interest.insert(H::Craft(handle.into()));
+        interest.insert(H::Body(craft.body.as_weak()));
+        interest.insert(H::Fuel(craft.fuel.as_weak()));
+        interest.insert(H::Health(craft.health.as_weak()));
+        interest.insert(H::Position(body.pos.as_weak()));
+        interest.insert(H::Velocity(body.vel.as_weak()));
+    }
+
+    for (handle, ship) in data.ships.iter() {
+        if interest.contains(&H::Craft(ship.craft.as_weak())) {
+            interest.insert(H::Ship(handle.into()));
+        }
+    }
+
+    for (handle, missile) in data.missiles.iter() {
+        if interest.contains(&H::Craft(missile.craft.as_weak())) {
+            interest.insert(H::Missile(handle.into()));
+        }
+    }
+
+    for (handle, explosion) in data.explosions.iter() {
+        if in_range(&explosion.pos) {
+            interest.insert(H::Explosion(handle.into()));
+            interest.insert(H::Position(explosion.pos.as_weak()));
+            interest.insert(H::Velocity(explosion.vel.as_weak()));
+        }
+    }
+
+    for (handle, projectile) in data.projectiles.iter() {
+        let body = match data.bodies.get(&projectile.body) {
+            Some(body) => body,
+            None => continue,
+        };
+        if in_range(&body.pos) {
+            interest.insert(H::Projectile(handle.into()));
+            interest.insert(H::Body(projectile.body.as_weak()));
+            interest.insert(H::Position(body.pos.as_weak()));
+            interest.insert(H::Velocity(body.vel.as_weak()));
+        }
+    }
+
+    interest
+}
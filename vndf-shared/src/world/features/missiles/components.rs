@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+use toadster::{store, Handle};
+
+use crate::{
+    data::{self, Remap as _},
+    world::{
+        crafts::Craft,
+        health::Health,
+        math::{Pnt2, Scalar, Vec2},
+    },
+};
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Missile {
+    pub craft: Handle<Craft>,
+
+    /// The craft the missile homes in on
+    ///
+    /// The missile doesn't keep its target alive. If the target is gone, the
+    /// missile detonates.
+    pub target: Option<Handle<Craft>>,
+}
+
+data::diff::diff!(Missile { craft, target });
+
+impl Missile {
+    pub const THRUST: Scalar = 300.0;
+    pub const FUEL: Scalar = 3_000.0;
+    pub const HEALTH: Scalar = 1.0;
+
+    /// How strongly the missile counters the rotation of the line of sight
+    pub const NAVIGATION_CONSTANT: Scalar = 4.0;
+
+    /// Distance to the target within which the missile detonates, in meters
+    ///
+    /// Needs to be smaller than the damage radius of explosions.
+    pub const PROXIMITY: Scalar = 15.0;
+
+    pub fn new(
+        craft: impl Into<Handle<Craft>>,
+        target: Option<Handle<Craft>>,
+    ) -> Self {
+        Self {
+            craft: craft.into(),
+            target,
+        }
+    }
+
+    pub fn to_weak(&self) -> Self {
+        Self {
+            craft: self.craft.as_weak(),
+            target: self.target.as_ref().map(|target| target.as_weak()),
+        }
+    }
+
+    pub fn remap(&mut self, handles: &data::server::HandleMap) -> Option<()> {
+        // The target might already be gone. The missile detonates then.
+        self.target = self
+            .target
+            .as_ref()
+            .and_then(|target| handles.remap(target))
+            .map(|target| target.as_weak());
+        self.craft = handles.remap(&self.craft)?;
+        Some(())
+    }
+
+    /// Blow the missile up
+    ///
+    /// Only drops its health to zero. The health and explosions features take
+    /// care of the rest.
+    pub fn detonate(
+        &self,
+        crafts: &impl store::Get<Craft>,
+        healths: &mut impl store::GetMut<Health>,
+    ) -> Option<()> {
+        let craft = crafts.get(&self.craft)?;
+        healths.get_mut(&craft.health)?.value = 0.0;
+        Some(())
+    }
+
+    /// The direction in which to thrust, to intercept a target
+    ///
+    /// Uses proportional navigation: Thrust perpendicular to the line of sight
+    /// stops it from rotating, which puts the missile on a collision course.
+    /// Whatever thrust is left over closes in on the target.
+    pub fn guidance(
+        pos: Pnt2,
+        vel: Vec2,
+        target_pos: Pnt2,
+        target_vel: Vec2,
+        acc: Scalar,
+    ) -> Vec2 {
+        let offset = target_pos - pos;
+        let rel_vel = target_vel - vel;
+
+        let distance = offset.length();
+        if distance == 0.0 {
+            return Vec2::zero();
+        }
+        let line_of_sight = offset / distance;
+
+        let rotation = offset.cross(rel_vel) / (distance * distance);
+        let closing_speed = -offset.dot(rel_vel) / distance;
+
+        // While the target is getting away, proportional navigation has
+        // nothing to work with. Just chase it, until the missile is closing in.
+        let lateral = Self::NAVIGATION_CONSTANT
+            * Scalar::max(closing_speed, 0.0)
+            * rotation;
+        let lateral = Scalar::max(Scalar::min(lateral, acc), -acc);
+        let forward = (acc * acc - lateral * lateral).sqrt();
+
+        let perpendicular = Vec2::new(-line_of_sight.y, line_of_sight.x);
+        (perpendicular * lateral + line_of_sight * forward).normalize()
+    }
+}
@@ -0,0 +1,78 @@
+use std::collections::HashSet;
+
+use toadster::{
+    handle::{self, Untyped},
+    store, Handle,
+};
+
+use crate::{
+    data,
+    world::{
+        crafts::{Craft, Fuel},
+        health::Health,
+        physics::{Body, Position, Velocity},
+        players::PlayerId,
+    },
+};
+
+use super::Missile;
+
+pub struct MissileEntity {
+    pub owner: PlayerId,
+    pub target: Handle<Craft>,
+
+    /// The body of the craft that launches the missile
+    pub launching: Body,
+}
+
+impl MissileEntity {
+    pub fn create(
+        &self,
+        bodies: &mut store::Strong<Body>,
+        crafts: &mut store::Strong<Craft>,
+        fuels: &mut store::Strong<Fuel>,
+        healths: &mut store::Strong<Health>,
+        missiles: &mut store::Strong<Missile>,
+        positions: &mut store::Strong<Position>,
+        velocities: &mut store::Strong<Velocity>,
+        entities: &mut HashSet<handle::Strong<Untyped>>,
+    ) -> Option<handle::Strong<Missile>> {
+        let pos = *positions.get(&self.launching.pos)?;
+        let pos = positions.insert(pos);
+
+        let vel = *velocities.get(&self.launching.vel)?;
+        let vel = velocities.insert(vel);
+
+        let mut body = Body::new(pos, vel);
+        body.dir = self.launching.dir;
+        let body = bodies.insert(body);
+
+        let fuel = fuels.insert(Fuel(Missile::FUEL));
+        let health = healths.insert(Health::new(body.clone(), Missile::HEALTH));
+
+        let craft = Craft {
+            body: body.into(),
+            fuel: fuel.into(),
+            health: health.clone().into(),
+
+            engine_on: false,
+            thrust: Missile::THRUST,
+            owner: self.owner,
+            maneuvers: Vec::new(),
+
+            firing: false,
+            ammo: 0,
+            cooldown: 0.0,
+        };
+        let craft = crafts.insert(craft);
+
+        let target = Some(self.target.as_weak());
+        let missile = missiles.insert(Missile::new(craft, target));
+        healths.get_mut(&health)?.finalize(
+            data::client::Handle::Missile(missile.clone().into()),
+            entities,
+        );
+
+        Some(missile)
+    }
+}
@@ -0,0 +1,55 @@
+use std::collections::HashSet;
+
+use toadster::{
+    handle::{self, Untyped},
+    store,
+};
+
+use crate::world::{
+    base::Update,
+    crafts::{Craft, Fuel},
+    health::Health,
+    physics::{Body, Position, Velocity},
+    ships::Ship,
+};
+
+use super::{guide_missiles, launch_missiles, Missile};
+
+pub struct Feature;
+
+impl Feature {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Needs to run before the crafts feature, so the missiles' engines follow
+    /// the guidance right away.
+    pub fn on_update(
+        &mut self,
+        event: &Update,
+        bodies: &mut store::Strong<Body>,
+        crafts: &mut store::Strong<Craft>,
+        fuels: &mut store::Strong<Fuel>,
+        healths: &mut store::Strong<Health>,
+        missiles: &mut store::Strong<Missile>,
+        positions: &mut store::Strong<Position>,
+        ships: &mut store::Strong<Ship>,
+        velocities: &mut store::Strong<Velocity>,
+        entities: &mut HashSet<handle::Strong<Untyped>>,
+    ) {
+        launch_missiles(
+            bodies, crafts, fuels, healths, missiles, positions, ships,
+            velocities, entities,
+        );
+        guide_missiles(
+            event.dt, bodies, crafts, fuels, healths, missiles, positions,
+            velocities,
+        );
+    }
+}
+
+impl Default for Feature {
+    fn default() -> Self {
+        Self::new()
+    }
+}
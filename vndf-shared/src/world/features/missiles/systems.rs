@@ -0,0 +1,154 @@
+use std::{collections::HashSet, mem};
+
+use toadster::{
+    handle::{self, Untyped},
+    store,
+};
+
+use crate::world::{
+    crafts::{Craft, Fuel},
+    health::Health,
+    math::{closest_approach, Scalar},
+    physics::{Body, Position, Velocity},
+    ships::Ship,
+};
+
+use super::{Missile, MissileEntity};
+
+/// Launch missiles from all ships that requested it
+///
+/// A missile is aimed at the nearest ship of another player. If there's none,
+/// nothing is launched.
+pub fn launch_missiles(
+    bodies: &mut store::Strong<Body>,
+    crafts: &mut store::Strong<Craft>,
+    fuels: &mut store::Strong<Fuel>,
+    healths: &mut store::Strong<Health>,
+    missiles: &mut store::Strong<Missile>,
+    positions: &mut store::Strong<Position>,
+    ships: &mut store::Strong<Ship>,
+    velocities: &mut store::Strong<Velocity>,
+    entities: &mut HashSet<handle::Strong<Untyped>>,
+) {
+    let targets: Vec<_> = ships
+        .values()
+        .filter_map(|ship| {
+            let craft = crafts.get(&ship.craft)?;
+            let body = bodies.get(&craft.body)?;
+            let pos = positions.get(&body.pos)?;
+            Some((craft.owner, ship.craft.as_weak(), pos.0))
+        })
+        .collect();
+
+    let mut launched = Vec::new();
+    for ship in ships.values_mut() {
+        if !mem::take(&mut ship.launch) || ship.missiles == 0 {
+            continue;
+        }
+
+        let craft = match crafts.get(&ship.craft) {
+            Some(craft) => craft,
+            None => continue,
+        };
+        let body = match bodies.get(&craft.body) {
+            Some(body) => body,
+            None => continue,
+        };
+        let pos = match positions.get(&body.pos) {
+            Some(pos) => pos.0,
+            None => continue,
+        };
+
+        let target = targets
+            .iter()
+            .filter(|(owner, _, _)| *owner != craft.owner)
+            .min_by(|(_, _, a), (_, _, b)| {
+                let a = (*a - pos).length();
+                let b = (*b - pos).length();
+                // A position can be NaN after a bad integration step. That
+                // must not bring down the server.
+                a.total_cmp(&b)
+            });
+        let target = match target {
+            Some((_, target, _)) => target.clone(),
+            None => continue,
+        };
+
+        ship.missiles -= 1;
+        launched.push(MissileEntity {
+            owner: craft.owner,
+            target,
+            launching: body.clone(),
+        });
+    }
+
+    for missile in launched {
+        missile.create(
+            bodies, crafts, fuels, healths, missiles, positions, velocities,
+            entities,
+        );
+    }
+}
+
+/// Steer all missiles towards their targets
+///
+/// Missiles detonate when they are going to come close enough to their target
+/// during the next step of length `dt`. They also detonate, if they run out of
+/// fuel or their target is gone.
+pub fn guide_missiles(
+    dt: Scalar,
+    bodies: &mut store::Strong<Body>,
+    crafts: &mut store::Strong<Craft>,
+    fuels: &store::Strong<Fuel>,
+    healths: &mut store::Strong<Health>,
+    missiles: &store::Strong<Missile>,
+    positions: &store::Strong<Position>,
+    velocities: &store::Strong<Velocity>,
+) {
+    for missile in missiles.values() {
+        let flying = guide_missile(
+            missile, dt, bodies, crafts, fuels, positions, velocities,
+        );
+        if !flying.unwrap_or(false) {
+            missile.detonate(crafts, healths);
+        }
+    }
+}
+
+fn guide_missile(
+    missile: &Missile,
+    dt: Scalar,
+    bodies: &mut store::Strong<Body>,
+    crafts: &mut store::Strong<Craft>,
+    fuels: &store::Strong<Fuel>,
+    positions: &store::Strong<Position>,
+    velocities: &store::Strong<Velocity>,
+) -> Option<bool> {
+    let target = crafts.get(missile.target.as_ref()?)?;
+    let target = bodies.get(&target.body)?;
+    let target_pos = positions.get(&target.pos)?.0;
+    let target_vel = velocities.get(&target.vel)?.0;
+
+    let craft = crafts.get_mut(&missile.craft)?;
+    if fuels.get(&craft.fuel)?.0 <= 0.0 {
+        return Some(false);
+    }
+
+    let body = bodies.get_mut(&craft.body)?;
+    let pos = positions.get(&body.pos)?.0;
+    let vel = velocities.get(&body.vel)?.0;
+
+    // The explosion only happens at the end of the step, so look ahead instead
+    // of back. Otherwise a fast missile would already be past its target.
+    let offset = target_pos - pos;
+    let rel_vel = target_vel - vel;
+    if closest_approach(offset, -rel_vel, dt) <= Missile::PROXIMITY {
+        return Some(false);
+    }
+
+    let acc = craft.thrust / body.mass;
+    body.dir = Missile::guidance(pos, vel, target_pos, target_vel, acc);
+    craft.engine_on = true;
+
+    Some(true)
+}
@@ -2,8 +2,12 @@ pub mod base;
 pub mod crafts;
 pub mod explosions;
 pub mod health;
+pub mod interest;
+pub mod missiles;
 pub mod orbits;
 pub mod physics;
 pub mod planets;
 pub mod players;
 pub mod ships;
+pub mod trajectory;
+pub mod weapons;
@@ -1,19 +1,22 @@
 use toadster::store;
 
 use crate::world::{
-    math::{Angle, Length, Pnt2, Scalar, Vec2},
+    math::{rotate, Angle, Length, Pnt2, Scalar, Vec2},
     planets::{Planet, Planets, G},
 };
 
+/// Eccentricities this close to 1 are considered to be parabolic
+pub const PARABOLIC_TOLERANCE: Scalar = 1e-4;
+
 pub struct Orbit {
     pub center: Pnt2,
+    pub center_vel: Vec2,
     pub eccentricity: Vec2,
-    pub semi_major_axis: Length,
-    pub semi_minor_axis: Length,
+    pub semi_latus_rectum: Length,
     pub arg_of_periapsis: Angle,
     pub periapsis: Apsis,
-    pub apoapsis: Apsis,
-    pub ellipse_pos: Pnt2,
+    pub counter_clockwise: bool,
+    pub kind: Kind,
     pub orbiter: Orbiter,
 }
 
@@ -22,15 +25,18 @@ impl Orbit {
         orbiter: Orbiter,
         planets: &Planets<impl for<'r> store::Values<'r, Planet>>,
     ) -> Option<Self> {
-        let planet = planets.dominant_at(orbiter.pos);
+        let planet = planets.dominant_at(orbiter.pos)?;
 
-        // State vectors
+        // State vectors, relative to the planet we're orbiting
         let r = orbiter.pos - planet.pos;
-        let v = orbiter.vel;
+        let v = orbiter.vel - planet.vel;
 
         // Standard gravitational parameter
         let mu = G * planet.mass;
 
+        // Specific angular momentum
+        let h = r.cross(v);
+
         // Orbital eccentricity
         //
         // The eccentricity vector points from apocenter to pericenter.
@@ -44,47 +50,133 @@ impl Orbit {
         // 0 < |e| < 1 => Elliptical
         // |e| == 1:   => Parabolic
         // |e| > 1:    => Hyperbolic
+        let ecc = e.length();
 
-        // For now, we're only dealing with circular and elliptical orbits.
-        if e.length() >= 1.0 {
-            return None;
-        }
-
-        // Specific orbital energy
-        let ep = v.length().powi(2) / 2.0 - mu / r.length();
+        // Direction of periapsis. It's not defined for circular orbits, so
+        // let's just use the current position then.
+        let dir = if ecc > 0.0 { e / ecc } else { r.normalize() };
 
-        // Semi-major axis
-        let a = -(mu / 2.0 / ep);
-
-        // Semi-minor axis
-        let b = a * (1.0 - e.length().powi(2)).sqrt();
+        // Semi-latus rectum
+        //
+        // Unlike the semi-major axis, this is defined for all kinds of orbits.
+        let p = h.powi(2) / mu;
 
         // Argument of periapsis
-        let w = -Scalar::atan2(e.y, e.x);
+        let w = -Scalar::atan2(dir.y, dir.x);
 
         // Pericenter (point of closest approach)
-        let periapsis = planet.pos + e.normalize() * (1.0 - e.length()) * a;
+        let periapsis = planet.pos + dir * p / (1.0 + ecc);
         let periapsis = Apsis::new(periapsis, planet);
 
-        // Apocenter (farthest point of orbit)
-        let apoapsis = periapsis.position - e.normalize() * 2.0 * a;
-        let apoapsis = Apsis::new(apoapsis, planet);
-
-        // Center of ellipse
-        let ellipse_pos = periapsis.position - e.normalize() * a;
+        let kind = if ecc < 1.0 - PARABOLIC_TOLERANCE {
+            // Semi-major axis
+            let a = p / (1.0 - ecc.powi(2));
+
+            // Semi-minor axis
+            let b = a * (1.0 - ecc.powi(2)).sqrt();
+
+            // Apocenter (farthest point of orbit)
+            let apoapsis = periapsis.position - dir * 2.0 * a;
+            let apoapsis = Apsis::new(apoapsis, planet);
+
+            // Center of ellipse
+            let ellipse_pos = periapsis.position - dir * a;
+
+            Kind::Ellipse {
+                semi_major_axis: Length::new(a),
+                semi_minor_axis: Length::new(b),
+                apoapsis,
+                ellipse_pos,
+            }
+        } else if ecc > 1.0 + PARABOLIC_TOLERANCE {
+            // Semi-major axis (as a positive length)
+            let a = p / (ecc.powi(2) - 1.0);
+
+            Kind::Hyperbola {
+                semi_major_axis: Length::new(a),
+                asymptote_anomaly: Angle::radians((-1.0 / ecc).acos()),
+                excess_velocity: (mu / a).sqrt(),
+            }
+        } else {
+            Kind::Parabola
+        };
 
         Some(Self {
             center: planet.pos,
+            center_vel: planet.vel,
             eccentricity: e,
-            semi_major_axis: Length::new(a),
-            semi_minor_axis: Length::new(b),
+            semi_latus_rectum: Length::new(p),
             arg_of_periapsis: Angle::radians(w),
             periapsis,
-            apoapsis,
-            ellipse_pos,
+            counter_clockwise: h >= 0.0,
+            kind,
             orbiter,
         })
     }
+
+    /// Whether the orbiter is going to escape from the planet it orbits
+    pub fn is_open(&self) -> bool {
+        match self.kind {
+            Kind::Ellipse { .. } => false,
+            Kind::Parabola | Kind::Hyperbola { .. } => true,
+        }
+    }
+
+    /// Returns points along the orbit, that can be drawn as a polyline
+    ///
+    /// Open orbits are cut off where they leave the given distance from the
+    /// center. Elliptical orbits are always returned in full.
+    pub fn points(&self, max_distance: Length, segments: u32) -> Vec<Pnt2> {
+        let ecc = self.eccentricity.length();
+        let p = self.semi_latus_rectum.0;
+
+        let dir = (self.periapsis.position - self.center).normalize();
+        let normal = if self.counter_clockwise {
+            rotate(dir, Angle::frac_pi_2())
+        } else {
+            rotate(dir, -Angle::frac_pi_2())
+        };
+
+        // The range of true anomaly that is covered. Distance from the center
+        // is `p / (1 + ecc * cos(anomaly))`, which we can solve for the
+        // anomaly at the maximum distance.
+        let max_anomaly = if self.is_open() {
+            let cos = (p / max_distance.0 - 1.0) / ecc;
+            cos.clamp(-1.0, 1.0).acos()
+        } else {
+            Angle::pi().radians
+        };
+
+        (0..=segments)
+            .map(|i| {
+                let t = i as Scalar / segments as Scalar;
+                let anomaly = -max_anomaly + 2.0 * max_anomaly * t;
+                let (sin, cos) = anomaly.sin_cos();
+
+                let distance = p / (1.0 + ecc * cos);
+                self.center + (dir * cos + normal * sin) * distance
+            })
+            .collect()
+    }
+}
+
+pub enum Kind {
+    Ellipse {
+        semi_major_axis: Length,
+        semi_minor_axis: Length,
+        apoapsis: Apsis,
+        ellipse_pos: Pnt2,
+    },
+    Parabola,
+    Hyperbola {
+        semi_major_axis: Length,
+
+        /// The true anomaly at which the orbit approaches its asymptotes
+        asymptote_anomaly: Angle,
+
+        /// The speed that remains, once the orbiter has escaped
+        excess_velocity: Scalar,
+    },
 }
 
 pub struct Orbiter {
@@ -1,14 +1,20 @@
 use serde::{Deserialize, Serialize};
 use toadster::{store, Handle};
 
-use crate::world::{
-    math::{self, rotate, Angle, Pnt2, Scalar, Vec2},
-    planets::{Planet, Planets},
+use crate::{
+    data::{self, Remap as _},
+    world::{
+        math::{rotate, Angle, Integrator, Pnt2, Scalar, Vec2},
+        planets::{Planet, Planets},
+        FRAME_TIME,
+    },
 };
 
 #[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
 pub struct Position(pub Pnt2);
 
+data::diff::diff!(Position { 0 });
+
 impl Position {
     pub fn new() -> Self {
         Self(Pnt2::new(0.0, 0.0))
@@ -17,11 +23,17 @@ impl Position {
     pub fn to_weak(&self) -> Self {
         Self(self.0.clone())
     }
+
+    pub fn remap(&mut self, _: &data::server::HandleMap) -> Option<()> {
+        Some(())
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
 pub struct Velocity(pub Vec2);
 
+data::diff::diff!(Velocity { 0 });
+
 impl Velocity {
     pub fn new() -> Self {
         Self(Vec2::new(0.0, 0.0))
@@ -30,6 +42,10 @@ impl Velocity {
     pub fn to_weak(&self) -> Self {
         Self(self.0.clone())
     }
+
+    pub fn remap(&mut self, _: &data::server::HandleMap) -> Option<()> {
+        Some(())
+    }
 }
 
 /// A physical body
@@ -51,6 +67,16 @@ pub struct Body {
     pub time_factor: Scalar,
 }
 
+data::diff::diff!(Body {
+    pos,
+    vel,
+    acc,
+    dir,
+    rot,
+    mass,
+    time_factor
+});
+
 impl Body {
     pub fn new(
         pos: impl Into<Handle<Position>>,
@@ -81,10 +107,24 @@ impl Body {
         }
     }
 
+    pub fn remap(&mut self, handles: &data::server::HandleMap) -> Option<()> {
+        self.pos = handles.remap(&self.pos)?;
+        self.vel = handles.remap(&self.vel)?;
+        Some(())
+    }
+
+    /// Maximum step size, relative to the time scale of the body's orbit
+    ///
+    /// See `Planets::time_scale_at`.
+    pub const MAX_STEP: Scalar = 0.01;
+
     pub fn update(
         &mut self,
         dt: Scalar,
-        planets: &Planets<impl for<'r> store::Values<'r, Planet>>,
+        planets: &Planets<
+            impl store::Get<Planet> + for<'r> store::Values<'r, Planet>,
+        >,
+        integrator: impl Integrator,
         mut positions: impl store::GetMut<Position>,
         mut velocities: impl store::GetMut<Velocity>,
     ) -> Option<()> {
@@ -95,9 +135,31 @@ impl Body {
 
         self.dir = rotate(self.dir, self.rot * dt);
 
-        math::integrate(dt, &mut pos.0, &mut vel.0, |pos| {
-            self.acc + planets.acceleration_at(pos)
-        });
+        // Bodies that are accelerated by an FTL jump cover a lot of time per
+        // frame. Split that time into steps that are small compared to their
+        // orbit, otherwise the orbit gets distorted, and the body might pass
+        // through a planet between two collision checks.
+        let mut remaining = dt;
+        while remaining > 0.0 {
+            let max_step = planets.time_scale_at(pos.0) * Self::MAX_STEP;
+            let step = Scalar::min(remaining, max_step.max(FRAME_TIME));
+            remaining -= step;
+
+            // The planets have already been moved to where they are at the end
+            // of the frame. Move them back to where they are at the end of this
+            // step. They're not affected by the body's time factor.
+            let moved = Planets(planets.after(-remaining / self.time_factor));
+
+            integrator.integrate(step, &mut pos.0, &mut vel.0, |pos| {
+                self.acc + moved.acceleration_at(pos)
+            });
+
+            // Stop right where the collision happens, so it's detected by the
+            // next collision check.
+            if moved.check_collision(pos.0) {
+                break;
+            }
+        }
         self.acc = Vec2::zero();
 
         Some(())
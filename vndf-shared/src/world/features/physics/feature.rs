@@ -1,14 +1,18 @@
 use toadster::store;
 
-use crate::world::{base::Update, planets::Planet};
+use crate::world::{base::Update, math::Method, planets::Planet};
 
 use super::{update_bodies, Body, Position, Velocity};
 
-pub struct Feature;
+pub struct Feature {
+    pub integrator: Method,
+}
 
 impl Feature {
     pub fn new() -> Self {
-        Self
+        Self {
+            integrator: Method::default(),
+        }
     }
 
     pub fn on_update(
@@ -19,6 +23,13 @@ impl Feature {
         positions: &mut store::Strong<Position>,
         velocities: &mut store::Strong<Velocity>,
     ) {
-        update_bodies(bodies, planets, positions, velocities, event.dt);
+        update_bodies(
+            bodies,
+            planets,
+            positions,
+            velocities,
+            self.integrator,
+            event.dt,
+        );
     }
 }
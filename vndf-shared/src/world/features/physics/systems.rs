@@ -1,7 +1,7 @@
 use toadster::store;
 
 use crate::world::{
-    math::Scalar,
+    math::{Method, Scalar},
     planets::{Planet, Planets},
 };
 
@@ -12,9 +12,16 @@ pub fn update_bodies(
     planets: &store::Strong<Planet>,
     mut positions: &mut store::Strong<Position>,
     mut velocities: &mut store::Strong<Velocity>,
+    integrator: Method,
     dt: Scalar,
 ) {
     for body in bodies.values_mut() {
-        body.update(dt, &Planets(planets), &mut positions, &mut velocities);
+        body.update(
+            dt,
+            &Planets(planets),
+            integrator,
+            &mut positions,
+            &mut velocities,
+        );
     }
 }
@@ -1,19 +1,58 @@
 use serde::{Deserialize, Serialize};
+use toadster::{store, Handle};
 
-use crate::world::math::{Length, Pnt2, Scalar, Vec2};
+use crate::{
+    data::{self, Remap as _},
+    world::math::{Angle, Length, Pnt2, Scalar, Vec2},
+};
 
 use super::G;
 
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 pub struct Planet {
     pub pos: Pnt2,
+    pub vel: Vec2,
     pub radius: Length,
     pub mass: Scalar,
+
+    /// The orbit around the parent planet, if this planet has a parent
+    pub rails: Option<Rails>,
 }
 
+data::diff::diff!(Planet {
+    pos,
+    vel,
+    radius,
+    mass,
+    rails
+});
+
 impl Planet {
+    pub fn new(pos: Pnt2, radius: Length, mass: Scalar) -> Self {
+        Self {
+            pos,
+            vel: Vec2::zero(),
+            radius,
+            mass,
+            rails: None,
+        }
+    }
+
     pub fn to_weak(&self) -> Self {
-        self.clone()
+        Self {
+            pos: self.pos.clone(),
+            vel: self.vel.clone(),
+            radius: self.radius.clone(),
+            mass: self.mass.clone(),
+            rails: self.rails.as_ref().map(|rails| rails.to_weak()),
+        }
+    }
+
+    pub fn remap(&mut self, handles: &data::server::HandleMap) -> Option<()> {
+        if let Some(rails) = &mut self.rails {
+            rails.parent = handles.remap(&rails.parent)?;
+        }
+        Some(())
     }
 
     /// Acceleration of a body at the given position, due to gravity
@@ -23,4 +62,96 @@ impl Planet {
 
         (self.pos - pos).normalize() * acc
     }
+
+    /// The radius of the region in which this planet's gravity is dominant
+    ///
+    /// Planets that don't orbit another planet have an unlimited sphere of
+    /// influence.
+    pub fn sphere_of_influence(&self) -> Scalar {
+        match &self.rails {
+            Some(rails) => rails.sphere_of_influence.0,
+            None => Scalar::INFINITY,
+        }
+    }
+
+    /// Computes position and velocity from the phase of this planet's orbit
+    ///
+    /// Returns the current position and velocity for planets without a parent.
+    pub fn state(
+        &self,
+        planets: &impl store::Get<Planet>,
+    ) -> Option<(Pnt2, Vec2)> {
+        self.state_after(0.0, planets)
+    }
+
+    /// Computes position and velocity, after moving along the orbit for `dt`
+    pub fn state_after(
+        &self,
+        dt: Scalar,
+        planets: &impl store::Get<Planet>,
+    ) -> Option<(Pnt2, Vec2)> {
+        let rails = match &self.rails {
+            Some(rails) => rails,
+            None => return Some((self.pos, self.vel)),
+        };
+
+        let parent = planets.get(&rails.parent)?;
+        let (parent_pos, parent_vel) = parent.state_after(dt, planets)?;
+
+        let phase = rails.phase + Angle::radians(rails.angular_speed * dt);
+        let (sin, cos) = phase.sin_cos();
+        let pos = parent_pos + Vec2::new(cos, sin) * rails.distance.0;
+        let vel = parent_vel
+            + Vec2::new(-sin, cos) * rails.angular_speed * rails.distance.0;
+
+        Some((pos, vel))
+    }
+}
+
+/// A circular orbit, that a planet follows exactly
+///
+/// Planets aren't affected by the gravity of other bodies. They just follow
+/// their orbit, "on rails".
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Rails {
+    pub parent: Handle<Planet>,
+    pub distance: Length,
+
+    /// The angle of the planet's position, relative to its parent
+    pub phase: Angle,
+
+    pub angular_speed: Scalar,
+    pub sphere_of_influence: Length,
+}
+
+impl Rails {
+    pub fn new(
+        parent_handle: impl Into<Handle<Planet>>,
+        parent: &Planet,
+        mass: Scalar,
+        distance: Length,
+        phase: Angle,
+    ) -> Self {
+        let angular_speed =
+            (G * (parent.mass + mass) / distance.0.powi(3)).sqrt();
+        let sphere_of_influence = distance * (mass / parent.mass).powf(0.4);
+
+        Self {
+            parent: parent_handle.into(),
+            distance,
+            phase,
+            angular_speed,
+            sphere_of_influence,
+        }
+    }
+
+    pub fn to_weak(&self) -> Self {
+        Self {
+            parent: self.parent.as_weak(),
+            distance: self.distance.clone(),
+            phase: self.phase.clone(),
+            angular_speed: self.angular_speed.clone(),
+            sphere_of_influence: self.sphere_of_influence.clone(),
+        }
+    }
 }
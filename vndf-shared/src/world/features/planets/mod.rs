@@ -1,7 +1,8 @@
 pub mod components;
+pub mod system;
 pub mod systems;
 
-pub use self::{components::*, systems::*};
+pub use self::{components::*, system::System, systems::*};
 
 use crate::world::math::Scalar;
 
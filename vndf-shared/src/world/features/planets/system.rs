@@ -0,0 +1,165 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, prelude::*},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use toadster::{handle, store};
+
+use crate::world::math::{Angle, Length, Pnt2, Scalar};
+
+use super::{Planet, Rails};
+
+/// The definition of a star system, which the world is created from
+///
+/// Like scripts, system definitions are loaded from TOML. Planets can orbit
+/// other planets, which must be defined before them. See `saturn.toml` for an
+/// example.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct System {
+    planets: Vec<PlanetDef>,
+}
+
+impl System {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let mut s = String::new();
+        File::open(path)?.read_to_string(&mut s)?;
+
+        Self::from_toml(&s)
+    }
+
+    pub fn from_toml(s: &str) -> Result<Self, Error> {
+        let system: Self = toml::from_str(s)?;
+        system.validate()?;
+        Ok(system)
+    }
+
+    /// Checks that the system can be created
+    ///
+    /// `from_toml` does this already. Systems that are deserialized from other
+    /// sources need to be checked separately.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.planets.is_empty() {
+            return Err(Error::Empty);
+        }
+
+        let mut names = Vec::new();
+        for planet in &self.planets {
+            if let Some(orbit) = &planet.orbit {
+                if !names.contains(&&orbit.parent) {
+                    return Err(Error::UnknownParent(orbit.parent.clone()));
+                }
+            }
+            if names.contains(&&planet.name) {
+                return Err(Error::DuplicateName(planet.name.clone()));
+            }
+            names.push(&planet.name);
+        }
+
+        Ok(())
+    }
+
+    pub fn planets(&self) -> &[PlanetDef] {
+        &self.planets
+    }
+
+    /// Creates the planets of this system
+    ///
+    /// The first planet is placed at the origin. Returns the handles of all
+    /// planets, in the order they were defined in.
+    pub fn create(
+        &self,
+        planets: &mut store::Strong<Planet>,
+    ) -> Vec<handle::Strong<Planet>> {
+        let mut handles = Vec::new();
+        let mut by_name: HashMap<&str, handle::Strong<Planet>> = HashMap::new();
+
+        for def in &self.planets {
+            let mut planet = Planet::new(
+                Pnt2::new(0.0, 0.0),
+                Length::new(def.radius),
+                def.mass,
+            );
+
+            if let Some(orbit) = &def.orbit {
+                // Parents are validated, so they must exist.
+                let parent_handle = &by_name[orbit.parent.as_str()];
+                let parent = planets.get(parent_handle).unwrap();
+
+                let rails = Rails::new(
+                    parent_handle.clone(),
+                    parent,
+                    def.mass,
+                    Length::new(orbit.distance),
+                    Angle::degrees(orbit.phase),
+                );
+                planet.rails = Some(rails);
+
+                let (pos, vel) = planet.state(planets).unwrap();
+                planet.pos = pos;
+                planet.vel = vel;
+            }
+
+            let handle = planets.insert(planet);
+            by_name.insert(def.name.as_str(), handle.clone());
+            handles.push(handle);
+        }
+
+        handles
+    }
+}
+
+impl Default for System {
+    /// The Saturn system
+    fn default() -> Self {
+        Self::from_toml(include_str!("saturn.toml"))
+            .expect("Default system definition is invalid")
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct PlanetDef {
+    pub name: String,
+    pub mass: Scalar,
+    pub radius: Scalar,
+    pub orbit: Option<OrbitDef>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct OrbitDef {
+    pub parent: String,
+    pub distance: Scalar,
+
+    /// The initial angle of the orbiting planet's position, in degrees
+    #[serde(default)]
+    pub phase: Scalar,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Toml(toml::de::Error),
+
+    /// The system doesn't have any planets
+    Empty,
+
+    /// More than one planet has this name
+    DuplicateName(String),
+
+    /// A planet orbits a parent that hasn't been defined before it
+    UnknownParent(String),
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for Error {
+    fn from(err: toml::de::Error) -> Self {
+        Self::Toml(err)
+    }
+}
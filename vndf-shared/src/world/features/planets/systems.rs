@@ -1,12 +1,14 @@
+use std::cmp::Ordering;
+
 use toadster::store;
 
 use crate::world::{
     health::Health,
-    math::{Pnt2, Vec2},
+    math::{Angle, Pnt2, Scalar, Vec2},
     physics::components::{Body, Position},
 };
 
-use super::Planet;
+use super::{Planet, G};
 
 pub struct Systems<B, H, Pl, Po> {
     pub bodies: B,
@@ -79,12 +81,84 @@ where
         false
     }
 
+    /// Characteristic time of motion around the dominant planet
+    ///
+    /// A circular orbit at the given position would have a period of 2π times
+    /// this value. Returns infinity, if no planet is dominant.
+    pub fn time_scale_at(&self, pos: Pnt2) -> Scalar {
+        match self.dominant_at(pos) {
+            Some(planet) => {
+                let distance = (pos - planet.pos).length();
+                (distance.powi(3) / (G * planet.mass)).sqrt()
+            }
+            None => Scalar::INFINITY,
+        }
+    }
+
     /// Returns the planet whose gravity is dominant at the given position
-    pub fn dominant_at(&self, _pos: Pnt2) -> &Planet {
-        // At some point, we'd look at the closest planets and compare their
-        // gravitation pull, but for now there's just one planet in the game.
-        assert_eq!(self.0.values().count(), 1);
+    ///
+    /// That's the planet with the smallest sphere of influence that contains
+    /// the position.
+    pub fn dominant_at(&self, pos: Pnt2) -> Option<&Planet> {
+        self.0
+            .values()
+            .filter(|planet| {
+                (pos - planet.pos).length() <= planet.sphere_of_influence()
+            })
+            .min_by(|a, b| {
+                a.sphere_of_influence()
+                    .partial_cmp(&b.sphere_of_influence())
+                    .unwrap_or(Ordering::Equal)
+            })
+    }
+}
 
-        self.0.values().next().unwrap()
+impl<S> Planets<S>
+where
+    S: store::Get<Planet> + for<'r> store::Values<'r, Planet>,
+{
+    /// Returns copies of all planets, moved along their orbits for `dt`
+    pub fn after(&self, dt: Scalar) -> Vec<Planet> {
+        self.0
+            .values()
+            .filter_map(|planet| {
+                let (pos, vel) = planet.state_after(dt, &self.0)?;
+                Some(Planet {
+                    pos,
+                    vel,
+                    ..planet.clone()
+                })
+            })
+            .collect()
+    }
+}
+
+/// Moves all planets along their orbits
+pub fn update_planets<S>(dt: Scalar, planets: &mut S)
+where
+    S: store::Get<Planet>
+        + for<'r> store::Values<'r, Planet>
+        + for<'r> store::ValuesMut<'r, Planet>,
+{
+    for planet in planets.values_mut() {
+        if let Some(rails) = &mut planet.rails {
+            rails.phase = (rails.phase
+                + Angle::radians(rails.angular_speed * dt))
+            .positive();
+        }
+    }
+
+    // A planet's position depends on the position of its parent, so we need
+    // to compute all of them, before we can update any.
+    let states: Vec<_> = planets
+        .values()
+        .map(|planet| planet.state(planets))
+        .collect();
+
+    for (planet, state) in planets.values_mut().zip(states) {
+        if let Some((pos, vel)) = state {
+            planet.pos = pos;
+            planet.vel = vel;
+        }
     }
 }
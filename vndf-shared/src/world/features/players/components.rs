@@ -2,16 +2,73 @@ use std::net::SocketAddr;
 
 use serde::{Deserialize, Serialize};
 
-use crate::world::players::PlayerId;
+use crate::{
+    data,
+    world::{
+        math::Scalar,
+        players::{IdentityToken, PlayerId},
+    },
+};
 
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 pub struct Player {
     pub id: PlayerId,
-    pub addr: SocketAddr,
+    pub token: IdentityToken,
+
+    /// The address the player is connected from, if connected at all
+    pub addr: Option<SocketAddr>,
+
+    /// The color of the player's ships
+    pub color: [f32; 3],
+
+    /// The tick the player's ship was destroyed at
+    ///
+    /// `None`, while the player has a ship.
+    pub destroyed_at: Option<u64>,
+
+    /// The time warp factor this player requests
+    ///
+    /// `None`, while the player hasn't requested any, and so doesn't object to
+    /// the factor other players request.
+    pub warp: Option<Scalar>,
 }
 
 impl Player {
-    pub fn new(id: PlayerId, addr: SocketAddr) -> Self {
-        Self { id, addr }
+    pub const MAX_WARP: Scalar = 1000.0;
+
+    /// Default for how long a disconnected player's ship stays around, in
+    /// seconds
+    ///
+    /// The player can reconnect and take it over again within that time.
+    pub const DEFAULT_DISCONNECT_TIMEOUT: Scalar = 60.0;
+
+    /// Time after the ship was destroyed, until the player can request a new
+    /// one, in seconds
+    pub const RESPAWN_DELAY: Scalar = 5.0;
+
+    pub fn new(
+        id: PlayerId,
+        token: IdentityToken,
+        addr: SocketAddr,
+        color: [f32; 3],
+    ) -> Self {
+        Self {
+            id,
+            token,
+            addr: Some(addr),
+            color,
+            destroyed_at: None,
+            warp: None,
+        }
+    }
+
+    pub fn request_warp(&mut self, warp: Scalar) {
+        if warp.is_finite() {
+            self.warp = Some(warp.clamp(1.0, Self::MAX_WARP));
+        }
+    }
+
+    pub fn remap(&mut self, _: &data::server::HandleMap) -> Option<()> {
+        Some(())
     }
 }
@@ -1,6 +1,9 @@
 use std::net::SocketAddr;
 
-use crate::{action::Action, world::PlayerId};
+use crate::{
+    action::Action,
+    world::{players::IdentityToken, PlayerId, Scalar},
+};
 
 pub struct InputHandled {
     pub addr: SocketAddr,
@@ -10,6 +13,12 @@ pub struct InputHandled {
 pub struct PlayerConnected {
     pub addr: SocketAddr,
     pub color: [f32; 3],
+
+    /// Identifies an existing player the client wants to take over again
+    pub token: Option<IdentityToken>,
+
+    /// The token that is issued, if a new player is created
+    pub new_token: IdentityToken,
 }
 
 pub struct PlayerDisconnected {
@@ -19,6 +28,35 @@ pub struct PlayerDisconnected {
 pub struct PlayerCreated {
     pub id: PlayerId,
     pub addr: SocketAddr,
+    pub token: IdentityToken,
+}
+
+pub struct PlayerReconnected {
+    pub id: PlayerId,
+    pub addr: SocketAddr,
+    pub token: IdentityToken,
+}
+
+/// A player was taken over by a new connection, while still connected
+///
+/// The previous connection no longer controls the player, and needs to be
+/// closed.
+pub struct PlayerTakenOver {
+    pub id: PlayerId,
+
+    /// The address of the previous connection
+    pub addr: SocketAddr,
+}
+
+/// The ship of a connected player was destroyed
+///
+/// Also sent when a player whose ship is destroyed reconnects.
+pub struct ShipDestroyed {
+    pub id: PlayerId,
+    pub addr: SocketAddr,
+
+    /// Time until the player can request a new ship, in seconds
+    pub respawn_in: Scalar,
 }
 
 pub struct PlayerInput {
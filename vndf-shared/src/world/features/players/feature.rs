@@ -1,8 +1,12 @@
 use std::{
     collections::{HashMap, HashSet},
+    fmt,
     net::SocketAddr,
+    num::ParseIntError,
+    str::FromStr,
 };
 
+use rand::Rng;
 use rinnsal::EventBuf;
 use serde::{Deserialize, Serialize};
 use toadster::{
@@ -13,25 +17,42 @@ use toadster::{
 use crate::world::{
     crafts::{Craft, Fuel},
     health::Health,
+    math::Scalar,
     physics::{Body, Position, Velocity},
     planets::Planet,
     ships::Ship,
 };
 
 use super::{
-    connect_player, disconnect_player, handle_input, InputHandled, Player,
-    PlayerConnected, PlayerCreated, PlayerDisconnected, PlayerInput,
+    connect_player, despawn_orphans, detect_destroyed_ships, disconnect_player,
+    expire_players, handle_input, reconnect_player, restore_player,
+    InputHandled, Player, PlayerConnected, PlayerCreated, PlayerDisconnected,
+    PlayerInput, PlayerReconnected, PlayerTakenOver, ShipDestroyed,
 };
 
 pub struct Feature {
-    next_id: PlayerId,
+    pub next_id: PlayerId,
     players_by_address: HashMap<SocketAddr, handle::Strong<Player>>,
 
+    /// How long a disconnected player's ship stays around, in seconds
+    pub disconnect_timeout: Scalar,
+
+    /// Players that lost their connection, with the tick that happened at
+    ///
+    /// They are kept around until the disconnect timeout is over.
+    disconnected: HashMap<IdentityToken, (handle::Strong<Player>, u64)>,
+
+    /// Ships without a connected owner, with the tick they were orphaned at
+    orphaned: HashMap<handle::Weak<Ship>, u64>,
+
     pub input_handled: EventBuf<InputHandled>,
     pub player_connected: EventBuf<PlayerConnected>,
     pub player_created: EventBuf<PlayerCreated>,
     pub player_disconnected: EventBuf<PlayerDisconnected>,
     pub player_input: EventBuf<PlayerInput>,
+    pub player_reconnected: EventBuf<PlayerReconnected>,
+    pub player_taken_over: EventBuf<PlayerTakenOver>,
+    pub ship_destroyed: EventBuf<ShipDestroyed>,
 }
 
 impl Feature {
@@ -39,19 +60,66 @@ impl Feature {
         Self {
             next_id: PlayerId::first(),
             players_by_address: HashMap::new(),
+            disconnect_timeout: Player::DEFAULT_DISCONNECT_TIMEOUT,
+            disconnected: HashMap::new(),
+            orphaned: HashMap::new(),
 
             input_handled: EventBuf::new(),
             player_connected: EventBuf::new(),
             player_created: EventBuf::new(),
             player_disconnected: EventBuf::new(),
             player_input: EventBuf::new(),
+            player_reconnected: EventBuf::new(),
+            player_taken_over: EventBuf::new(),
+            ship_destroyed: EventBuf::new(),
         }
     }
 
+    /// Take over the players from a restored snapshot
+    pub fn restore(
+        &mut self,
+        tick: u64,
+        players: &mut store::Strong<Player>,
+        restored: impl IntoIterator<Item = handle::Strong<Player>>,
+    ) {
+        for handle in restored {
+            restore_player(handle, tick, players, &mut self.disconnected);
+        }
+    }
+
+    pub fn on_update(
+        &mut self,
+        tick: u64,
+        crafts: &store::Strong<Craft>,
+        players: &mut store::Strong<Player>,
+        ships: &store::Strong<Ship>,
+        entities: &mut HashSet<handle::Strong<Untyped>>,
+    ) {
+        detect_destroyed_ships(
+            tick,
+            crafts,
+            players,
+            ships,
+            &mut self.ship_destroyed.sink(),
+        );
+        expire_players(&mut self.disconnected, tick, self.disconnect_timeout);
+        despawn_orphans(
+            tick,
+            self.disconnect_timeout,
+            crafts,
+            players,
+            ships,
+            &mut self.orphaned,
+            entities,
+        );
+    }
+
     pub fn on_player_connected(
         &mut self,
         event: &PlayerConnected,
+        tick: u64,
         planet: &Planet,
+        rng: &mut impl Rng,
         bodies: &mut store::Strong<Body>,
         crafts: &mut store::Strong<Craft>,
         fuels: &mut store::Strong<Fuel>,
@@ -62,11 +130,30 @@ impl Feature {
         velocities: &mut store::Strong<Velocity>,
         entities: &mut HashSet<handle::Strong<Untyped>>,
     ) {
+        if let Some(token) = event.token {
+            let reconnected = reconnect_player(
+                token,
+                event.addr,
+                tick,
+                players,
+                &mut self.player_reconnected.sink(),
+                &mut self.player_taken_over.sink(),
+                &mut self.ship_destroyed.sink(),
+                &mut self.players_by_address,
+                &mut self.disconnected,
+            );
+            if reconnected.is_some() {
+                return;
+            }
+        }
+
         connect_player(
             self.next_id.increment(),
+            event.new_token,
             event.addr,
             event.color,
             planet,
+            rng,
             bodies,
             crafts,
             fuels,
@@ -81,27 +168,54 @@ impl Feature {
         );
     }
 
-    pub fn on_player_disconnected(&mut self, event: &PlayerDisconnected) {
-        disconnect_player(&mut self.players_by_address, event.addr);
+    pub fn on_player_disconnected(
+        &mut self,
+        event: &PlayerDisconnected,
+        tick: u64,
+        players: &mut store::Strong<Player>,
+    ) {
+        disconnect_player(
+            event.addr,
+            tick,
+            players,
+            &mut self.players_by_address,
+            &mut self.disconnected,
+        );
     }
 
     pub fn on_player_input(
         &mut self,
         event: &PlayerInput,
+        tick: u64,
+        planet: &Planet,
+        rng: &mut impl Rng,
         bodies: &mut store::Strong<Body>,
         crafts: &mut store::Strong<Craft>,
-        players: &store::Strong<Player>,
+        fuels: &mut store::Strong<Fuel>,
+        healths: &mut store::Strong<Health>,
+        players: &mut store::Strong<Player>,
+        positions: &mut store::Strong<Position>,
         ships: &mut store::Strong<Ship>,
+        velocities: &mut store::Strong<Velocity>,
+        entities: &mut HashSet<handle::Strong<Untyped>>,
     ) {
         handle_input(
             event.addr,
             event.action,
+            tick,
+            planet,
+            rng,
             bodies,
             crafts,
+            fuels,
+            healths,
             players,
+            positions,
             ships,
+            velocities,
             &mut self.input_handled.sink(),
             &mut self.players_by_address,
+            entities,
         );
     }
 }
@@ -120,3 +234,34 @@ impl PlayerId {
         Self(current)
     }
 }
+
+/// Identifies a player across connections
+///
+/// Issued to the client when the player is created. A client that presents it
+/// when connecting again takes over the existing player, and with it the ship.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Eq, PartialEq, Hash)]
+pub struct IdentityToken(pub u64);
+
+impl IdentityToken {
+    /// Generate a new token from the operating system's randomness
+    ///
+    /// Knowing the world's seed must not allow anyone to guess the tokens, so
+    /// they can't come from the world's random number generator.
+    pub fn generate() -> Self {
+        Self(rand::thread_rng().gen())
+    }
+}
+
+impl fmt::Display for IdentityToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl FromStr for IdentityToken {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        u64::from_str_radix(s, 16).map(Self)
+    }
+}
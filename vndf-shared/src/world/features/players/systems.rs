@@ -4,6 +4,7 @@ use std::{
 };
 
 use log::warn;
+use rand::Rng;
 use rinnsal::EventSink;
 use toadster::{
     handle::{self, Untyped},
@@ -11,24 +12,32 @@ use toadster::{
 };
 
 use crate::{
-    action::Action,
+    action::{self, Action},
+    data,
     world::{
         crafts::{Craft, Fuel},
         health::Health,
+        math::Scalar,
         physics::{Body, Position, Velocity},
         planets::Planet,
-        players::PlayerId,
+        players::{IdentityToken, PlayerId},
         ships::{Ship, ShipEntity},
+        FRAME_TIME,
     },
 };
 
-use super::{InputHandled, Player, PlayerCreated};
+use super::{
+    InputHandled, Player, PlayerCreated, PlayerReconnected, PlayerTakenOver,
+    ShipDestroyed,
+};
 
 pub fn connect_player(
     id: PlayerId,
+    token: IdentityToken,
     addr: SocketAddr,
     color: [f32; 3],
     planet: &Planet,
+    rng: &mut impl Rng,
     bodies: &mut store::Strong<Body>,
     crafts: &mut store::Strong<Craft>,
     fuels: &mut store::Strong<Fuel>,
@@ -41,42 +50,279 @@ pub fn connect_player(
     index: &mut HashMap<SocketAddr, handle::Strong<Player>>,
     entities: &mut HashSet<handle::Strong<Untyped>>,
 ) {
-    let handle = players.insert(Player::new(id, addr));
-    index.insert(addr, handle);
-
     ShipEntity { owner: id, color }.create(
-        planet, bodies, crafts, fuels, healths, positions, ships, velocities,
-        entities,
+        planet, rng, bodies, crafts, fuels, healths, positions, ships,
+        velocities, entities,
     );
-    player_created.push(PlayerCreated { id, addr });
+
+    let handle = players.insert(Player::new(id, token, addr, color));
+    index.insert(addr, handle);
+
+    player_created.push(PlayerCreated { id, addr, token });
 }
 
-pub fn disconnect_player(
+/// Attach a new connection to the player the token belongs to
+///
+/// If the player is still connected from another address, that connection is
+/// taken over, and a `PlayerTakenOver` event is emitted for it. This is
+/// deliberate: A client that crashed or lost its network can't tell the server
+/// about it, and the token is only known to the client it was issued to.
+/// Returns `None`, if the token doesn't belong to any player (any more).
+pub fn reconnect_player(
+    token: IdentityToken,
+    addr: SocketAddr,
+    tick: u64,
+    players: &mut store::Strong<Player>,
+    player_reconnected: &mut EventSink<PlayerReconnected>,
+    player_taken_over: &mut EventSink<PlayerTakenOver>,
+    ship_destroyed: &mut EventSink<ShipDestroyed>,
     index: &mut HashMap<SocketAddr, handle::Strong<Player>>,
+    disconnected: &mut HashMap<IdentityToken, (handle::Strong<Player>, u64)>,
+) -> Option<()> {
+    let handle = match disconnected.remove(&token) {
+        Some((handle, _)) => handle,
+        None => {
+            let previous = index
+                .iter()
+                .find(|(_, handle)| {
+                    players
+                        .get(*handle)
+                        .map(|player| player.token == token)
+                        .unwrap_or(false)
+                })
+                .map(|(&addr, _)| addr)?;
+            let handle = index.remove(&previous)?;
+
+            let id = players.get(&handle)?.id;
+            player_taken_over.push(PlayerTakenOver { id, addr: previous });
+
+            handle
+        }
+    };
+
+    let player = players.get_mut(&handle)?;
+    player.addr = Some(addr);
+
+    player_reconnected.push(PlayerReconnected {
+        id: player.id,
+        addr,
+        token,
+    });
+    if let Some(destroyed_at) = player.destroyed_at {
+        // The new connection doesn't know yet that there's no ship.
+        ship_destroyed.push(ShipDestroyed {
+            id: player.id,
+            addr,
+            respawn_in: respawn_in(destroyed_at, tick),
+        });
+    }
+    index.insert(addr, handle);
+
+    Some(())
+}
+
+pub fn disconnect_player(
     address: SocketAddr,
+    tick: u64,
+    players: &mut store::Strong<Player>,
+    index: &mut HashMap<SocketAddr, handle::Strong<Player>>,
+    disconnected: &mut HashMap<IdentityToken, (handle::Strong<Player>, u64)>,
+) -> Option<()> {
+    let handle = index.remove(&address)?;
+    let player = players.get_mut(&handle)?;
+    player.addr = None;
+
+    disconnected.insert(player.token, (handle, tick));
+
+    Some(())
+}
+
+/// Treat a player from a restored snapshot as disconnected
+///
+/// Connections don't survive a restart, but the player can reconnect with
+/// their token, until the disconnect timeout is over.
+pub fn restore_player(
+    handle: handle::Strong<Player>,
+    tick: u64,
+    players: &mut store::Strong<Player>,
+    disconnected: &mut HashMap<IdentityToken, (handle::Strong<Player>, u64)>,
+) -> Option<()> {
+    let player = players.get_mut(&handle)?;
+    player.addr = None;
+
+    disconnected.insert(player.token, (handle, tick));
+
+    Some(())
+}
+
+/// Forget about players that have been disconnected for too long
+pub fn expire_players(
+    disconnected: &mut HashMap<IdentityToken, (handle::Strong<Player>, u64)>,
+    tick: u64,
+    timeout: Scalar,
+) {
+    disconnected.retain(|_, (_, since)| !is_expired(*since, tick, timeout));
+}
+
+/// Despawn ships whose owner hasn't been connected for too long
+///
+/// This includes ships whose owner doesn't exist at all (any more).
+/// Despawning a ship releases it from the index of entities, so it and its
+/// components are removed like those of a destroyed ship.
+pub fn despawn_orphans(
+    tick: u64,
+    timeout: Scalar,
+    crafts: &store::Strong<Craft>,
+    players: &store::Strong<Player>,
+    ships: &store::Strong<Ship>,
+    orphaned: &mut HashMap<handle::Weak<Ship>, u64>,
+    entities: &mut HashSet<handle::Strong<Untyped>>,
+) {
+    let connected: HashSet<_> = players
+        .values()
+        .filter(|player| player.addr.is_some())
+        .map(|player| player.id)
+        .collect();
+
+    orphaned.retain(|ship, _| ships.get(ship).is_some());
+
+    for (handle, ship) in ships {
+        let owner = crafts.get(&ship.craft).map(|craft| craft.owner);
+        if owner
+            .map(|owner| connected.contains(&owner))
+            .unwrap_or(false)
+        {
+            orphaned.remove(&handle);
+            continue;
+        }
+
+        let since = *orphaned.entry(handle).or_insert(tick);
+        if is_expired(since, tick, timeout) {
+            let entity = data::client::Handle::Ship(handle.into());
+            entities.remove(&entity.into_weak_untyped());
+        }
+    }
+}
+
+/// Notice which players have lost their ship
+///
+/// Ships are destroyed by the health feature, which doesn't know about
+/// players. So instead of reacting to that, this checks which players that
+/// are supposed to have a ship don't own one anymore.
+pub fn detect_destroyed_ships(
+    tick: u64,
+    crafts: &store::Strong<Craft>,
+    players: &mut store::Strong<Player>,
+    ships: &store::Strong<Ship>,
+    ship_destroyed: &mut EventSink<ShipDestroyed>,
 ) {
-    index.remove(&address);
+    let owners: HashSet<_> = ships
+        .values()
+        .filter_map(|ship| crafts.get(&ship.craft))
+        .map(|craft| craft.owner)
+        .collect();
+
+    for player in players.values_mut() {
+        if player.destroyed_at.is_some() || owners.contains(&player.id) {
+            continue;
+        }
+
+        player.destroyed_at = Some(tick);
+        if let Some(addr) = player.addr {
+            ship_destroyed.push(ShipDestroyed {
+                id: player.id,
+                addr,
+                respawn_in: Player::RESPAWN_DELAY,
+            });
+        }
+    }
+}
+
+/// Give a player whose ship was destroyed a new one
+///
+/// Returns `None`, if the player still has a ship, or the respawn delay isn't
+/// over yet.
+pub fn respawn_player(
+    player: &mut Player,
+    tick: u64,
+    planet: &Planet,
+    rng: &mut impl Rng,
+    bodies: &mut store::Strong<Body>,
+    crafts: &mut store::Strong<Craft>,
+    fuels: &mut store::Strong<Fuel>,
+    healths: &mut store::Strong<Health>,
+    positions: &mut store::Strong<Position>,
+    ships: &mut store::Strong<Ship>,
+    velocities: &mut store::Strong<Velocity>,
+    entities: &mut HashSet<handle::Strong<Untyped>>,
+) -> Option<()> {
+    let destroyed_at = player.destroyed_at?;
+    if respawn_in(destroyed_at, tick) > 0.0 {
+        return None;
+    }
+
+    ShipEntity {
+        owner: player.id,
+        color: player.color,
+    }
+    .create(
+        planet, rng, bodies, crafts, fuels, healths, positions, ships,
+        velocities, entities,
+    );
+    player.destroyed_at = None;
+
+    Some(())
+}
+
+fn is_expired(since: u64, tick: u64, timeout: Scalar) -> bool {
+    (tick - since) as Scalar * FRAME_TIME >= timeout
+}
+
+fn respawn_in(destroyed_at: u64, tick: u64) -> Scalar {
+    let elapsed = (tick - destroyed_at) as Scalar * FRAME_TIME;
+    (Player::RESPAWN_DELAY - elapsed).max(0.0)
 }
 
 pub fn handle_input(
     addr: SocketAddr,
     action: Action,
+    tick: u64,
+    planet: &Planet,
+    rng: &mut impl Rng,
     bodies: &mut store::Strong<Body>,
     crafts: &mut store::Strong<Craft>,
-    players: &store::Strong<Player>,
+    fuels: &mut store::Strong<Fuel>,
+    healths: &mut store::Strong<Health>,
+    players: &mut store::Strong<Player>,
+    positions: &mut store::Strong<Position>,
     ships: &mut store::Strong<Ship>,
+    velocities: &mut store::Strong<Velocity>,
     input_handled: &mut EventSink<InputHandled>,
     index: &mut HashMap<SocketAddr, handle::Strong<Player>>,
+    entities: &mut HashSet<handle::Strong<Untyped>>,
 ) -> Option<()> {
     let player = index.get(&addr).or_else(|| {
         warn!("Player not in index: {}", addr);
         None
     })?;
-    let player = players.get(player).or_else(|| {
+    let player = players.get_mut(player).or_else(|| {
         warn!("Player component not found: {}", addr);
         None
     })?;
 
+    match action.kind {
+        action::Kind::TimeWarp(warp) => {
+            player.request_warp(warp);
+        }
+        action::Kind::Respawn => {
+            respawn_player(
+                player, tick, planet, rng, bodies, crafts, fuels, healths,
+                positions, ships, velocities, entities,
+            );
+        }
+        _ => {}
+    }
+
     for ship in ships.values_mut() {
         ship.apply_input(bodies, crafts, player, action);
     }
@@ -88,3 +334,22 @@ pub fn handle_input(
 
     Some(())
 }
+
+/// The time warp factor that all players agree on
+///
+/// Any player can object to time warp by requesting a smaller factor, so this
+/// is the smallest factor that any connected player requests. Players that
+/// haven't requested any factor don't object.
+pub fn time_warp(players: &store::Strong<Player>) -> Scalar {
+    let warp = players
+        .values()
+        .filter(|player| player.addr.is_some())
+        .filter_map(|player| player.warp)
+        .fold(Scalar::INFINITY, Scalar::min);
+
+    if warp.is_finite() {
+        warp
+    } else {
+        1.0
+    }
+}
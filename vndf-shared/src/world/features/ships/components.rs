@@ -4,6 +4,7 @@ use toadster::{store, Handle};
 
 use crate::{
     action::{self, Action, Rotation},
+    data::{self, Remap as _},
     world::{
         crafts::Craft,
         math::{Angle, Scalar},
@@ -18,15 +19,36 @@ pub struct Ship {
     pub rotation: Rotation,
     pub color: [f32; 3],
     pub ftl_timer: Scalar,
+
+    /// Number of missiles left
+    pub missiles: u32,
+
+    /// Whether a missile is launched on the next update
+    pub launch: bool,
 }
 
+data::diff::diff!(Ship {
+    craft,
+    rotation,
+    color,
+    ftl_timer,
+    missiles,
+    launch
+});
+
 impl Ship {
-    pub fn new(craft: impl Into<Handle<Craft>>, color: [f32; 3]) -> Self {
+    pub fn new(
+        craft: impl Into<Handle<Craft>>,
+        color: [f32; 3],
+        missiles: u32,
+    ) -> Self {
         Self {
             craft: craft.into(),
             rotation: Rotation::None,
             color,
             ftl_timer: 0.0,
+            missiles,
+            launch: false,
         }
     }
 
@@ -36,9 +58,16 @@ impl Ship {
             rotation: self.rotation.clone(),
             color: self.color.clone(),
             ftl_timer: self.ftl_timer.clone(),
+            missiles: self.missiles,
+            launch: self.launch,
         }
     }
 
+    pub fn remap(&mut self, handles: &data::server::HandleMap) -> Option<()> {
+        self.craft = handles.remap(&self.craft)?;
+        Some(())
+    }
+
     pub fn apply_input(
         &mut self,
         bodies: &mut store::Strong<Body>,
@@ -55,32 +84,58 @@ impl Ship {
             return None;
         }
 
-        let mut body = bodies.get_mut(&craft.body)?;
+        let body = bodies.get_mut(&craft.body)?;
+        self.apply_action(craft, body, action.kind);
+
+        Some(())
+    }
 
-        match action.kind {
+    /// Applies an action to the ship, and the craft and body it consists of
+    ///
+    /// The client uses this too, to predict the effects of its own actions.
+    pub fn apply_action(
+        &mut self,
+        craft: &mut Craft,
+        body: &mut Body,
+        kind: action::Kind,
+    ) {
+        match kind {
             action::Kind::Rotate(rotation) => {
                 self.rotation = rotation;
             }
             action::Kind::Thrust(thrust) => {
                 craft.engine_on = thrust;
             }
+            action::Kind::Fire(fire) => {
+                craft.firing = fire;
+            }
+            action::Kind::LaunchMissile => {
+                self.launch = true;
+            }
             action::Kind::FtlJump(time) => {
                 body.time_factor = 10_000.0;
                 self.ftl_timer = time;
             }
+            action::Kind::Maneuver(maneuver) => {
+                craft.schedule(maneuver);
+            }
+            action::Kind::CancelManeuvers => {
+                craft.maneuvers.clear();
+            }
+            action::Kind::TimeWarp(_) | action::Kind::Respawn => {
+                // Not specific to ships. Handled by the players feature.
+            }
         }
-
-        Some(())
     }
 
     pub fn update(
         &mut self,
         dt: Scalar,
-        bodies: &mut store::Strong<Body>,
-        crafts: &store::Strong<Craft>,
+        bodies: &mut impl store::GetMut<Body>,
+        crafts: &impl store::Get<Craft>,
     ) -> Option<()> {
         let craft = crafts.get(&self.craft)?;
-        let mut body = bodies.get_mut(&craft.body)?;
+        let body = bodies.get_mut(&craft.body)?;
 
         let rotation = self.rotation as i32 as Scalar;
         body.rot = Angle::two_pi() * 0.6 * rotation;
@@ -1,6 +1,6 @@
 use std::collections::HashSet;
 
-use rand::{prelude::*, thread_rng};
+use rand::Rng;
 use toadster::{
     handle::{self, Untyped},
     store,
@@ -29,6 +29,7 @@ impl ShipEntity {
     pub fn create(
         &self,
         planet: &Planet,
+        rng: &mut impl Rng,
         bodies: &mut store::Strong<Body>,
         crafts: &mut store::Strong<Craft>,
         fuels: &mut store::Strong<Fuel>,
@@ -41,21 +42,22 @@ impl ShipEntity {
         const THRUST: Scalar = 100.0;
         const FUEL: Scalar = 6_000.0;
         const HEALTH: Scalar = 10.0;
+        const AMMO: u32 = 100;
+        const MISSILES: u32 = 4;
 
         let distance = planet.radius * 1.5;
-        let angle = Angle::radians(
-            thread_rng().gen_range(0.0, Angle::two_pi().radians),
-        );
+        let angle = Angle::radians(rng.gen_range(0.0, Angle::two_pi().radians));
         let (sin, cos) = angle.sin_cos();
         let position =
             planet.pos + Vec2::new(sin * distance.0, cos * distance.0);
 
         // Compute velocity for circular orbit at the given distance.
         let speed = (G * planet.mass / distance.0).sqrt();
-        let velocity = rotate(
-            (position - planet.pos).normalize() * speed,
-            Angle::frac_pi_2(),
-        );
+        let velocity = planet.vel
+            + rotate(
+                (position - planet.pos).normalize() * speed,
+                Angle::frac_pi_2(),
+            );
 
         let pos = positions.insert(Position(position));
         let vel = velocities.insert(Velocity(velocity));
@@ -71,10 +73,15 @@ impl ShipEntity {
             engine_on: false,
             thrust: THRUST,
             owner: self.owner,
+            maneuvers: Vec::new(),
+
+            firing: false,
+            ammo: AMMO,
+            cooldown: 0.0,
         };
         let craft = crafts.insert(craft);
 
-        let ship = ships.insert(Ship::new(craft, self.color));
+        let ship = ships.insert(Ship::new(craft, self.color, MISSILES));
         healths
             .get_mut(&health)
             .unwrap()
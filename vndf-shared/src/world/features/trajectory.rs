@@ -0,0 +1,107 @@
+use toadster::store;
+
+use crate::world::{
+    math::{Integrator, Pnt2, Scalar, Vec2},
+    planets::{Planet, Planets},
+};
+
+/// A craft's predicted path, computed by numerical integration
+///
+/// Unlike `orbits::Orbit`, which only considers the dominant planet, this takes
+/// the gravity of all planets into account, as well as their movement and any
+/// planned burns. It should use the same integrator as the simulation, but
+/// generally with a larger time step.
+pub struct Trajectory {
+    /// Positions along the path, one per time step
+    ///
+    /// The first point is the craft's current position.
+    pub points: Vec<Pnt2>,
+
+    /// Velocity at the last point
+    pub vel: Vec2,
+
+    /// Whether the path ends prematurely, because the craft hits a planet
+    pub collides: bool,
+}
+
+impl Trajectory {
+    pub fn predict<S>(
+        mut pos: Pnt2,
+        mut vel: Vec2,
+        burns: &[Burn],
+        planets: &Planets<S>,
+        integrator: impl Integrator,
+        dt: Scalar,
+        steps: usize,
+    ) -> Self
+    where
+        S: store::Get<Planet> + for<'r> store::Values<'r, Planet>,
+    {
+        let mut points = Vec::with_capacity(steps + 1);
+        points.push(pos);
+
+        for step in 0..steps {
+            let t = step as Scalar * dt;
+
+            // This mirrors the order of things in the simulation: Planets are
+            // moved first, then everything else is integrated.
+            let moved = Planets(planets.after(t + dt));
+
+            let thrust = burns
+                .iter()
+                .fold(Vec2::zero(), |acc, burn| acc + burn.average_acc(t, dt));
+
+            integrator.integrate(dt, &mut pos, &mut vel, |pos| {
+                thrust + moved.acceleration_at(pos)
+            });
+            points.push(pos);
+
+            if moved.check_collision(pos) {
+                return Self {
+                    points,
+                    vel,
+                    collides: true,
+                };
+            }
+        }
+
+        Self {
+            points,
+            vel,
+            collides: false,
+        }
+    }
+}
+
+/// A burn that is planned for the future
+///
+/// Fuel consumption is not taken into account, so burns that would run out of
+/// fuel are predicted as if the craft had enough.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Burn {
+    /// Start of the burn, in seconds from now
+    pub start: Scalar,
+
+    /// Duration of the burn, in seconds
+    pub duration: Scalar,
+
+    /// The acceleration caused by the burn
+    pub acc: Vec2,
+}
+
+impl Burn {
+    /// The average acceleration during the time step that starts at `t`
+    ///
+    /// Burns are often much shorter than the time step used for prediction, so
+    /// only checking whether a burn is active would miss them.
+    pub fn average_acc(&self, t: Scalar, dt: Scalar) -> Vec2 {
+        let start = Scalar::max(t, self.start);
+        let end = Scalar::min(t + dt, self.start + self.duration);
+
+        if end <= start {
+            return Vec2::zero();
+        }
+
+        self.acc * (end - start) / dt
+    }
+}
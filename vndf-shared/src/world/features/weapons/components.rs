@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+use toadster::Handle;
+
+use crate::{
+    data::{self, Remap as _},
+    world::{
+        math::{closest_approach, Pnt2, Scalar, Vec2},
+        physics::Body,
+        players::PlayerId,
+    },
+};
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Projectile {
+    pub body: Handle<Body>,
+
+    /// The player whose craft fired the projectile
+    ///
+    /// Projectiles never hit the crafts of the player that fired them.
+    pub owner: PlayerId,
+
+    /// Time until the projectile disappears, in seconds
+    pub lifetime: Scalar,
+}
+
+data::diff::diff!(Projectile {
+    body,
+    owner,
+    lifetime
+});
+
+impl Projectile {
+    /// Speed relative to the craft that fires the projectile, in m/s
+    pub const SPEED: Scalar = 300.0;
+
+    /// Health that a craft loses when hit
+    pub const DAMAGE: Scalar = 2.5;
+
+    /// Time until a projectile that hasn't hit anything disappears, in seconds
+    pub const LIFETIME: Scalar = 30.0;
+
+    /// Distance within which a projectile hits a craft, in meters
+    pub const HIT_DISTANCE: Scalar = 10.0;
+
+    pub fn new(body: impl Into<Handle<Body>>, owner: PlayerId) -> Self {
+        Self {
+            body: body.into(),
+            owner,
+            lifetime: Self::LIFETIME,
+        }
+    }
+
+    pub fn to_weak(&self) -> Self {
+        Self {
+            body: self.body.as_weak(),
+            owner: self.owner,
+            lifetime: self.lifetime,
+        }
+    }
+
+    pub fn remap(&mut self, handles: &data::server::HandleMap) -> Option<()> {
+        self.body = handles.remap(&self.body)?;
+        Some(())
+    }
+
+    /// Whether the projectile came close enough to a target to hit it, during
+    /// the last step of length `dt`
+    ///
+    /// Considers the whole step, not just the current positions, so a fast
+    /// projectile can't pass through its target between two checks.
+    pub fn hits(
+        pos: Pnt2,
+        vel: Vec2,
+        target_pos: Pnt2,
+        target_vel: Vec2,
+        dt: Scalar,
+    ) -> bool {
+        let offset = target_pos - pos;
+        let rel_vel = target_vel - vel;
+
+        closest_approach(offset, rel_vel, dt) <= Self::HIT_DISTANCE
+    }
+}
@@ -0,0 +1,44 @@
+use std::collections::HashSet;
+
+use toadster::{handle, store};
+
+use crate::world::{
+    physics::{Body, Position, Velocity},
+    players::PlayerId,
+};
+
+use super::Projectile;
+
+pub struct ProjectileEntity {
+    pub owner: PlayerId,
+
+    /// The body of the craft that fires the projectile
+    pub firing: Body,
+}
+
+impl ProjectileEntity {
+    pub fn create(
+        &self,
+        bodies: &mut store::Strong<Body>,
+        positions: &mut store::Strong<Position>,
+        projectiles: &mut store::Strong<Projectile>,
+        velocities: &mut store::Strong<Velocity>,
+        index: &mut HashSet<handle::Strong<Projectile>>,
+    ) -> Option<handle::Strong<Projectile>> {
+        let dir = self.firing.dir.normalize();
+
+        let pos = *positions.get(&self.firing.pos)?;
+        let pos = positions.insert(pos);
+
+        let vel = velocities.get(&self.firing.vel)?.0 + dir * Projectile::SPEED;
+        let vel = velocities.insert(Velocity(vel));
+
+        let mut body = Body::new(pos, vel);
+        body.dir = dir;
+        let body = bodies.insert(body);
+
+        let projectile = projectiles.insert(Projectile::new(body, self.owner));
+        index.insert(projectile.clone());
+        Some(projectile)
+    }
+}
@@ -0,0 +1,68 @@
+use std::collections::HashSet;
+
+use toadster::{handle, store};
+
+use crate::world::{
+    base::Update,
+    crafts::Craft,
+    health::Health,
+    physics::{Body, Position, Velocity},
+    planets::Planet,
+};
+
+use super::{check_hits, fire_weapons, update_projectiles, Projectile};
+
+pub struct Feature {
+    pub index: HashSet<handle::Strong<Projectile>>,
+}
+
+impl Feature {
+    pub fn new() -> Self {
+        Self {
+            index: HashSet::new(),
+        }
+    }
+
+    pub fn on_update(
+        &mut self,
+        event: &Update,
+        bodies: &mut store::Strong<Body>,
+        crafts: &mut store::Strong<Craft>,
+        healths: &mut store::Strong<Health>,
+        planets: &store::Strong<Planet>,
+        positions: &mut store::Strong<Position>,
+        projectiles: &mut store::Strong<Projectile>,
+        velocities: &mut store::Strong<Velocity>,
+    ) {
+        // Hits are checked over the last step, so this needs to happen before
+        // new projectiles are fired. Otherwise, they would be treated as if
+        // they had been moving during that step.
+        check_hits(
+            event.dt,
+            bodies,
+            crafts,
+            healths,
+            planets,
+            positions,
+            projectiles,
+            velocities,
+            &mut self.index,
+        );
+        update_projectiles(event.dt, projectiles, &mut self.index);
+        fire_weapons(
+            event.dt,
+            bodies,
+            crafts,
+            positions,
+            projectiles,
+            velocities,
+            &mut self.index,
+        );
+    }
+}
+
+impl Default for Feature {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,6 @@
+pub mod components;
+pub mod entities;
+pub mod feature;
+pub mod systems;
+
+pub use self::{components::*, entities::*, feature::*, systems::*};
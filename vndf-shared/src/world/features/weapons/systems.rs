@@ -0,0 +1,121 @@
+use std::collections::HashSet;
+
+use toadster::{handle, store};
+
+use crate::world::{
+    crafts::Craft,
+    health::Health,
+    math::Scalar,
+    physics::{Body, Position, Velocity},
+    planets::{Planet, Planets},
+};
+
+use super::{Projectile, ProjectileEntity};
+
+pub fn fire_weapons(
+    dt: Scalar,
+    bodies: &mut store::Strong<Body>,
+    crafts: &mut store::Strong<Craft>,
+    positions: &mut store::Strong<Position>,
+    projectiles: &mut store::Strong<Projectile>,
+    velocities: &mut store::Strong<Velocity>,
+    index: &mut HashSet<handle::Strong<Projectile>>,
+) {
+    let mut fired = Vec::new();
+    for craft in crafts.values_mut() {
+        if !craft.update_weapon(dt) {
+            continue;
+        }
+        if let Some(body) = bodies.get(&craft.body) {
+            fired.push(ProjectileEntity {
+                owner: craft.owner,
+                firing: body.clone(),
+            });
+        }
+    }
+
+    for projectile in fired {
+        projectile.create(bodies, positions, projectiles, velocities, index);
+    }
+}
+
+/// Remove projectiles that hit a craft or a planet
+///
+/// Crafts that are hit lose health. What happens if that destroys them is up
+/// to the health feature.
+pub fn check_hits(
+    dt: Scalar,
+    bodies: &store::Strong<Body>,
+    crafts: &store::Strong<Craft>,
+    healths: &mut store::Strong<Health>,
+    planets: &store::Strong<Planet>,
+    positions: &store::Strong<Position>,
+    projectiles: &store::Strong<Projectile>,
+    velocities: &store::Strong<Velocity>,
+    index: &mut HashSet<handle::Strong<Projectile>>,
+) {
+    for (handle, projectile) in projectiles {
+        let hit = check_hit(
+            projectile, dt, bodies, crafts, healths, planets, positions,
+            velocities,
+        );
+        if hit.unwrap_or(false) {
+            index.remove(&handle);
+        }
+    }
+}
+
+fn check_hit(
+    projectile: &Projectile,
+    dt: Scalar,
+    bodies: &store::Strong<Body>,
+    crafts: &store::Strong<Craft>,
+    healths: &mut store::Strong<Health>,
+    planets: &store::Strong<Planet>,
+    positions: &store::Strong<Position>,
+    velocities: &store::Strong<Velocity>,
+) -> Option<bool> {
+    let body = bodies.get(&projectile.body)?;
+    let pos = positions.get(&body.pos)?.0;
+    let vel = velocities.get(&body.vel)?.0;
+
+    if Planets(planets).check_collision(pos) {
+        return Some(true);
+    }
+
+    for craft in crafts.values() {
+        if craft.owner == projectile.owner {
+            continue;
+        }
+
+        let target = match bodies.get(&craft.body) {
+            Some(target) => target,
+            None => continue,
+        };
+        let target_pos = positions.get(&target.pos)?.0;
+        let target_vel = velocities.get(&target.vel)?.0;
+
+        if Projectile::hits(pos, vel, target_pos, target_vel, dt) {
+            if let Some(health) = healths.get_mut(&craft.health) {
+                health.value -= Projectile::DAMAGE;
+            }
+            return Some(true);
+        }
+    }
+
+    Some(false)
+}
+
+/// Remove projectiles that haven't hit anything for too long
+pub fn update_projectiles(
+    dt: Scalar,
+    projectiles: &mut store::Strong<Projectile>,
+    index: &mut HashSet<handle::Strong<Projectile>>,
+) {
+    for (handle, projectile) in projectiles.iter_mut() {
+        projectile.lifetime -= dt;
+        if projectile.lifetime <= 0.0 {
+            index.remove(&handle);
+        }
+    }
+}
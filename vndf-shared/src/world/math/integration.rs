@@ -1,18 +1,107 @@
 /// Collection of symplectic numerical integrators
 ///
-/// Only one is used in the game, but the others are kept around for quick
-/// comparison. Since orbital mechanics are being simulated, only symplectic
-/// integrators are useful.
+/// Since orbital mechanics are being simulated, only symplectic integrators are
+/// useful. Which one is used can be selected at runtime, using `Method`.
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
 use crate::world::math::{Pnt2, Scalar, Vec2};
 
-pub fn integrate(
-    dt: Scalar,
-    pos: &mut Pnt2,
-    vel: &mut Vec2,
-    acc: impl Fn(Pnt2) -> Vec2,
-) {
-    // semi_implicit_euler(dt, pos, vel, acc)
-    velocity_verlet(dt, pos, vel, acc)
+pub trait Integrator {
+    /// Advance position and velocity by one time step
+    ///
+    /// `acc` computes the acceleration at a given position.
+    fn integrate(
+        &self,
+        dt: Scalar,
+        pos: &mut Pnt2,
+        vel: &mut Vec2,
+        acc: impl Fn(Pnt2) -> Vec2,
+    );
+}
+
+/// Selects one of the integrators at runtime
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Eq, PartialEq)]
+pub enum Method {
+    SemiImplicitEuler,
+    VelocityVerlet,
+    Yoshida4,
+    Yoshida6,
+    Pefrl,
+}
+
+impl Method {
+    pub const ALL: [Self; 5] = [
+        Self::SemiImplicitEuler,
+        Self::VelocityVerlet,
+        Self::Yoshida4,
+        Self::Yoshida6,
+        Self::Pefrl,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::SemiImplicitEuler => "semi-implicit-euler",
+            Self::VelocityVerlet => "velocity-verlet",
+            Self::Yoshida4 => "yoshida4",
+            Self::Yoshida6 => "yoshida6",
+            Self::Pefrl => "pefrl",
+        }
+    }
+}
+
+impl Default for Method {
+    fn default() -> Self {
+        Self::VelocityVerlet
+    }
+}
+
+impl Integrator for Method {
+    fn integrate(
+        &self,
+        dt: Scalar,
+        pos: &mut Pnt2,
+        vel: &mut Vec2,
+        acc: impl Fn(Pnt2) -> Vec2,
+    ) {
+        match self {
+            Self::SemiImplicitEuler => {
+                SemiImplicitEuler.integrate(dt, pos, vel, acc)
+            }
+            Self::VelocityVerlet => VelocityVerlet.integrate(dt, pos, vel, acc),
+            Self::Yoshida4 => Yoshida4.integrate(dt, pos, vel, acc),
+            Self::Yoshida6 => Yoshida6.integrate(dt, pos, vel, acc),
+            Self::Pefrl => Pefrl.integrate(dt, pos, vel, acc),
+        }
+    }
+}
+
+impl FromStr for Method {
+    type Err = UnknownMethod;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|method| method.name() == s)
+            .ok_or_else(|| UnknownMethod(s.to_owned()))
+    }
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug)]
+pub struct UnknownMethod(pub String);
+
+impl fmt::Display for UnknownMethod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unknown integration method: {}", self.0)
+    }
 }
 
 /// Semi-implicit Euler method
@@ -21,14 +110,19 @@ pub fn integrate(
 /// with a bigger time step, as is done when computing the projected path. As a
 /// result, the projected path is inaccurate, and varies according to the
 /// current position in the orbit.
-pub fn semi_implicit_euler(
-    dt: Scalar,
-    pos: &mut Pnt2,
-    vel: &mut Vec2,
-    acc: impl Fn(Pnt2) -> Vec2,
-) {
-    *vel += acc(*pos) * dt;
-    *pos += *vel * dt;
+pub struct SemiImplicitEuler;
+
+impl Integrator for SemiImplicitEuler {
+    fn integrate(
+        &self,
+        dt: Scalar,
+        pos: &mut Pnt2,
+        vel: &mut Vec2,
+        acc: impl Fn(Pnt2) -> Vec2,
+    ) {
+        *vel += acc(*pos) * dt;
+        *pos += *vel * dt;
+    }
 }
 
 /// Velocity Verlet method
@@ -40,21 +134,125 @@ pub fn semi_implicit_euler(
 /// In principle, it shows the same problems as semi-implicit Euler in regards
 /// to the path projection (as can be expected), but the improved accuracy makes
 /// it good enough for now.
-pub fn velocity_verlet(
+pub struct VelocityVerlet;
+
+impl Integrator for VelocityVerlet {
+    fn integrate(
+        &self,
+        dt: Scalar,
+        pos: &mut Pnt2,
+        vel: &mut Vec2,
+        acc: impl Fn(Pnt2) -> Vec2,
+    ) {
+        let acc_t = acc(*pos);
+        *pos += *vel * dt + acc_t * 0.5 * dt * dt;
+        let acc_t_plus_dt = acc(*pos);
+        *vel += (acc_t + acc_t_plus_dt) * 0.5 * dt;
+    }
+}
+
+/// Yoshida's fourth-order method
+///
+/// Composes three leapfrog steps, one of which goes backwards in time. This is
+/// the same method that is known as Forest-Ruth. Needs three evaluations of the
+/// acceleration per step.
+pub struct Yoshida4;
+
+impl Integrator for Yoshida4 {
+    fn integrate(
+        &self,
+        dt: Scalar,
+        pos: &mut Pnt2,
+        vel: &mut Vec2,
+        acc: impl Fn(Pnt2) -> Vec2,
+    ) {
+        let cbrt_2 = Scalar::cbrt(2.0);
+        let w1 = 1.0 / (2.0 - cbrt_2);
+        let w0 = -cbrt_2 * w1;
+
+        let c = [w1 / 2.0, (w0 + w1) / 2.0, (w0 + w1) / 2.0, w1 / 2.0];
+        let d = [w1, w0, w1];
+
+        drift_kick(dt, pos, vel, &acc, &c, &d);
+    }
+}
+
+/// Yoshida's sixth-order method
+///
+/// Composes three steps of `Yoshida4` in the same way that method composes
+/// leapfrog steps. Needs nine evaluations of the acceleration per step.
+pub struct Yoshida6;
+
+impl Integrator for Yoshida6 {
+    fn integrate(
+        &self,
+        dt: Scalar,
+        pos: &mut Pnt2,
+        vel: &mut Vec2,
+        acc: impl Fn(Pnt2) -> Vec2,
+    ) {
+        let root = Scalar::powf(2.0, 1.0 / 5.0);
+        let w1 = 1.0 / (2.0 - root);
+        let w0 = -root * w1;
+
+        for &w in &[w1, w0, w1] {
+            Yoshida4.integrate(dt * w, pos, vel, &acc);
+        }
+    }
+}
+
+/// Position-extended Forest-Ruth-like method (PEFRL), by Omelyan et al.
+///
+/// Fourth-order, like `Yoshida4`, but with a much smaller error, at the cost
+/// of one more evaluation of the acceleration per step.
+pub struct Pefrl;
+
+impl Integrator for Pefrl {
+    fn integrate(
+        &self,
+        dt: Scalar,
+        pos: &mut Pnt2,
+        vel: &mut Vec2,
+        acc: impl Fn(Pnt2) -> Vec2,
+    ) {
+        const XI: Scalar = 0.178_617_895_844_809_1;
+        const LAMBDA: Scalar = -0.212_341_831_062_605_4;
+        const CHI: Scalar = -0.066_264_582_669_818_49;
+
+        let c = [XI, CHI, 1.0 - 2.0 * (CHI + XI), CHI, XI];
+        let d = [
+            (1.0 - 2.0 * LAMBDA) / 2.0,
+            LAMBDA,
+            LAMBDA,
+            (1.0 - 2.0 * LAMBDA) / 2.0,
+        ];
+
+        drift_kick(dt, pos, vel, &acc, &c, &d);
+    }
+}
+
+/// Alternately updates position and velocity, using the given coefficients
+///
+/// `c` must have one more element than `d`. The sequence starts and ends with
+/// a position update.
+fn drift_kick(
     dt: Scalar,
     pos: &mut Pnt2,
     vel: &mut Vec2,
     acc: impl Fn(Pnt2) -> Vec2,
+    c: &[Scalar],
+    d: &[Scalar],
 ) {
-    let acc_t = acc(*pos);
-    *pos += *vel * dt + acc_t * 0.5 * dt * dt;
-    let acc_t_plus_dt = acc(*pos);
-    *vel += (acc_t + acc_t_plus_dt) * 0.5 * dt;
+    for (&c, &d) in c.iter().zip(d) {
+        *pos += *vel * c * dt;
+        *vel += acc(*pos) * d * dt;
+    }
+    *pos += *vel * c[c.len() - 1] * dt;
 }
 
 // I found a helpful page that lists a bunch of symplectic integrators:
 // https://docs.juliadiffeq.org/latest/solvers/dynamical_solve/#Symplectic-Integrators-1
 //
-// The list on that page has a not for each of the integrators listed. If more
-// accuracy is desired, it might make sense to go through the higher-order
+// The list on that page has a note for each of the integrators listed. If more
+// accuracy is desired, it might make sense to go through the remaining
 // integrators in that list, and see if they can be applied here.
@@ -1,6 +1,6 @@
 pub mod integration;
 
-pub use integration::integrate;
+pub use integration::{Integrator, Method};
 
 use euclid;
 
@@ -20,3 +20,20 @@ pub fn rotate(vec: Vec2, angle: Angle) -> Vec2 {
     let rot = euclid::Rotation2D::new(angle);
     rot.transform_vector(vec)
 }
+
+/// The smallest distance between two objects during the last `dt` seconds
+///
+/// `offset` is their current offset from each other, and `rel_vel` the rate at
+/// which that offset changes. Assumes they moved in straight lines. To look
+/// ahead instead, pass the negated `rel_vel`.
+pub fn closest_approach(offset: Vec2, rel_vel: Vec2, dt: Scalar) -> Scalar {
+    // Going back in time by `t`, the offset was `offset - rel_vel * t`.
+    let speed_sq = rel_vel.square_length();
+    let t = if speed_sq > 0.0 {
+        (offset.dot(rel_vel) / speed_sq).clamp(0.0, dt)
+    } else {
+        0.0
+    };
+
+    (offset - rel_vel * t).length()
+}
@@ -1,8 +1,15 @@
 pub mod features;
 pub mod math;
+pub mod snapshot;
 
-pub use self::math::{Angle, Length, Pnt2, Scalar, Size, Vec2};
+pub use self::{
+    math::{Angle, Length, Method, Pnt2, Scalar, Size, Vec2},
+    snapshot::Snapshot,
+};
+
+use std::{collections::HashSet, net::SocketAddr};
 
+use rand::{rngs::StdRng, SeedableRng as _};
 use rinnsal::{EventSink, EventSource};
 use toadster::handle;
 
@@ -10,13 +17,14 @@ use crate::data;
 
 use self::features::{
     base::{self, ComponentRemoved, Update},
-    crafts, explosions, health, physics,
-    planets::{self, Planet, Planets},
+    crafts, explosions, health, interest, missiles, physics,
+    planets::{self, Planet, Planets, System},
     players::{
         self, InputHandled, PlayerConnected, PlayerCreated, PlayerDisconnected,
-        PlayerId, PlayerInput,
+        PlayerId, PlayerInput, PlayerReconnected, PlayerTakenOver,
+        ShipDestroyed,
     },
-    ships,
+    ships, weapons,
 };
 
 pub const TARGET_FPS: u32 = 60;
@@ -25,40 +33,185 @@ pub const FRAME_TIME: Scalar = 1.0 / TARGET_FPS as Scalar;
 pub struct State {
     data: data::server::Components,
 
+    // All randomness in the world must come from here, to keep the simulation
+    // reproducible for a given seed.
+    rng: StdRng,
+
+    /// The number of updates that have been handled so far
+    tick: u64,
+
+    /// The factor by which time currently passes faster for everyone
+    time_warp: Scalar,
+
     base: base::Feature,
     crafts: crafts::Feature,
     explosions: explosions::Feature,
     health: health::Feature,
+    missiles: missiles::Feature,
     physics: physics::Feature,
     players: players::Feature,
     ships: ships::Feature,
+    weapons: weapons::Feature,
 
-    // Need to keep this handle, otherwise planet will get garbage-collected.
-    _planet: handle::Strong<Planet>,
+    // Need to keep these handles, otherwise planets will get garbage-collected.
+    // The first one is the primary of the system.
+    planets: Vec<handle::Strong<Planet>>,
 }
 
 impl State {
-    pub fn new() -> Self {
+    pub fn new(seed: u64) -> Self {
+        Self::from_system(&System::default(), seed)
+    }
+
+    pub fn from_system(system: &System, seed: u64) -> Self {
         let mut data = data::server::Components::new();
+        let planets = system.create(&mut data.planets);
 
-        let planet = data.planets.insert(Planet {
-            pos: Pnt2::new(0.0, 0.0),
-            radius: Length::new(60_268_000.0), // size of Saturn (in m)
-            mass: 5.6834e26,                   // mass of Saturn (in kg)
-        });
+        Self::from_data(data, planets, seed)
+    }
+
+    /// Restore a world from a snapshot
+    ///
+    /// The snapshot doesn't include the state of the random number generator,
+    /// so it is seeded anew from the given seed.
+    pub fn restore(
+        snapshot: Snapshot,
+        seed: u64,
+    ) -> Result<Self, snapshot::Error> {
+        // Entities that have health are kept alive by the health index, which
+        // refers to them through the health component's parent.
+        let parents: Vec<_> = snapshot
+            .components
+            .healths
+            .iter()
+            .filter(|(_, health)| !health.is_dead())
+            .filter_map(|(_, health)| health.parent_ref().cloned())
+            .collect();
+
+        let (data, handles) =
+            data::server::Components::restore(snapshot.components)
+                .ok_or(snapshot::Error::Inconsistent)?;
+        let planets: Vec<_> = snapshot
+            .planets
+            .iter()
+            .map(|planet| handles.planets.get(planet).cloned())
+            .collect::<Option<_>>()
+            .ok_or(snapshot::Error::Inconsistent)?;
+        if planets.is_empty() {
+            return Err(snapshot::Error::NoPlanets);
+        }
+
+        let mut state = Self::from_data(data, planets, seed);
+        state.tick = snapshot.tick;
+        state.players.next_id = snapshot.next_player_id;
+
+        for parent in parents {
+            if let Some(parent) = parent.remap(&handles) {
+                state.health.index.insert(parent.into_strong_untyped());
+            }
+        }
+        for explosion in snapshot.explosions {
+            let explosion = handles
+                .explosions
+                .get(&explosion)
+                .ok_or(snapshot::Error::Inconsistent)?;
+            state.explosions.index.insert(explosion.clone());
+        }
+        for projectile in snapshot.projectiles {
+            let projectile = handles
+                .projectiles
+                .get(&projectile)
+                .ok_or(snapshot::Error::Inconsistent)?;
+            state.weapons.index.insert(projectile.clone());
+        }
+        state.players.restore(
+            state.tick,
+            &mut state.data.players,
+            handles.players.values().cloned(),
+        );
 
+        // The snapshot might contain components that were about to be
+        // garbage-collected. Removing a component can release the last handle
+        // to another one, so we need to keep going until nothing changes.
+        drop(handles);
+        loop {
+            let before = state.diagnostics();
+            state.data.apply_changes();
+            if state.diagnostics() == before {
+                break;
+            }
+        }
+
+        Ok(state)
+    }
+
+    fn from_data(
+        data: data::server::Components,
+        planets: Vec<handle::Strong<Planet>>,
+        seed: u64,
+    ) -> Self {
         Self {
             data,
+            rng: StdRng::seed_from_u64(seed),
+            tick: 0,
+            time_warp: 1.0,
 
             base: base::Feature::new(),
             crafts: crafts::Feature::new(),
             explosions: explosions::Feature::new(),
             health: health::Feature::new(),
+            missiles: missiles::Feature::new(),
             physics: physics::Feature::new(),
             players: players::Feature::new(),
             ships: ships::Feature::new(),
+            weapons: weapons::Feature::new(),
+
+            planets,
+        }
+    }
+
+    pub fn time_warp(&self) -> Scalar {
+        self.time_warp
+    }
+
+    /// Select the integrator that is used to move bodies
+    pub fn set_integrator(&mut self, integrator: Method) {
+        self.physics.integrator = integrator;
+    }
 
-            _planet: planet,
+    /// Set how long a disconnected player's ship stays around, in seconds
+    ///
+    /// Within that time, the player can reconnect and take it over again.
+    pub fn set_disconnect_timeout(&mut self, timeout: Scalar) {
+        self.players.disconnect_timeout = timeout;
+    }
+
+    /// The number of updates that have been handled so far
+    ///
+    /// Any event that is pushed now is handled in the same `dispatch` call as
+    /// the update with this number.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            tick: self.tick,
+            components: self.data.snapshot(),
+            planets: self.planets.iter().map(|planet| planet.into()).collect(),
+            explosions: self
+                .explosions
+                .index
+                .iter()
+                .map(|explosion| explosion.into())
+                .collect(),
+            projectiles: self
+                .weapons
+                .index
+                .iter()
+                .map(|projectile| projectile.into())
+                .collect(),
+            next_player_id: self.players.next_id,
         }
     }
 
@@ -81,6 +234,28 @@ impl State {
     pub fn dispatch(&mut self) {
         self.data.apply_changes();
         for event in self.base.update.source().ready() {
+            self.tick += 1;
+
+            // Time warp applies to everything, so it's easiest to just scale
+            // the time step.
+            let event = Update {
+                dt: event.dt * self.time_warp,
+            };
+
+            planets::update_planets(event.dt, &mut self.data.planets);
+
+            self.missiles.on_update(
+                &event,
+                &mut self.data.bodies,
+                &mut self.data.crafts,
+                &mut self.data.fuels,
+                &mut self.data.healths,
+                &mut self.data.missiles,
+                &mut self.data.positions,
+                &mut self.data.ships,
+                &mut self.data.velocities,
+                &mut self.health.index,
+            );
             self.crafts.on_update(
                 &event,
                 &mut self.data.bodies,
@@ -88,6 +263,23 @@ impl State {
                 &mut self.data.fuels,
             );
             self.explosions.on_update(&event, &mut self.data.explosions);
+            self.weapons.on_update(
+                &event,
+                &mut self.data.bodies,
+                &mut self.data.crafts,
+                &mut self.data.healths,
+                &self.data.planets,
+                &mut self.data.positions,
+                &mut self.data.projectiles,
+                &mut self.data.velocities,
+            );
+            self.players.on_update(
+                self.tick,
+                &self.data.crafts,
+                &mut self.data.players,
+                &self.data.ships,
+                &mut self.health.index,
+            );
 
             let mut planets = planets::Systems {
                 bodies: &mut self.data.bodies,
@@ -114,12 +306,17 @@ impl State {
         }
         self.data.apply_changes();
         while let Some(event) = self.players.player_connected.source().next() {
-            // We only have one planet right now.
-            let planet = self.data.planets.iter().next().unwrap().1;
+            // Ships always start out orbiting the primary.
+            let planet = match self.planets.first() {
+                Some(planet) => self.data.planets.get(planet).unwrap(),
+                None => continue,
+            };
 
             self.players.on_player_connected(
                 &event,
+                self.tick,
                 planet,
+                &mut self.rng,
                 &mut self.data.bodies,
                 &mut self.data.crafts,
                 &mut self.data.fuels,
@@ -134,19 +331,38 @@ impl State {
         self.data.apply_changes();
         while let Some(event) = self.players.player_disconnected.source().next()
         {
-            self.players.on_player_disconnected(&event);
+            self.players.on_player_disconnected(
+                &event,
+                self.tick,
+                &mut self.data.players,
+            );
         }
         self.data.apply_changes();
         while let Some(event) = self.players.player_input.source().next() {
+            // New ships always start out orbiting the primary.
+            let planet = match self.planets.first() {
+                Some(planet) => self.data.planets.get(planet).unwrap(),
+                None => continue,
+            };
+
             self.players.on_player_input(
                 &event,
+                self.tick,
+                planet,
+                &mut self.rng,
                 &mut self.data.bodies,
                 &mut self.data.crafts,
-                &self.data.players,
+                &mut self.data.fuels,
+                &mut self.data.healths,
+                &mut self.data.players,
+                &mut self.data.positions,
                 &mut self.data.ships,
+                &mut self.data.velocities,
+                &mut self.health.index,
             );
         }
         self.data.apply_changes();
+        self.time_warp = players::time_warp(&self.data.players);
         while let Some(event) = self.health.death.source().next() {
             self.explosions.on_death(
                 &event,
@@ -194,12 +410,18 @@ impl State {
         let healths = self.data.healths.iter().map(|(handle, c)| {
             data::client::Component::Health(handle.into(), c.to_weak())
         });
+        let missiles = self.data.missiles.iter().map(|(handle, c)| {
+            data::client::Component::Missile(handle.into(), c.to_weak())
+        });
         let planets = self.data.planets.iter().map(|(handle, c)| {
             data::client::Component::Planet(handle.into(), c.to_weak())
         });
         let positions = self.data.positions.iter().map(|(handle, c)| {
             data::client::Component::Position(handle.into(), c.to_weak())
         });
+        let projectiles = self.data.projectiles.iter().map(|(handle, c)| {
+            data::client::Component::Projectile(handle.into(), c.to_weak())
+        });
         let ships = self.data.ships.iter().map(|(handle, c)| {
             data::client::Component::Ship(handle.into(), c.to_weak())
         });
@@ -212,8 +434,10 @@ impl State {
             .chain(explosions)
             .chain(fuels)
             .chain(healths)
+            .chain(missiles)
             .chain(planets)
             .chain(positions)
+            .chain(projectiles)
             .chain(ships)
             .chain(velocities)
     }
@@ -244,11 +468,21 @@ impl State {
             let event = ComponentRemoved { handle };
             self.base.component_removed.sink().push(event);
         }
+        for handle in self.data.missiles.removed().ready() {
+            let handle = data::client::Handle::Missile(handle.into());
+            let event = ComponentRemoved { handle };
+            self.base.component_removed.sink().push(event);
+        }
         for handle in self.data.positions.removed().ready() {
             let handle = data::client::Handle::Position(handle.into());
             let event = ComponentRemoved { handle };
             self.base.component_removed.sink().push(event);
         }
+        for handle in self.data.projectiles.removed().ready() {
+            let handle = data::client::Handle::Projectile(handle.into());
+            let event = ComponentRemoved { handle };
+            self.base.component_removed.sink().push(event);
+        }
         for handle in self.data.ships.removed().ready() {
             let handle = data::client::Handle::Ship(handle.into());
             let event = ComponentRemoved { handle };
@@ -267,10 +501,37 @@ impl State {
         self.players.player_created.source()
     }
 
+    pub fn player_reconnected(&mut self) -> EventSource<PlayerReconnected> {
+        self.players.player_reconnected.source()
+    }
+
+    pub fn player_taken_over(&mut self) -> EventSource<PlayerTakenOver> {
+        self.players.player_taken_over.source()
+    }
+
+    pub fn ship_destroyed(&mut self) -> EventSource<ShipDestroyed> {
+        self.players.ship_destroyed.source()
+    }
+
     pub fn input_handled(&mut self) -> EventSource<InputHandled> {
         self.players.input_handled.source()
     }
 
+    /// The components that the player with the given address needs to know
+    /// about
+    pub fn interest(
+        &self,
+        addr: SocketAddr,
+        range: Scalar,
+    ) -> HashSet<data::client::Handle> {
+        self.data
+            .players
+            .values()
+            .find(|player| player.addr == Some(addr))
+            .map(|player| interest::interest(player.id, range, &self.data))
+            .unwrap_or_default()
+    }
+
     pub fn diagnostics(&self) -> data::server::Diagnostics {
         (&self.data).into()
     }
@@ -0,0 +1,82 @@
+use std::{fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+use toadster::handle;
+
+use crate::{
+    data,
+    world::{
+        explosions::Explosion, planets::Planet, players::PlayerId,
+        weapons::Projectile,
+    },
+};
+
+/// Everything needed to restore a world, after the server was restarted
+///
+/// See `State::snapshot` and `State::restore`.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Snapshot {
+    pub tick: u64,
+    pub components: data::server::Snapshot,
+
+    // Handles that keep components alive, but live outside of the components.
+    // The health index is not included, as it can be reconstructed from the
+    // health components.
+    pub planets: Vec<handle::Weak<Planet>>,
+    pub explosions: Vec<handle::Weak<Explosion>>,
+    pub projectiles: Vec<handle::Weak<Projectile>>,
+
+    pub next_player_id: PlayerId,
+}
+
+impl Snapshot {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let bytes = fs::read(path)?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Decode a snapshot, as it is stored in a file
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let snapshot = postcard::from_bytes(bytes)?;
+        Ok(snapshot)
+    }
+
+    /// Write the snapshot to the given path
+    ///
+    /// The snapshot is written to a temporary file first, so a crash while
+    /// saving can't corrupt a previous snapshot.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let tmp = path.with_extension("tmp");
+
+        let bytes = postcard::to_stdvec(self)?;
+        fs::write(&tmp, bytes)?;
+        fs::rename(tmp, path)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Postcard(postcard::Error),
+
+    /// The snapshot refers to components that are not part of it
+    Inconsistent,
+
+    /// The snapshot has no planets, so there's nowhere to spawn ships
+    NoPlanets,
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<postcard::Error> for Error {
+    fn from(err: postcard::Error) -> Self {
+        Self::Postcard(err)
+    }
+}
@@ -10,6 +10,14 @@ use vndf_shared::{main_loop::main_loop, net};
 struct Options {
     #[structopt(short, long, default_value = "auto")]
     graphics: client::Graphics,
+
+    /// Transport the client connects over (tcp or udp)
+    #[structopt(long, default_value = "tcp")]
+    transport: net::Transport,
+
+    /// Seed for the world's random number generator (random, if not given)
+    #[structopt(long)]
+    seed: Option<u64>,
 }
 
 fn main() -> Result<(), Error> {
@@ -20,11 +28,17 @@ fn main() -> Result<(), Error> {
 
     let options = Options::from_args();
 
-    let mut server = Server::start_local().map_err(|err| Error::Init(err))?;
+    let seed = options.seed.unwrap_or_else(rand::random);
+
+    let mut server = Server::start_local(seed, options.transport)
+        .map_err(|err| Error::Init(err))?;
     let addr = server.addr();
 
+    // The server is started fresh every time, so there's never a player to
+    // reconnect to.
     thread::spawn(move || main_loop(|| server.update()));
-    client::start(addr, options.graphics).map_err(|err| Error::Run(err))
+    client::start(addr, options.transport, options.graphics, None, |_| {})
+        .map_err(|err| Error::Run(err))
 }
 
 #[derive(Debug)]
@@ -0,0 +1,149 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use vndf_server::server::Server;
+use vndf_shared::net::{
+    client::Conn,
+    delta::Update,
+    msg::{self, Capabilities, Rejection},
+    Message, Transport,
+};
+
+#[test]
+fn handshake_messages_should_keep_their_encoding() {
+    // Clients and servers of any version need to be able to decode these, so
+    // their encoding must never change.
+    let hello = msg::FromClient::Hello {
+        version: 1,
+        capabilities: Capabilities(1),
+        color: [0.0, 0.0, 0.0],
+    };
+    let accepted = msg::FromServer::Accepted {
+        capabilities: Capabilities(1),
+    };
+    let rejected = msg::FromServer::Rejected(Rejection::UnsupportedVersion {
+        client: 2,
+        server: 1,
+    });
+
+    #[rustfmt::skip]
+    assert_eq!(
+        encode(&hello),
+        [
+            0,
+            1, 0, 0, 0,
+            1, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ],
+    );
+    assert_eq!(encode(&accepted), [0, 1, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(encode(&rejected), [1, 0, 2, 0, 0, 0, 1, 0, 0, 0]);
+}
+
+#[test]
+fn server_should_reject_clients_with_another_protocol_version() {
+    let mut server = Server::start_local(0, Transport::Tcp).unwrap();
+    let mut conn = Conn::connect(server.addr(), Transport::Tcp).unwrap();
+
+    conn.send(msg::FromClient::Hello {
+        version: msg::PROTOCOL_VERSION + 1,
+        capabilities: msg::CAPABILITIES,
+        color: [1.0, 1.0, 0.0],
+    })
+    .unwrap();
+
+    let messages = receive_for(&mut server, &mut conn, 200);
+    assert_eq!(
+        messages[0],
+        msg::FromServer::Rejected(Rejection::UnsupportedVersion {
+            client: msg::PROTOCOL_VERSION + 1,
+            server: msg::PROTOCOL_VERSION,
+        })
+    );
+    assert!(!messages
+        .iter()
+        .any(|message| matches!(message, msg::FromServer::Welcome { .. })));
+}
+
+#[test]
+fn server_should_only_use_capabilities_both_sides_support() {
+    let mut server = Server::start_local(0, Transport::Tcp).unwrap();
+    let mut conn = Conn::connect(server.addr(), Transport::Tcp).unwrap();
+
+    // Announces a capability the server doesn't know about, but not the one
+    // for delta updates.
+    conn.send(msg::FromClient::Hello {
+        version: msg::PROTOCOL_VERSION,
+        capabilities: Capabilities(1 << 63),
+        color: [1.0, 1.0, 0.0],
+    })
+    .unwrap();
+
+    let mut messages = Vec::new();
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_millis(300) {
+        server.update();
+
+        let mut acks = Vec::new();
+        for message in conn.incoming() {
+            let message = message.unwrap();
+            if let msg::FromServer::Updates { tick, .. } = &message {
+                acks.push(*tick);
+            }
+            messages.push(message);
+        }
+        for tick in acks {
+            conn.send(msg::FromClient::Ack { tick }).unwrap();
+        }
+
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    assert_eq!(
+        messages[0],
+        msg::FromServer::Accepted {
+            capabilities: Capabilities::NONE,
+        }
+    );
+
+    let updates: Vec<_> = messages
+        .iter()
+        .filter_map(|message| match message {
+            msg::FromServer::Updates { updates, .. } => Some(updates),
+            _ => None,
+        })
+        .flatten()
+        .collect();
+    assert!(!updates.is_empty());
+    assert!(updates
+        .iter()
+        .all(|update| matches!(update, Update::Full(_))));
+}
+
+fn receive_for(
+    server: &mut Server,
+    conn: &mut Conn,
+    millis: u64,
+) -> Vec<msg::FromServer> {
+    let mut messages = Vec::new();
+
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_millis(millis) {
+        server.update();
+        for message in conn.incoming() {
+            messages.push(message.unwrap());
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    messages
+}
+
+/// Encodes a message, without the length prefix
+fn encode(message: &impl Message) -> Vec<u8> {
+    let mut buf = Vec::new();
+    message.write(&mut buf).unwrap();
+    buf.split_off(4)
+}
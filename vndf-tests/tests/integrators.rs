@@ -0,0 +1,80 @@
+use std::f64::consts::PI;
+
+use vndf_shared::world::{math::Integrator, Method, Pnt2, Vec2};
+
+// Units are chosen so the orbit has a semi-major axis of 1 and a period of 2π.
+const MU: f64 = 1.0;
+const ECCENTRICITY: f64 = 0.5;
+
+const ORBITS: usize = 100;
+const STEPS_PER_ORBIT: usize = 200;
+
+#[test]
+fn higher_order_integrators_should_drift_less() {
+    let euler = energy_drift(Method::SemiImplicitEuler);
+    let verlet = energy_drift(Method::VelocityVerlet);
+    let yoshida4 = energy_drift(Method::Yoshida4);
+    let yoshida6 = energy_drift(Method::Yoshida6);
+    let pefrl = energy_drift(Method::Pefrl);
+
+    assert!(verlet < euler, "{:e} >= {:e}", verlet, euler);
+    assert!(yoshida4 < verlet, "{:e} >= {:e}", yoshida4, verlet);
+    assert!(pefrl < yoshida4, "{:e} >= {:e}", pefrl, yoshida4);
+    assert!(yoshida6 < yoshida4, "{:e} >= {:e}", yoshida6, yoshida4);
+}
+
+#[test]
+fn symplectic_integrators_should_not_accumulate_energy_error() {
+    for &method in &Method::ALL {
+        let first = energy_drift_over(method, 0..10);
+        let last = energy_drift_over(method, ORBITS - 10..ORBITS);
+
+        // The error oscillates, but doesn't grow over time.
+        assert!(last < first * 2.0, "{}: {} -> {}", method, first, last);
+    }
+}
+
+#[test]
+fn methods_should_be_parsed_from_their_names() {
+    for &method in &Method::ALL {
+        assert_eq!(method.name().parse::<Method>().unwrap(), method);
+    }
+    assert!("runge-kutta".parse::<Method>().is_err());
+}
+
+/// Maximum relative energy error over all orbits
+fn energy_drift(method: Method) -> f64 {
+    energy_drift_over(method, 0..ORBITS)
+}
+
+/// Maximum relative energy error over the given range of orbits
+fn energy_drift_over(method: Method, orbits: std::ops::Range<usize>) -> f64 {
+    // Start at periapsis.
+    let periapsis = 1.0 - ECCENTRICITY;
+    let speed = (MU * (1.0 + ECCENTRICITY) / periapsis).sqrt();
+
+    let mut pos = Pnt2::new(periapsis, 0.0);
+    let mut vel = Vec2::new(0.0, speed);
+
+    let initial = energy(pos, vel);
+    let dt = 2.0 * PI / STEPS_PER_ORBIT as f64;
+
+    let mut max_error: f64 = 0.0;
+    for step in 0..orbits.end * STEPS_PER_ORBIT {
+        method.integrate(dt, &mut pos, &mut vel, |pos| {
+            let r = pos.to_vector();
+            -r * MU / r.length().powi(3)
+        });
+
+        if step >= orbits.start * STEPS_PER_ORBIT {
+            let error = ((energy(pos, vel) - initial) / initial).abs();
+            max_error = max_error.max(error);
+        }
+    }
+
+    max_error
+}
+
+fn energy(pos: Pnt2, vel: Vec2) -> f64 {
+    vel.square_length() / 2.0 - MU / pos.to_vector().length()
+}
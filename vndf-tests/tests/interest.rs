@@ -0,0 +1,62 @@
+use std::collections::HashSet;
+
+use vndf_shared::{
+    data::client::{Component, Handle},
+    sim::Simulation,
+    world::features::players::PlayerId,
+};
+
+#[test]
+fn players_should_only_be_interested_in_crafts_in_range() {
+    let mut sim = Simulation::new(0);
+
+    sim.connect(1, [1.0, 1.0, 0.0]);
+    sim.connect(2, [0.0, 1.0, 1.0]);
+    let tick = sim.step();
+
+    let crafts: Vec<_> = tick
+        .updates
+        .iter()
+        .filter_map(|component| match component {
+            Component::Craft(handle, craft) => {
+                Some((Handle::Craft(handle.clone()), craft.owner))
+            }
+            _ => None,
+        })
+        .collect();
+    let planets: HashSet<_> = tick
+        .updates
+        .iter()
+        .filter(|component| matches!(component, Component::Planet(_, _)))
+        .map(Handle::from_component)
+        .collect();
+    let craft_of = |owner: PlayerId| {
+        crafts
+            .iter()
+            .find(|(_, craft_owner)| *craft_owner == owner)
+            .map(|(handle, _)| handle.clone())
+            .unwrap()
+    };
+
+    let own = craft_of(tick.players_created[0].1);
+    let other = craft_of(tick.players_created[1].1);
+    let addr = Simulation::address(tick.players_created[0].0);
+
+    let near = sim.state().interest(addr, 0.0);
+    assert!(near.is_superset(&planets));
+    assert!(near.contains(&own));
+    assert!(!near.contains(&other));
+
+    let far = sim.state().interest(addr, 1e12);
+    assert!(far.contains(&own));
+    assert!(far.contains(&other));
+}
+
+#[test]
+fn unknown_players_should_not_be_interested_in_anything() {
+    let mut sim = Simulation::new(0);
+    sim.step();
+
+    let interest = sim.state().interest(Simulation::address(1), 1e12);
+    assert!(interest.is_empty());
+}
@@ -0,0 +1,225 @@
+use vndf_shared::{
+    action,
+    data::client::{Component, Handle},
+    sim::{trace::Tick, Simulation},
+    world::{
+        self,
+        features::{
+            crafts::Craft, health::Health, missiles::Missile,
+            players::PlayerId, ships::Ship,
+        },
+        Pnt2, Snapshot, Vec2,
+    },
+};
+
+#[test]
+fn ship_should_launch_missiles_at_the_nearest_other_ship() {
+    let mut sim = Simulation::new(0);
+
+    sim.connect(1, [1.0, 1.0, 0.0]);
+    sim.connect(2, [0.0, 1.0, 1.0]);
+    let tick = sim.step();
+    let shooter = tick.players_created[0].1;
+    let target = tick.players_created[1].1;
+    let missiles = ship(&sim.step(), shooter).missiles;
+
+    // The input is handled after the update, so the missile is launched on
+    // the next one.
+    sim.input(1, action::Kind::LaunchMissile);
+    sim.step();
+    let tick = sim.step();
+
+    let launched: Vec<_> = tick
+        .updates
+        .iter()
+        .filter_map(|component| match component {
+            Component::Missile(_, missile) => Some(missile),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(launched.len(), 1);
+
+    let target = ship(&tick, target);
+    assert_eq!(
+        launched[0].target.as_ref().map(|target| target.weak()),
+        Some(target.craft.weak()),
+    );
+    assert_eq!(ship(&tick, shooter).missiles, missiles - 1);
+}
+
+#[test]
+fn ship_should_not_launch_missiles_without_a_target() {
+    let mut sim = Simulation::new(0);
+
+    sim.connect(1, [1.0, 1.0, 0.0]);
+    let id = sim.step().players_created[0].1;
+    let missiles = ship(&sim.step(), id).missiles;
+
+    // The input is handled after the update, so the missile is launched on
+    // the next one.
+    sim.input(1, action::Kind::LaunchMissile);
+    sim.step();
+    let tick = sim.step();
+    assert!(!tick
+        .updates
+        .iter()
+        .any(|component| matches!(component, Component::Missile(..))));
+    assert_eq!(ship(&tick, id).missiles, missiles);
+    assert!(!ship(&tick, id).launch);
+}
+
+#[test]
+fn missile_should_home_in_on_its_target_and_explode() {
+    let mut sim = Simulation::new(0);
+
+    sim.connect(1, [1.0, 1.0, 0.0]);
+    sim.connect(2, [0.0, 1.0, 1.0]);
+    let tick = sim.step();
+    let shooter = tick.players_created[0].1;
+    let target = tick.players_created[1].1;
+
+    // The target is some distance away, and moving sideways. A missile that
+    // just heads straight for it would miss.
+    let mut snapshot = sim.state().snapshot();
+    place(
+        &mut snapshot,
+        shooter,
+        target,
+        Vec2::new(3000.0, 0.0),
+        Vec2::new(0.0, 150.0),
+    );
+    let components = &mut snapshot.components;
+    for (_, ship) in &mut components.ships {
+        let (_, craft) = components
+            .crafts
+            .iter()
+            .find(|(handle, _)| *handle == ship.craft.weak())
+            .unwrap();
+        if craft.owner == shooter {
+            ship.launch = true;
+        }
+    }
+    let mut sim =
+        Simulation::from_state(world::State::restore(snapshot, 0).unwrap());
+
+    let mut removals = Vec::new();
+    for _ in 0..600 {
+        removals.extend(sim.step().removals);
+    }
+    let tick = sim.step();
+
+    assert!(health(&tick, target).value < 10.0);
+    assert_eq!(health(&tick, shooter).value, 10.0);
+    assert!(removals
+        .iter()
+        .any(|handle| matches!(handle, Handle::Missile(_))));
+}
+
+#[test]
+fn guidance_should_lead_targets_that_move_sideways() {
+    let pos = Pnt2::new(0.0, 0.0);
+    let target = Pnt2::new(1000.0, 0.0);
+
+    let closing = Vec2::new(-100.0, 0.0);
+    let sideways = Vec2::new(0.0, 100.0);
+
+    let straight = Missile::guidance(pos, Vec2::zero(), target, closing, 300.0);
+    let leading =
+        Missile::guidance(pos, Vec2::zero(), target, closing + sideways, 300.0);
+
+    assert_eq!(straight, Vec2::new(1.0, 0.0));
+    assert!(leading.x > 0.0);
+    assert!(leading.y > 0.0);
+}
+
+/// Put the target's ship at an offset from the shooter's, with the given
+/// velocity relative to it
+fn place(
+    snapshot: &mut Snapshot,
+    shooter: PlayerId,
+    target: PlayerId,
+    offset: Vec2,
+    rel_vel: Vec2,
+) {
+    let components = &mut snapshot.components;
+
+    let body = |owner: PlayerId| {
+        let (_, craft) = components
+            .crafts
+            .iter()
+            .find(|(_, craft)| craft.owner == owner)
+            .unwrap();
+        let (_, body) = components
+            .bodies
+            .iter()
+            .find(|(handle, _)| *handle == craft.body.weak())
+            .unwrap();
+        body.clone()
+    };
+    let shooter_body = body(shooter);
+    let target_body = body(target);
+
+    let (_, pos) = components
+        .positions
+        .iter()
+        .find(|(handle, _)| *handle == shooter_body.pos.weak())
+        .unwrap();
+    let (_, vel) = components
+        .velocities
+        .iter()
+        .find(|(handle, _)| *handle == shooter_body.vel.weak())
+        .unwrap();
+    let (pos, vel) = (pos.0, vel.0);
+
+    for (handle, target_pos) in &mut components.positions {
+        if *handle == target_body.pos.weak() {
+            target_pos.0 = pos + offset;
+        }
+    }
+    for (handle, target_vel) in &mut components.velocities {
+        if *handle == target_body.vel.weak() {
+            target_vel.0 = vel + rel_vel;
+        }
+    }
+}
+
+/// The ship of the given player, and its craft
+///
+/// Missiles are crafts of the same owner, so looking at crafts alone isn't
+/// enough.
+fn ship_and_craft(tick: &Tick, owner: PlayerId) -> (Ship, Craft) {
+    tick.updates
+        .iter()
+        .filter_map(|component| match component {
+            Component::Ship(_, ship) => Some(ship),
+            _ => None,
+        })
+        .find_map(|ship| {
+            tick.updates.iter().find_map(|component| match component {
+                Component::Craft(handle, craft)
+                    if *handle == ship.craft && craft.owner == owner =>
+                {
+                    Some((ship.clone(), craft.clone()))
+                }
+                _ => None,
+            })
+        })
+        .unwrap()
+}
+
+fn ship(tick: &Tick, owner: PlayerId) -> Ship {
+    ship_and_craft(tick, owner).0
+}
+
+fn health(tick: &Tick, owner: PlayerId) -> Health {
+    let (_, craft) = ship_and_craft(tick, owner);
+    tick.updates
+        .iter()
+        .find_map(|component| match component {
+            Component::Health(handle, health) if *handle == craft.health => {
+                Some(health.clone())
+            }
+            _ => None,
+        })
+        .unwrap()
+}
@@ -1,13 +1,17 @@
 use vndf_server::net::{Error, Event, Network};
-use vndf_shared::net::{self, client::Conn, msg};
+use vndf_shared::{
+    data::client::Handle,
+    net::{self, client::Conn, msg, udp, Transport},
+    sim::Simulation,
+};
 
 #[test]
 fn network_should_emit_receive_events() -> net::Result {
-    let mut server = Network::start_local()?;
-    let mut conn = Conn::connect(server.addr())?;
+    let mut server = Network::start_local(Transport::Tcp)?;
+    let mut conn = Conn::connect(server.addr(), Transport::Tcp)?;
 
-    let sent = msg::FromClient::Ping;
-    conn.send(sent)?;
+    let sent = msg::FromClient::Ping { sent: 0.0 };
+    conn.send(sent.clone())?;
 
     let mut received = None;
 
@@ -26,8 +30,8 @@ fn network_should_emit_receive_events() -> net::Result {
 
 #[test]
 fn network_should_report_client_errors() -> net::Result {
-    let mut server = Network::start_local()?;
-    let client = Conn::connect(server.addr())?;
+    let mut server = Network::start_local(Transport::Tcp)?;
+    let client = Conn::connect(server.addr(), Transport::Tcp)?;
     let addr = client.local_addr;
 
     client.disconnect();
@@ -51,10 +55,10 @@ fn network_should_report_client_errors() -> net::Result {
 
 #[test]
 fn clients_should_emit_receive_events() -> Result<(), Error> {
-    let mut server = Network::start_local()?;
-    let mut client = Conn::connect(server.addr())?;
+    let mut server = Network::start_local(Transport::Tcp)?;
+    let mut client = Conn::connect(server.addr(), Transport::Tcp)?;
 
-    client.send(msg::FromClient::Ping)?;
+    client.send(msg::FromClient::Ping { sent: 0.0 })?;
 
     let mut client_connected = false;
     while !client_connected {
@@ -80,3 +84,219 @@ fn clients_should_emit_receive_events() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+fn udp_transport_should_deliver_messages_both_ways() -> Result<(), Error> {
+    let mut server = Network::start_local(Transport::Udp)?;
+    let mut client = Conn::connect(server.addr(), Transport::Udp)?;
+
+    let sent = msg::FromClient::Hello {
+        version: msg::PROTOCOL_VERSION,
+        capabilities: msg::CAPABILITIES,
+        color: [1.0, 1.0, 0.0],
+    };
+    client.send(sent.clone())?;
+
+    let mut received = None;
+    while received.is_none() {
+        for event in server.events() {
+            if let Event::Message(id, message) = event {
+                received = Some((id, message));
+            }
+        }
+    }
+    assert_eq!(received, Some((client.local_addr, sent)));
+
+    let messages = vec![
+        msg::FromServer::TimeWarp(2.0),
+        msg::FromServer::InputHandled { seq: 1 },
+        msg::FromServer::InputHandled { seq: 2 },
+    ];
+    for message in &messages {
+        server.send(client.local_addr, message.clone());
+    }
+
+    let mut received = Vec::new();
+    while received.len() < messages.len() {
+        for message in client.incoming() {
+            received.push(message?);
+        }
+    }
+    assert_eq!(received, messages);
+
+    Ok(())
+}
+
+#[test]
+fn udp_transport_should_report_disconnected_clients() -> net::Result {
+    let mut server = Network::start_local(Transport::Udp)?;
+    let mut client = Conn::connect(server.addr(), Transport::Udp)?;
+    let addr = client.local_addr;
+
+    // The server only learns about a client when it receives the handshake.
+    client.send(hello())?;
+    client.disconnect();
+
+    let mut disconnect_id = None;
+    while disconnect_id.is_none() {
+        for event in server.events() {
+            if let Event::Error(id, _error) = event {
+                disconnect_id = Some(id);
+            }
+        }
+    }
+
+    assert_eq!(Some(addr), disconnect_id);
+
+    Ok(())
+}
+
+#[test]
+fn network_should_reject_messages_larger_than_the_maximum() -> net::Result {
+    for &transport in &[Transport::Tcp, Transport::Udp] {
+        let mut server = Network::start_local(transport)?;
+        server.set_max_message_size(8);
+
+        let mut client = Conn::connect(server.addr(), transport)?;
+        client.send(msg::FromClient::Hello {
+            version: msg::PROTOCOL_VERSION,
+            capabilities: msg::CAPABILITIES,
+            color: [1.0, 1.0, 0.0],
+        })?;
+
+        let mut error = None;
+        while error.is_none() {
+            for event in server.events() {
+                match event {
+                    Event::Error(_, err) => error = Some(err),
+                    Event::Message(_, message) => {
+                        panic!("Unexpected message: {:?}", message)
+                    }
+                }
+            }
+        }
+
+        assert!(
+            matches!(
+                error,
+                Some(net::Error::Msg(msg::Error::TooLarge { max_size: 8, .. }))
+            ),
+            "Error: {:?}",
+            error
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn network_should_disconnect_clients_that_dont_keep_up() -> net::Result {
+    for &transport in &[Transport::Tcp, Transport::Udp] {
+        let mut server = Network::start_local(transport)?;
+        let mut client = Conn::connect(server.addr(), transport)?;
+
+        client.send(hello())?;
+
+        let mut connected = false;
+        while !connected {
+            for event in server.events() {
+                if let Event::Message(_, _) = event {
+                    connected = true;
+                }
+            }
+        }
+
+        // The client never reads anything, so whatever is sent to it piles
+        // up.
+        let mut error = None;
+        let mut seq = 0;
+        while error.is_none() {
+            server
+                .send(client.local_addr, msg::FromServer::InputHandled { seq });
+            seq += 1;
+
+            for event in server.events() {
+                if let Event::Error(_, err) = event {
+                    error = Some(err);
+                }
+            }
+        }
+
+        assert_eq!(error, Some(net::Error::Congested));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn udp_transport_should_only_accept_peers_that_send_a_handshake() -> net::Result
+{
+    let mut server = Network::start_local(Transport::Udp)?;
+
+    let mut stranger = Conn::connect(server.addr(), Transport::Udp)?;
+    stranger.send(msg::FromClient::Ping { sent: 0.0 })?;
+
+    let mut client = Conn::connect(server.addr(), Transport::Udp)?;
+    client.send(hello())?;
+
+    let mut senders = Vec::new();
+    while senders.is_empty() {
+        for event in server.events() {
+            if let Event::Message(addr, _) = event {
+                senders.push(addr);
+            }
+        }
+    }
+
+    assert_eq!(senders, vec![client.local_addr]);
+
+    Ok(())
+}
+
+#[test]
+fn udp_transport_should_deliver_messages_larger_than_a_packet() -> net::Result {
+    let mut server = Network::start_local(Transport::Udp)?;
+    let mut client = Conn::connect(server.addr(), Transport::Udp)?;
+
+    client.send(hello())?;
+
+    // Every handle takes up at least a few bytes.
+    let handles = Simulation::new(0)
+        .step()
+        .updates
+        .iter()
+        .map(Handle::from_component)
+        .cycle()
+        .take(udp::MAX_PACKET)
+        .collect();
+    let sent = msg::FromClient::Resync(handles);
+    client.send(sent.clone())?;
+
+    let mut received = None;
+    while received.is_none() {
+        // Lets the client resend what got lost.
+        for message in client.incoming() {
+            message?;
+        }
+
+        for event in server.events() {
+            if let Event::Message(_, message @ msg::FromClient::Resync(_)) =
+                event
+            {
+                received = Some(message);
+            }
+        }
+    }
+
+    assert_eq!(received, Some(sent));
+
+    Ok(())
+}
+
+fn hello() -> msg::FromClient {
+    msg::FromClient::Hello {
+        version: msg::PROTOCOL_VERSION,
+        capabilities: msg::CAPABILITIES,
+        color: [1.0, 1.0, 0.0],
+    }
+}
@@ -0,0 +1,88 @@
+use vndf_shared::{
+    data::client::{Component, Components},
+    sim::Script,
+    world::{
+        features::{
+            orbits::{Kind, Orbit, Orbiter},
+            planets::{Planet, Planets, G},
+        },
+        Length, Vec2,
+    },
+};
+
+#[test]
+fn orbit_above_escape_velocity_should_be_hyperbolic() {
+    let components = system();
+    let saturn = primary(&components);
+
+    let distance = saturn.radius.0 * 2.0;
+    let escape_velocity = (2.0 * G * saturn.mass / distance).sqrt();
+
+    let orbiter = Orbiter {
+        pos: saturn.pos + Vec2::new(distance, 0.0),
+        vel: Vec2::new(0.0, escape_velocity * 1.5),
+    };
+    let orbit = Orbit::new(orbiter, &Planets(&components.planets)).unwrap();
+
+    let excess_velocity = match orbit.kind {
+        Kind::Hyperbola {
+            excess_velocity, ..
+        } => excess_velocity,
+        _ => panic!("Expected hyperbolic orbit"),
+    };
+    let expected =
+        ((escape_velocity * 1.5).powi(2) - escape_velocity.powi(2)).sqrt();
+
+    assert!((excess_velocity - expected).abs() < 1.0);
+    assert!((orbit.periapsis.distance.0 - distance).abs() < 1.0);
+
+    let max_distance = distance * 10.0;
+    for point in orbit.points(Length::new(max_distance), 64) {
+        let distance = (point - saturn.pos).length();
+        assert!(distance <= max_distance * 1.001);
+    }
+}
+
+#[test]
+fn orbit_at_escape_velocity_should_be_parabolic() {
+    let components = system();
+    let saturn = primary(&components);
+
+    let distance = saturn.radius.0 * 2.0;
+    let escape_velocity = (2.0 * G * saturn.mass / distance).sqrt();
+
+    let orbiter = Orbiter {
+        pos: saturn.pos + Vec2::new(distance, 0.0),
+        vel: Vec2::new(0.0, escape_velocity),
+    };
+    let orbit = Orbit::new(orbiter, &Planets(&components.planets)).unwrap();
+
+    assert!(matches!(orbit.kind, Kind::Parabola));
+    assert!(orbit.is_open());
+}
+
+fn system() -> Components {
+    let trace = Script {
+        ticks: 1,
+        seed: 0,
+        events: Vec::new(),
+    }
+    .run();
+
+    let mut components = Components::new();
+    for component in &trace.last().unwrap().updates {
+        if let Component::Planet(_, _) = component {
+            component.clone().update(&mut components);
+        }
+    }
+
+    components
+}
+
+fn primary(components: &Components) -> &Planet {
+    components
+        .planets
+        .values()
+        .find(|planet| planet.rails.is_none())
+        .unwrap()
+}
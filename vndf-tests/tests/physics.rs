@@ -0,0 +1,141 @@
+use vndf_shared::{
+    data::server::Components,
+    world::{
+        features::{
+            physics::{Body, Position, Velocity},
+            planets::{update_planets, Planet, Planets, Rails, G},
+        },
+        Angle, Length, Method, Pnt2, Vec2, FRAME_TIME,
+    },
+};
+
+const RADIUS: f64 = 1_000_000.0;
+const MASS: f64 = 1e22;
+
+// The factor that an FTL jump applies to a ship's time
+const TIME_FACTOR: f64 = 10_000.0;
+
+#[test]
+fn accelerated_body_should_stay_in_orbit() {
+    let distance = RADIUS * 1.1;
+    let speed = (G * MASS / distance).sqrt();
+
+    let mut data = components();
+    let mut body =
+        body(&mut data, Pnt2::new(distance, 0.0), Vec2::new(0.0, speed));
+
+    for _ in 0..60 {
+        update(&mut body, &mut data);
+    }
+
+    let (pos, _) = state(&body, &data);
+    let error = (pos.to_vector().length() - distance) / distance;
+
+    assert!(error.abs() < 1e-4, "Relative error: {}", error);
+}
+
+#[test]
+fn accelerated_body_should_stop_at_planet() {
+    // Without sub-stepping, the body would pass through the planet within a
+    // single frame.
+    let speed = RADIUS * 4.0 / (FRAME_TIME * TIME_FACTOR);
+
+    let mut data = components();
+    let mut body = body(
+        &mut data,
+        Pnt2::new(-RADIUS * 2.0, 0.0),
+        Vec2::new(speed, 0.0),
+    );
+
+    update(&mut body, &mut data);
+
+    let (pos, _) = state(&body, &data);
+    assert!(pos.to_vector().length() <= RADIUS);
+}
+
+#[test]
+fn sub_steps_should_follow_moving_planets() {
+    // The moon moves fast compared to the size of the body's orbit, but the
+    // tidal forces from its parent are weak.
+    let parent_mass = 1.5e24;
+    let moon_distance = 1e8;
+
+    let mut data = Components::new();
+    let parent = Planet::new(Pnt2::origin(), Length::new(RADIUS), parent_mass);
+    let mut moon = Planet::new(Pnt2::origin(), Length::new(RADIUS), MASS);
+    moon.rails = Some(Rails::new(
+        data.planets.insert(parent.clone()),
+        &parent,
+        MASS,
+        Length::new(moon_distance),
+        Angle::zero(),
+    ));
+    let moon = data.planets.insert(moon);
+    update_planets(0.0, &mut data.planets);
+
+    let distance = RADIUS * 1.1;
+    let speed = (G * MASS / distance).sqrt();
+
+    let (pos, vel) = {
+        let moon = data.planets.get(&moon).unwrap();
+        (
+            moon.pos + Vec2::new(distance, 0.0),
+            moon.vel + Vec2::new(0.0, speed),
+        )
+    };
+    let mut body = body(&mut data, pos, vel);
+    body.time_factor = 1.0;
+
+    // Large frames, as under time warp, so the body takes many sub-steps.
+    let dt = 600.0;
+    for _ in 0..5 {
+        update_planets(dt, &mut data.planets);
+        body.update(
+            dt,
+            &Planets(&data.planets),
+            Method::default(),
+            &mut data.positions,
+            &mut data.velocities,
+        );
+    }
+
+    let (pos, _) = state(&body, &data);
+    let moon = data.planets.get(&moon).unwrap();
+    let error = ((pos - moon.pos).length() - distance) / distance;
+
+    assert!(error.abs() < 1e-2, "Relative error: {}", error);
+}
+
+fn components() -> Components {
+    let mut data = Components::new();
+    data.planets
+        .insert(Planet::new(Pnt2::origin(), Length::new(RADIUS), MASS));
+    data
+}
+
+fn body(data: &mut Components, pos: Pnt2, vel: Vec2) -> Body {
+    let pos = data.positions.insert(Position(pos));
+    let vel = data.velocities.insert(Velocity(vel));
+
+    let mut body = Body::new(pos, vel);
+    body.time_factor = TIME_FACTOR;
+
+    body
+}
+
+fn update(body: &mut Body, data: &mut Components) {
+    body.update(
+        FRAME_TIME,
+        &Planets(&data.planets),
+        Method::default(),
+        &mut data.positions,
+        &mut data.velocities,
+    );
+}
+
+fn state(body: &Body, data: &Components) -> (Pnt2, Vec2) {
+    let pos = data.positions.get(&body.pos).unwrap();
+    let vel = data.velocities.get(&body.vel).unwrap();
+
+    (pos.0, vel.0)
+}
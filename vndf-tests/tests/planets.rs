@@ -0,0 +1,111 @@
+use vndf_shared::{
+    data::client::{Component, Components},
+    sim::{trace::Tick, Script},
+    world::{
+        features::planets::{system::Error, Planet, Planets, System},
+        Vec2,
+    },
+};
+
+#[test]
+fn moons_should_follow_their_orbits() {
+    let trace = script(60).run();
+
+    let before = planets(&trace.ticks[0]);
+    let after = planets(trace.last().unwrap());
+
+    let saturn = primary(&after);
+
+    for (handle, moon) in after.planets.iter() {
+        let rails = match &moon.rails {
+            Some(rails) => rails,
+            None => continue,
+        };
+
+        let distance = (moon.pos - saturn.pos).length();
+        assert!((distance - rails.distance.0).abs() < 1.0);
+
+        let moon_before = before.planets.get(handle).unwrap();
+        assert_ne!(moon.pos, moon_before.pos);
+    }
+}
+
+#[test]
+fn dominant_planet_should_be_chosen_by_sphere_of_influence() {
+    let trace = script(1).run();
+
+    let components = planets(trace.last().unwrap());
+    let planets = Planets(&components.planets);
+
+    let saturn = primary(&components);
+    let titan = components
+        .planets
+        .values()
+        .filter(|planet| planet.rails.is_some())
+        .max_by(|a, b| a.mass.partial_cmp(&b.mass).unwrap())
+        .unwrap();
+
+    let near_titan = titan.pos + Vec2::new(titan.radius.0 * 2.0, 0.0);
+    let between = saturn.pos + (titan.pos - saturn.pos) / 2.0;
+
+    assert_eq!(planets.dominant_at(near_titan), Some(titan));
+    assert_eq!(planets.dominant_at(between), Some(saturn));
+}
+
+#[test]
+fn system_without_planets_should_be_rejected() {
+    let result = System::from_toml("planets = []");
+    assert!(matches!(result, Err(Error::Empty)));
+}
+
+#[test]
+fn system_with_duplicate_names_should_be_rejected() {
+    let result = System::from_toml(
+        r#"
+            [[planets]]
+            name   = "Saturn"
+            mass   = 5.0e26
+            radius = 6.0e7
+
+            [[planets]]
+            name   = "Saturn"
+            mass   = 1.0e23
+            radius = 2.5e6
+
+            [planets.orbit]
+            parent   = "Saturn"
+            distance = 1.2e9
+        "#,
+    );
+    assert!(
+        matches!(result, Err(Error::DuplicateName(name)) if name == "Saturn")
+    );
+}
+
+fn script(ticks: u64) -> Script {
+    Script {
+        ticks,
+        seed: 0,
+        events: Vec::new(),
+    }
+}
+
+fn planets(tick: &Tick) -> Components {
+    let mut components = Components::new();
+
+    for component in &tick.updates {
+        if let Component::Planet(_, _) = component {
+            component.clone().update(&mut components);
+        }
+    }
+
+    components
+}
+
+fn primary(components: &Components) -> &Planet {
+    components
+        .planets
+        .values()
+        .find(|planet| planet.rails.is_none())
+        .unwrap()
+}
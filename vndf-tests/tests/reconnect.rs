@@ -0,0 +1,230 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use vndf_server::server::Server;
+use vndf_shared::{
+    action,
+    data::client::{Component, Handle},
+    net::{client::Conn, msg, Transport},
+    sim::{trace::Tick, Simulation},
+    world::{
+        self,
+        features::players::{IdentityToken, Player, PlayerId},
+        FRAME_TIME,
+    },
+};
+
+#[test]
+fn reconnected_player_should_get_their_ship_back() {
+    let mut sim = Simulation::new(0);
+
+    sim.connect(1, [1.0, 1.0, 0.0]);
+    let id = sim.step().players_created[0].1;
+
+    sim.disconnect(1);
+    sim.step();
+
+    sim.reconnect(1, [1.0, 1.0, 0.0]);
+    let tick = sim.step();
+    assert_eq!(tick.players_reconnected, vec![(1, id)]);
+    assert!(tick.players_created.is_empty());
+    assert_eq!(owners(&tick), vec![id]);
+
+    sim.input(1, action::Kind::Thrust(true));
+    let tick = sim.step();
+    assert_eq!(tick.inputs_handled.len(), 1);
+}
+
+#[test]
+fn player_should_not_be_reconnected_after_the_grace_period() {
+    let mut sim = Simulation::new(0);
+
+    sim.connect(1, [1.0, 1.0, 0.0]);
+    let id = sim.step().players_created[0].1;
+
+    sim.disconnect(1);
+    let ticks = (Player::DEFAULT_DISCONNECT_TIMEOUT / FRAME_TIME) as u64 + 1;
+    for _ in 0..ticks {
+        sim.step();
+    }
+
+    sim.reconnect(1, [1.0, 1.0, 0.0]);
+    let tick = sim.step();
+    assert!(tick.players_reconnected.is_empty());
+    assert_eq!(tick.players_created.len(), 1);
+    assert_ne!(tick.players_created[0].1, id);
+}
+
+#[test]
+fn ship_of_disconnected_player_should_be_despawned_after_the_timeout() {
+    let mut sim = Simulation::new(0);
+    sim.state().set_disconnect_timeout(1.0);
+
+    sim.connect(1, [1.0, 1.0, 0.0]);
+    sim.connect(2, [0.0, 1.0, 1.0]);
+    let tick = sim.step();
+    let ids: Vec<_> = tick.players_created.iter().map(|&(_, id)| id).collect();
+
+    sim.disconnect(1);
+    let mut removals = Vec::new();
+    for _ in 0..(1.0 / FRAME_TIME) as u64 - 1 {
+        removals.extend(sim.step().removals);
+    }
+    assert!(removals.is_empty());
+
+    // Give the removals a few ticks to propagate from the ship to its
+    // components.
+    for _ in 0..3 {
+        removals.extend(sim.step().removals);
+    }
+    let tick = sim.step();
+    assert_eq!(owners(&tick), vec![ids[1]]);
+    assert!(removals
+        .iter()
+        .any(|handle| matches!(handle, Handle::Ship(_))));
+    assert!(removals
+        .iter()
+        .any(|handle| matches!(handle, Handle::Craft(_))));
+}
+
+#[test]
+fn restored_ship_should_be_kept_once_its_owner_reconnects() {
+    let mut sim = Simulation::new(0);
+    sim.state().set_disconnect_timeout(1.0);
+
+    sim.connect(1, [1.0, 1.0, 0.0]);
+    let id = sim.step().players_created[0].1;
+
+    let snapshot = sim.state().snapshot();
+    sim.restart(world::State::restore(snapshot, 0).unwrap());
+    sim.state().set_disconnect_timeout(1.0);
+
+    sim.reconnect(1, [1.0, 1.0, 0.0]);
+    sim.step();
+
+    let mut removals = Vec::new();
+    for _ in 0..(2.0 / FRAME_TIME) as u64 {
+        removals.extend(sim.step().removals);
+    }
+    let tick = sim.step();
+    assert_eq!(owners(&tick), vec![id]);
+    assert!(!removals
+        .iter()
+        .any(|handle| matches!(handle, Handle::Ship(_))));
+}
+
+#[test]
+fn server_should_welcome_reconnected_clients_as_the_same_player() {
+    let mut server = Server::start_local(0, Transport::Tcp).unwrap();
+
+    let mut conn = Conn::connect(server.addr(), Transport::Tcp).unwrap();
+    conn.send(msg::FromClient::Hello {
+        version: msg::PROTOCOL_VERSION,
+        capabilities: msg::CAPABILITIES,
+        color: [1.0, 1.0, 0.0],
+    })
+    .unwrap();
+    let (id, token) = welcome(&mut server, &mut conn);
+    conn.disconnect();
+
+    let mut conn = Conn::connect(server.addr(), Transport::Tcp).unwrap();
+    conn.send(msg::FromClient::Reconnect {
+        version: msg::PROTOCOL_VERSION,
+        capabilities: msg::CAPABILITIES,
+        color: [1.0, 1.0, 0.0],
+        token,
+    })
+    .unwrap();
+    assert_eq!(welcome(&mut server, &mut conn), (id, token));
+}
+
+#[test]
+fn server_should_close_the_connection_that_was_taken_over() {
+    let mut server = Server::start_local(0, Transport::Tcp).unwrap();
+
+    let mut old = Conn::connect(server.addr(), Transport::Tcp).unwrap();
+    old.send(msg::FromClient::Hello {
+        version: msg::PROTOCOL_VERSION,
+        capabilities: msg::CAPABILITIES,
+        color: [1.0, 1.0, 0.0],
+    })
+    .unwrap();
+    let (id, token) = welcome(&mut server, &mut old);
+
+    let mut new = Conn::connect(server.addr(), Transport::Tcp).unwrap();
+    new.send(msg::FromClient::Reconnect {
+        version: msg::PROTOCOL_VERSION,
+        capabilities: msg::CAPABILITIES,
+        color: [1.0, 1.0, 0.0],
+        token,
+    })
+    .unwrap();
+    assert_eq!(welcome(&mut server, &mut new), (id, token));
+
+    let mut rejection = None;
+    let mut closed = false;
+    let start = Instant::now();
+    while !closed && start.elapsed() < Duration::from_secs(1) {
+        server.update();
+        for message in old.incoming() {
+            match message {
+                Ok(msg::FromServer::Rejected(reason)) => {
+                    rejection = Some(reason)
+                }
+                Ok(_) => {}
+                Err(_) => closed = true,
+            }
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    assert_eq!(rejection, Some(msg::Rejection::TakenOver));
+    assert!(closed);
+}
+
+#[test]
+fn server_should_not_issue_tokens_that_follow_from_the_seed() {
+    let token = || {
+        let mut server = Server::start_local(0, Transport::Tcp).unwrap();
+
+        let mut conn = Conn::connect(server.addr(), Transport::Tcp).unwrap();
+        conn.send(msg::FromClient::Hello {
+            version: msg::PROTOCOL_VERSION,
+            capabilities: msg::CAPABILITIES,
+            color: [1.0, 1.0, 0.0],
+        })
+        .unwrap();
+
+        let (_, token) = welcome(&mut server, &mut conn);
+        token
+    };
+
+    assert_ne!(token(), token());
+}
+
+fn welcome(server: &mut Server, conn: &mut Conn) -> (PlayerId, IdentityToken) {
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(1) {
+        server.update();
+        for message in conn.incoming() {
+            if let msg::FromServer::Welcome { id, token } = message.unwrap() {
+                return (id, token);
+            }
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    panic!("No welcome received");
+}
+
+fn owners(tick: &Tick) -> Vec<PlayerId> {
+    tick.updates
+        .iter()
+        .filter_map(|component| match component {
+            Component::Craft(_, craft) => Some(craft.owner),
+            _ => None,
+        })
+        .collect()
+}
@@ -0,0 +1,145 @@
+use std::{
+    env, thread,
+    time::{Duration, Instant},
+};
+
+use vndf_server::{
+    recording::{self, Header, Recorder, Recording},
+    server::Server,
+};
+use vndf_shared::{
+    action::{self, Action},
+    data::client::Component,
+    net::{client::Conn, msg, Transport},
+    sim::Simulation,
+    world::{
+        features::{planets::System, players::Player},
+        Method,
+    },
+};
+
+#[test]
+fn recorded_session_should_replay_without_network() {
+    let path = env::temp_dir()
+        .join(format!("vndf-replay-test-{}.rec", std::process::id()));
+
+    let mut server = Server::start_local(7, Transport::Tcp).unwrap();
+    server.record(&path).unwrap();
+
+    let mut conn = Conn::connect(server.addr(), Transport::Tcp).unwrap();
+    conn.send(msg::FromClient::Hello {
+        version: msg::PROTOCOL_VERSION,
+        capabilities: msg::CAPABILITIES,
+        color: [1.0, 1.0, 0.0],
+    })
+    .unwrap();
+    conn.send(msg::FromClient::Action(Action {
+        seq: 0,
+        kind: action::Kind::Thrust(true),
+    }))
+    .unwrap();
+
+    let mut token = None;
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_millis(200) {
+        server.update();
+        for message in conn.incoming() {
+            if let msg::FromServer::Welcome { token: t, .. } = message.unwrap()
+            {
+                token = Some(t);
+            }
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    let recording = Recording::load(&path).unwrap();
+    let trace = recording.replay(1).unwrap();
+
+    let player = conn.local_addr.port();
+    let handled = trace
+        .ticks
+        .iter()
+        .flat_map(|tick| &tick.inputs_handled)
+        .any(|&input| input == (player, 0));
+
+    assert_eq!(recording.header.seed, 7);
+    assert!(trace.player_id(player).is_some());
+    assert!(token.is_some());
+    assert_eq!(trace.token(player), token);
+    assert!(handled);
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn session_recorded_after_a_restore_should_replay_from_the_snapshot() {
+    let id = std::process::id();
+    let snapshot = env::temp_dir().join(format!("vndf-replay-{}.snapshot", id));
+    let path = env::temp_dir().join(format!("vndf-restored-{}.rec", id));
+
+    let mut original = Simulation::new(7);
+    original.connect(1, [1.0, 1.0, 0.0]);
+    for _ in 0..10 {
+        original.step();
+    }
+    original.state().snapshot().save(&snapshot).unwrap();
+
+    let mut server = Server::start_local(7, Transport::Tcp).unwrap();
+    server.restore(&snapshot).unwrap();
+    server.record(&path).unwrap();
+    server.update();
+
+    let recording = Recording::load(&path).unwrap();
+    let trace = recording.replay(1).unwrap();
+
+    // The restored player's ship is still there, though nobody connected.
+    let ships = trace
+        .last()
+        .unwrap()
+        .updates
+        .iter()
+        .filter(|component| matches!(component, Component::Ship(_, _)))
+        .count();
+
+    assert_eq!(trace.ticks[0].tick, original.state().tick());
+    assert_eq!(ships, 1);
+
+    std::fs::remove_file(snapshot).unwrap();
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn recording_with_invalid_system_should_be_rejected() {
+    let path = env::temp_dir()
+        .join(format!("vndf-invalid-test-{}.rec", std::process::id()));
+
+    let header = Header {
+        seed: 0,
+        system: System::default(),
+        integrator: Method::default(),
+        disconnect_timeout: Player::DEFAULT_DISCONNECT_TIMEOUT,
+        snapshot: None,
+    };
+    let mut recorder = Recorder::create(&path, header).unwrap();
+    recorder.flush().unwrap();
+    drop(recorder);
+
+    // Edit the recording, so the moons orbit a planet that doesn't exist.
+    let mut bytes = std::fs::read(&path).unwrap();
+    let name = b"Saturn";
+    let mut found = 0;
+    for i in 0..bytes.len() - name.len() {
+        if &bytes[i..][..name.len()] == name {
+            if found > 0 {
+                bytes[i] = b'X';
+            }
+            found += 1;
+        }
+    }
+    std::fs::write(&path, bytes).unwrap();
+
+    let result = Recording::load(&path);
+    assert!(matches!(result, Err(recording::Error::System(_))));
+
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,113 @@
+use vndf_shared::{
+    action,
+    data::client::Component,
+    sim::{trace::Tick, Simulation},
+    world::{
+        features::{planets::System, players::Player},
+        State, FRAME_TIME,
+    },
+};
+
+#[test]
+fn player_should_be_told_when_their_ship_is_destroyed() {
+    let mut sim = crashing_sim();
+
+    sim.connect(1, [1.0, 1.0, 0.0]);
+    let id = sim.step().players_created[0].1;
+
+    let destroyed =
+        step_until(&mut sim, 10, |tick| !tick.ships_destroyed.is_empty());
+    assert_eq!(destroyed.ships_destroyed, vec![(1, id)]);
+    assert_eq!(crafts(&sim.step()), 0);
+}
+
+#[test]
+fn player_should_get_a_new_ship_after_the_respawn_delay() {
+    let mut sim = crashing_sim();
+
+    sim.connect(1, [1.0, 1.0, 0.0]);
+    sim.step();
+    step_until(&mut sim, 10, |tick| !tick.ships_destroyed.is_empty());
+
+    // Too early. The request is handled, but ignored.
+    sim.input(1, action::Kind::Respawn);
+    let tick = sim.step();
+    assert_eq!(tick.inputs_handled.len(), 1);
+    assert_eq!(crafts(&tick), 0);
+
+    for _ in 0..(Player::RESPAWN_DELAY / FRAME_TIME) as u64 {
+        sim.step();
+    }
+
+    sim.input(1, action::Kind::Respawn);
+    assert_eq!(crafts(&sim.step()), 1);
+
+    // The new ship crashes just like the old one.
+    step_until(&mut sim, 10, |tick| !tick.ships_destroyed.is_empty());
+}
+
+#[test]
+fn reconnected_player_should_be_told_their_ship_is_destroyed() {
+    let mut sim = crashing_sim();
+
+    sim.connect(1, [1.0, 1.0, 0.0]);
+    let id = sim.step().players_created[0].1;
+    step_until(&mut sim, 10, |tick| !tick.ships_destroyed.is_empty());
+
+    sim.disconnect(1);
+    sim.step();
+
+    sim.reconnect(1, [1.0, 1.0, 0.0]);
+    let tick = sim.step();
+    assert_eq!(tick.players_reconnected, vec![(1, id)]);
+    assert_eq!(tick.ships_destroyed, vec![(1, id)]);
+}
+
+/// A simulation in which every ship crashes right after it was created
+///
+/// Ships are created in orbit around the primary, which is engulfed by the
+/// second planet.
+fn crashing_sim() -> Simulation {
+    let system = System::from_toml(
+        r#"
+            [[planets]]
+            name   = "Primary"
+            mass   = 5.0e26
+            radius = 6.0e7
+
+            [[planets]]
+            name   = "Engulfing"
+            mass   = 1.0e20
+            radius = 2.0e8
+
+            [planets.orbit]
+            parent   = "Primary"
+            distance = 1.0
+        "#,
+    )
+    .unwrap();
+
+    Simulation::from_state(State::from_system(&system, 0))
+}
+
+fn step_until(
+    sim: &mut Simulation,
+    max_ticks: u64,
+    condition: impl Fn(&Tick) -> bool,
+) -> Tick {
+    for _ in 0..max_ticks {
+        let tick = sim.step();
+        if condition(&tick) {
+            return tick;
+        }
+    }
+
+    panic!("Condition not met within {} ticks", max_ticks);
+}
+
+fn crafts(tick: &Tick) -> usize {
+    tick.updates
+        .iter()
+        .filter(|component| matches!(component, Component::Craft(..)))
+        .count()
+}
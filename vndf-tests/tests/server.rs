@@ -0,0 +1,157 @@
+use std::{
+    collections::HashMap,
+    thread,
+    time::{Duration, Instant},
+};
+
+use vndf_server::server::Server;
+use vndf_shared::{
+    data::client::Component,
+    net::{
+        client::Conn,
+        delta::{Decoder, Update},
+        msg, Transport,
+    },
+};
+
+#[test]
+fn positions_should_be_sent_often_and_decode_against_baselines() {
+    receive_positions(Transport::Tcp);
+}
+
+#[test]
+fn positions_should_be_received_over_udp() {
+    receive_positions(Transport::Udp);
+}
+
+fn receive_positions(transport: Transport) {
+    let mut server = Server::start_local(0, transport).unwrap();
+
+    let mut conn = Conn::connect(server.addr(), transport).unwrap();
+    conn.send(msg::FromClient::Hello {
+        version: msg::PROTOCOL_VERSION,
+        capabilities: msg::CAPABILITIES,
+        color: [1.0, 1.0, 0.0],
+    })
+    .unwrap();
+
+    let mut decoder = Decoder::new();
+    let mut ticks = HashMap::new();
+    let mut acks = Vec::new();
+    let mut deltas = 0;
+
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_millis(500) {
+        server.update();
+
+        for message in conn.incoming() {
+            if let msg::FromServer::Updates { tick, updates } = message.unwrap()
+            {
+                for update in updates {
+                    if let Update::Delta { .. } = update {
+                        deltas += 1;
+                    }
+                    let component = decoder.decode(tick, update).unwrap();
+                    if let Component::Position(handle, _) = component {
+                        ticks.entry(handle).or_insert_with(Vec::new).push(tick);
+                    }
+                }
+                acks.push(tick);
+            }
+        }
+        for tick in acks.drain(..) {
+            conn.send(msg::FromClient::Ack { tick }).unwrap();
+        }
+
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    assert!(deltas > 0);
+    assert!(!ticks.is_empty());
+    for ticks in ticks.values() {
+        // Enough snapshots to interpolate between
+        assert!(ticks.len() >= 3, "Ticks: {:?}", ticks);
+        assert!(ticks.windows(2).all(|w| w[0] < w[1]), "Ticks: {:?}", ticks);
+    }
+}
+
+#[test]
+fn pings_should_be_answered_with_the_current_tick() {
+    let mut server = Server::start_local(0, Transport::Tcp).unwrap();
+    let mut conn = Conn::connect(server.addr(), Transport::Tcp).unwrap();
+
+    // Let the world run for a bit, so the tick isn't zero.
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_millis(100) {
+        server.update();
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    conn.send(msg::FromClient::Ping { sent: 1.5 }).unwrap();
+
+    let mut pong = None;
+    while pong.is_none() {
+        server.update();
+
+        for message in conn.incoming() {
+            if let msg::FromServer::Pong { sent, tick } = message.unwrap() {
+                pong = Some((sent, tick));
+            }
+        }
+    }
+
+    let (sent, tick) = pong.unwrap();
+    assert_eq!(sent, 1.5);
+    assert!(tick > 0);
+}
+
+#[test]
+fn components_should_be_sent_in_full_after_a_resync() {
+    let mut server = Server::start_local(0, Transport::Tcp).unwrap();
+
+    let mut conn = Conn::connect(server.addr(), Transport::Tcp).unwrap();
+    conn.send(msg::FromClient::Hello {
+        version: msg::PROTOCOL_VERSION,
+        capabilities: msg::CAPABILITIES,
+        color: [1.0, 1.0, 0.0],
+    })
+    .unwrap();
+
+    let mut resynced = None;
+    let mut full_after_resync = false;
+
+    let start = Instant::now();
+    while !full_after_resync && start.elapsed() < Duration::from_secs(2) {
+        server.update();
+
+        let messages: Vec<_> = conn.incoming().collect();
+        for message in messages {
+            if let msg::FromServer::Updates { tick, updates } = message.unwrap()
+            {
+                for update in updates {
+                    match (&resynced, &update) {
+                        (None, Update::Delta { .. }) => {
+                            let handle = update.handle();
+                            conn.send(msg::FromClient::Resync(vec![
+                                handle.clone()
+                            ]))
+                            .unwrap();
+                            resynced = Some(handle);
+                        }
+                        (Some(handle), Update::Full(_))
+                            if update.handle() == *handle =>
+                        {
+                            full_after_resync = true;
+                        }
+                        _ => {}
+                    }
+                }
+                conn.send(msg::FromClient::Ack { tick }).unwrap();
+            }
+        }
+
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    assert!(full_after_resync);
+}
@@ -0,0 +1,241 @@
+use vndf_shared::{
+    action,
+    data::client::Component,
+    sim::{script::Error, trace::Tick, Script, Simulation},
+    world::{
+        features::crafts::{Craft, Maneuver},
+        Vec2,
+    },
+};
+
+#[test]
+fn connected_player_should_get_a_ship() -> Result<(), Error> {
+    let script = Script::from_toml(
+        r#"
+        ticks = 2
+
+        [[events]]
+        tick   = 0
+        player = 1
+        kind   = { Connect = { color = [1.0, 1.0, 0.0] } }
+        "#,
+    )?;
+
+    let trace = script.run();
+
+    let id = trace.player_id(1).expect("Player was not created");
+
+    let tick = trace.last().unwrap();
+    let owners: Vec<_> = tick
+        .updates
+        .iter()
+        .filter_map(|component| match component {
+            Component::Craft(_, craft) => Some(craft.owner),
+            _ => None,
+        })
+        .collect();
+    let ships = tick
+        .updates
+        .iter()
+        .filter(|component| matches!(component, Component::Ship(_, _)))
+        .count();
+
+    assert_eq!(owners, vec![id]);
+    assert_eq!(ships, 1);
+
+    Ok(())
+}
+
+#[test]
+fn thrust_should_consume_fuel() -> Result<(), Error> {
+    let script = Script::from_toml(
+        r#"
+        ticks = 60
+
+        [[events]]
+        tick   = 0
+        player = 1
+        kind   = { Connect = { color = [1.0, 1.0, 0.0] } }
+
+        [[events]]
+        tick   = 1
+        player = 1
+        kind   = { Input = { Thrust = true } }
+        "#,
+    )?;
+
+    let trace = script.run();
+
+    let fuel_before = fuel(&trace.ticks[0]).unwrap();
+    let fuel_after = fuel(trace.last().unwrap()).unwrap();
+
+    assert_eq!(trace.ticks[1].inputs_handled, vec![(1, 0)]);
+    assert!(fuel_after < fuel_before);
+
+    Ok(())
+}
+
+#[test]
+fn maneuver_should_be_executed_at_the_planned_time() -> Result<(), Error> {
+    let script = Script::from_toml(
+        r#"
+        ticks = 60
+
+        [[events]]
+        tick   = 0
+        player = 1
+        kind   = { Connect = { color = [1.0, 1.0, 0.0] } }
+
+        [[events]]
+        tick   = 1
+        player = 1
+        kind   = { Input = { Maneuver = { delay = 0.5, delta_v = [10.0, 0.0] } } }
+        "#,
+    )?;
+
+    let trace = script.run();
+
+    let fuel_before = fuel(&trace.ticks[0]).unwrap();
+    let fuel_waiting = fuel(&trace.ticks[20]).unwrap();
+    let fuel_after = fuel(trace.last().unwrap()).unwrap();
+
+    assert_eq!(fuel_waiting, fuel_before);
+
+    // The ship has a mass of 1, so fuel used equals delta-v.
+    assert!((fuel_before - fuel_after - 10.0).abs() < 1e-6);
+
+    Ok(())
+}
+
+#[test]
+fn maneuver_should_follow_the_ship_time_during_ftl_jump() {
+    let mut sim = Simulation::new(0);
+
+    sim.connect(1, [1.0, 1.0, 0.0]);
+    let fuel_before = fuel(&sim.step()).unwrap();
+
+    sim.input(1, action::Kind::FtlJump(3600.0));
+    sim.input(
+        1,
+        action::Kind::Maneuver(Maneuver {
+            delay: 600.0,
+            delta_v: Vec2::new(10.0, 0.0),
+        }),
+    );
+
+    // The jump speeds up the ship's time by a factor of 10000, so the
+    // maneuver is due after a few ticks.
+    let mut tick = sim.step();
+    for _ in 0..10 {
+        tick = sim.step();
+    }
+    let fuel_after = fuel(&tick).unwrap();
+
+    // The ship has a mass of 1, so fuel used equals delta-v.
+    assert!((fuel_before - fuel_after - 10.0).abs() < 1e-6);
+}
+
+#[test]
+fn maneuvers_should_be_limited() {
+    let mut sim = Simulation::new(0);
+
+    sim.connect(1, [1.0, 1.0, 0.0]);
+    sim.step();
+
+    sim.input(
+        1,
+        action::Kind::Maneuver(Maneuver {
+            delay: Maneuver::MAX_DELAY * 2.0,
+            delta_v: Vec2::new(10.0, 0.0),
+        }),
+    );
+    for i in 0..Craft::MAX_MANEUVERS * 2 {
+        sim.input(
+            1,
+            action::Kind::Maneuver(Maneuver {
+                delay: 60.0 + i as f64,
+                delta_v: Vec2::new(10.0, 0.0),
+            }),
+        );
+    }
+    let tick = sim.step();
+
+    let maneuvers = tick.updates.iter().find_map(|component| match component {
+        Component::Craft(_, craft) => Some(craft.maneuvers.clone()),
+        _ => None,
+    });
+    let maneuvers = maneuvers.unwrap();
+
+    assert_eq!(maneuvers.len(), Craft::MAX_MANEUVERS);
+    assert!(maneuvers
+        .iter()
+        .all(|maneuver| maneuver.delay <= Maneuver::MAX_DELAY));
+}
+
+#[test]
+fn inputs_of_unknown_players_should_be_ignored() -> Result<(), Error> {
+    let script = Script::from_toml(
+        r#"
+        ticks = 2
+
+        [[events]]
+        tick   = 0
+        player = 1
+        kind   = { Input = { Thrust = true } }
+        "#,
+    )?;
+
+    let trace = script.run();
+
+    for tick in &trace.ticks {
+        let ships = tick
+            .updates
+            .iter()
+            .filter(|component| matches!(component, Component::Ship(_, _)))
+            .count();
+
+        assert_eq!(tick.inputs_handled, vec![]);
+        assert_eq!(ships, 0);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn simulation_should_be_reproducible_for_a_given_seed() -> Result<(), Error> {
+    let script = |seed| {
+        Script::from_toml(&format!(
+            r#"
+            ticks = 10
+            seed  = {}
+
+            [[events]]
+            tick   = 0
+            player = 1
+            kind   = {{ Connect = {{ color = [1.0, 1.0, 0.0] }} }}
+
+            [[events]]
+            tick   = 5
+            player = 1
+            kind   = {{ Input = {{ Thrust = true }} }}
+            "#,
+            seed,
+        ))
+    };
+
+    let trace_a = script(1)?.run();
+    let trace_b = script(1)?.run();
+    let trace_c = script(2)?.run();
+
+    assert_eq!(trace_a, trace_b);
+    assert_ne!(trace_a, trace_c);
+
+    Ok(())
+}
+
+fn fuel(tick: &Tick) -> Option<f64> {
+    tick.updates.iter().find_map(|component| match component {
+        Component::Fuel(_, fuel) => Some(fuel.0),
+        _ => None,
+    })
+}
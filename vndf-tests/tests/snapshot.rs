@@ -0,0 +1,92 @@
+use std::env;
+
+use vndf_shared::{
+    data::client::Component,
+    sim::{trace::Tick, Simulation},
+    world::{self, snapshot::Error, Pnt2, Snapshot},
+};
+
+#[test]
+fn restored_world_should_continue_like_the_original() -> Result<(), Error> {
+    let path = env::temp_dir().join(format!(
+        "vndf-snapshot-test-{}.snapshot",
+        std::process::id()
+    ));
+
+    let mut original = Simulation::new(1);
+    original.connect(1, [1.0, 1.0, 0.0]);
+    for _ in 0..10 {
+        original.step();
+    }
+
+    original.state().snapshot().save(&path)?;
+    let snapshot = Snapshot::load(&path)?;
+    std::fs::remove_file(&path)?;
+
+    let mut restored =
+        Simulation::from_state(world::State::restore(snapshot, 1)?);
+
+    assert_eq!(restored.state().tick(), original.state().tick());
+    assert_eq!(
+        restored.state().diagnostics(),
+        original.state().diagnostics()
+    );
+
+    let mut last = None;
+    for _ in 0..60 {
+        let a = original.step();
+        let b = restored.step();
+
+        assert_eq!(positions(&a), positions(&b));
+        last = Some(b);
+    }
+
+    let ships = last
+        .unwrap()
+        .updates
+        .iter()
+        .filter(|component| matches!(component, Component::Ship(_, _)))
+        .count();
+    assert_eq!(ships, 1);
+
+    Ok(())
+}
+
+#[test]
+fn restored_player_should_reconnect_with_their_token() -> Result<(), Error> {
+    let mut sim = Simulation::new(0);
+    sim.connect(1, [1.0, 1.0, 0.0]);
+    let id = sim.step().players_created[0].1;
+
+    let snapshot = sim.state().snapshot();
+    sim.restart(world::State::restore(snapshot, 0)?);
+
+    sim.reconnect(1, [1.0, 1.0, 0.0]);
+    let tick = sim.step();
+    assert_eq!(tick.players_reconnected, vec![(1, id)]);
+    assert!(tick.players_created.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn snapshot_without_planets_should_be_rejected() {
+    let mut snapshot = Simulation::new(0).state().snapshot();
+    snapshot.planets.clear();
+
+    let result = world::State::restore(snapshot, 0);
+    assert!(matches!(result, Err(Error::NoPlanets)));
+}
+
+fn positions(tick: &Tick) -> Vec<Pnt2> {
+    let mut positions: Vec<_> = tick
+        .updates
+        .iter()
+        .filter_map(|component| match component {
+            Component::Position(_, pos) => Some(pos.0),
+            _ => None,
+        })
+        .collect();
+    positions.sort_by(|a, b| a.x.partial_cmp(&b.x).unwrap());
+    positions
+}
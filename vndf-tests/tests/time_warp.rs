@@ -0,0 +1,92 @@
+use vndf_shared::{
+    action,
+    data::client::Component,
+    sim::{trace::Tick, Simulation},
+    world::features::planets::Planet,
+};
+
+#[test]
+fn time_warp_should_require_consensus() {
+    let mut sim = Simulation::new(0);
+
+    sim.connect(1, [1.0, 1.0, 0.0]);
+    sim.connect(2, [0.0, 1.0, 1.0]);
+    sim.step();
+
+    sim.input(1, action::Kind::TimeWarp(10.0));
+    sim.input(2, action::Kind::TimeWarp(1.0));
+    sim.step();
+    assert_eq!(sim.state().time_warp(), 1.0);
+
+    sim.input(2, action::Kind::TimeWarp(100.0));
+    sim.step();
+    assert_eq!(sim.state().time_warp(), 10.0);
+
+    sim.disconnect(1);
+    sim.step();
+    assert_eq!(sim.state().time_warp(), 100.0);
+}
+
+#[test]
+fn time_warp_should_not_be_blocked_by_idle_players() {
+    let mut sim = Simulation::new(0);
+
+    sim.connect(1, [1.0, 1.0, 0.0]);
+    sim.connect(2, [0.0, 1.0, 1.0]);
+    sim.step();
+
+    sim.input(1, action::Kind::TimeWarp(10.0));
+    sim.step();
+    assert_eq!(sim.state().time_warp(), 10.0);
+}
+
+#[test]
+fn time_warp_should_be_limited() {
+    let mut sim = Simulation::new(0);
+
+    sim.connect(1, [1.0, 1.0, 0.0]);
+    sim.step();
+
+    sim.input(1, action::Kind::TimeWarp(1e9));
+    sim.step();
+    assert_eq!(sim.state().time_warp(), 1000.0);
+
+    sim.input(1, action::Kind::TimeWarp(0.1));
+    sim.step();
+    assert_eq!(sim.state().time_warp(), 1.0);
+}
+
+#[test]
+fn time_warp_should_speed_up_the_world() {
+    let elapsed = |time_warp| {
+        let mut sim = Simulation::new(0);
+
+        sim.connect(1, [1.0, 1.0, 0.0]);
+        sim.input(1, action::Kind::TimeWarp(time_warp));
+        let before = planets(&sim.step());
+        let after = planets(&sim.step());
+
+        // Moons move on rails, so how far they moved is a good measure of how
+        // much time has passed.
+        before
+            .iter()
+            .zip(&after)
+            .map(|(a, b)| (b.pos - a.pos).length())
+            .fold(0.0, f64::max)
+    };
+
+    let normal = elapsed(1.0);
+    let warped = elapsed(10.0);
+
+    assert!((warped / normal - 10.0).abs() < 0.01);
+}
+
+fn planets(tick: &Tick) -> Vec<Planet> {
+    tick.updates
+        .iter()
+        .filter_map(|component| match component {
+            Component::Planet(_, planet) => Some(planet.clone()),
+            _ => None,
+        })
+        .collect()
+}
@@ -0,0 +1,105 @@
+use vndf_shared::{
+    data::client::Components,
+    sim::{trace::Tick, Script},
+    world::{
+        features::{
+            planets::Planets,
+            trajectory::{Burn, Trajectory},
+        },
+        Method, Pnt2, Vec2, FRAME_TIME,
+    },
+};
+
+#[test]
+fn prediction_should_match_simulation() {
+    const TICKS: u64 = 120;
+
+    let trace = script(TICKS).run();
+
+    let start = components(&trace.ticks[0]);
+    let (pos, vel) = ship(&start);
+
+    let trajectory = Trajectory::predict(
+        pos,
+        vel,
+        &[],
+        &Planets(&start.planets),
+        Method::default(),
+        FRAME_TIME,
+        TICKS as usize - 1,
+    );
+
+    let (actual, _) = ship(&components(trace.last().unwrap()));
+    let predicted = *trajectory.points.last().unwrap();
+
+    assert!(!trajectory.collides);
+    assert!((actual - predicted).length() < 1.0);
+}
+
+#[test]
+fn prediction_should_include_burns() {
+    let trace = script(1).run();
+
+    let start = components(&trace.ticks[0]);
+    let (pos, vel) = ship(&start);
+    let planets = Planets(&start.planets);
+
+    let coasting = Trajectory::predict(
+        pos,
+        vel,
+        &[],
+        &planets,
+        Method::default(),
+        10.0,
+        100,
+    );
+
+    // Kill most of the ship's velocity, so it falls into the planet.
+    let burn = Burn {
+        start: 0.0,
+        duration: 10.0,
+        acc: -vel * 0.09,
+    };
+    let falling = Trajectory::predict(
+        pos,
+        vel,
+        &[burn],
+        &planets,
+        Method::default(),
+        10.0,
+        1000,
+    );
+
+    assert!(!coasting.collides);
+    assert!(falling.collides);
+    assert!(falling.points.len() < 1001);
+}
+
+fn script(ticks: u64) -> Script {
+    Script::from_toml(&format!(
+        r#"
+        ticks = {}
+
+        [[events]]
+        tick   = 0
+        player = 1
+        kind   = {{ Connect = {{ color = [1.0, 1.0, 0.0] }} }}
+        "#,
+        ticks,
+    ))
+    .unwrap()
+}
+
+fn components(tick: &Tick) -> Components {
+    let mut components = Components::new();
+    for component in &tick.updates {
+        component.clone().update(&mut components);
+    }
+    components
+}
+
+fn ship(components: &Components) -> (Pnt2, Vec2) {
+    let pos = components.positions.values().next().unwrap();
+    let vel = components.velocities.values().next().unwrap();
+    (pos.0, vel.0)
+}
@@ -0,0 +1,163 @@
+use vndf_shared::{
+    action,
+    data::client::{Component, Handle},
+    sim::{trace::Tick, Simulation},
+    world::{
+        self,
+        features::{
+            crafts::Craft, health::Health, players::PlayerId,
+            weapons::Projectile,
+        },
+        Vec2,
+    },
+};
+
+#[test]
+fn crafts_should_fire_while_their_weapon_is_triggered() {
+    let mut sim = Simulation::new(0);
+
+    sim.connect(1, [1.0, 1.0, 0.0]);
+    let id = sim.step().players_created[0].1;
+    let ammo = craft(&sim.step(), id).ammo;
+
+    // Long enough for two shots, but not for a third.
+    sim.input(1, action::Kind::Fire(true));
+    for _ in 0..45 {
+        sim.step();
+    }
+    let tick = sim.step();
+    assert_eq!(projectiles(&tick), 2);
+    assert_eq!(craft(&tick, id).ammo, ammo - 2);
+
+    sim.input(1, action::Kind::Fire(false));
+    for _ in 0..60 {
+        sim.step();
+    }
+    let tick = sim.step();
+    assert_eq!(projectiles(&tick), 2);
+    assert_eq!(craft(&tick, id).ammo, ammo - 2);
+}
+
+#[test]
+fn projectiles_should_damage_the_crafts_they_hit() {
+    let mut sim = Simulation::new(0);
+
+    sim.connect(1, [1.0, 1.0, 0.0]);
+    sim.connect(2, [0.0, 1.0, 1.0]);
+    let tick = sim.step();
+    let shooter = tick.players_created[0].1;
+    let target = tick.players_created[1].1;
+
+    // Put the target right in front of the shooter, and pull the trigger.
+    let mut snapshot = sim.state().snapshot();
+    let components = &mut snapshot.components;
+
+    let mut shooter_body = None;
+    let mut target_body = None;
+    for (_, craft) in &mut components.crafts {
+        if craft.owner == shooter {
+            craft.firing = true;
+            shooter_body = Some(craft.body.weak());
+        }
+        if craft.owner == target {
+            target_body = Some(craft.body.weak());
+        }
+    }
+
+    let (_, shooter_body) = components
+        .bodies
+        .iter_mut()
+        .find(|(handle, _)| Some(*handle) == shooter_body)
+        .unwrap();
+    shooter_body.dir = Vec2::new(1.0, 0.0);
+    let shooter_body = shooter_body.clone();
+    let (_, target_body) = components
+        .bodies
+        .iter()
+        .find(|(handle, _)| Some(*handle) == target_body)
+        .unwrap();
+    let target_body = target_body.clone();
+
+    let (pos, vel) = {
+        let (_, pos) = components
+            .positions
+            .iter()
+            .find(|(handle, _)| *handle == shooter_body.pos.weak())
+            .unwrap();
+        let (_, vel) = components
+            .velocities
+            .iter()
+            .find(|(handle, _)| *handle == shooter_body.vel.weak())
+            .unwrap();
+        (pos.0, vel.0)
+    };
+    for (handle, target_pos) in &mut components.positions {
+        if *handle == target_body.pos.weak() {
+            target_pos.0 = pos + Vec2::new(100.0, 0.0);
+        }
+    }
+    for (handle, target_vel) in &mut components.velocities {
+        if *handle == target_body.vel.weak() {
+            target_vel.0 = vel;
+        }
+    }
+
+    let mut sim =
+        Simulation::from_state(world::State::restore(snapshot, 0).unwrap());
+
+    let mut removals = Vec::new();
+    for _ in 0..60 {
+        removals.extend(sim.step().removals);
+    }
+    let tick = sim.step();
+
+    assert!(health(&tick, target).value <= 10.0 - Projectile::DAMAGE);
+    assert_eq!(health(&tick, shooter).value, 10.0);
+    assert!(removals
+        .iter()
+        .any(|handle| matches!(handle, Handle::Projectile(_))));
+}
+
+#[test]
+fn projectiles_should_hit_targets_they_pass_between_two_checks() {
+    let target = world::Pnt2::new(0.0, 0.0);
+
+    // Moved from one side of the target to the other during the last step.
+    let pos = world::Pnt2::new(50.0, 0.0);
+    let vel = Vec2::new(1000.0, 0.0);
+
+    assert!(Projectile::hits(pos, vel, target, Vec2::zero(), 0.1));
+    assert!(!Projectile::hits(pos, vel, target, Vec2::zero(), 0.01));
+}
+
+fn craft(tick: &Tick, owner: PlayerId) -> Craft {
+    tick.updates
+        .iter()
+        .find_map(|component| match component {
+            Component::Craft(_, craft) if craft.owner == owner => {
+                Some(craft.clone())
+            }
+            _ => None,
+        })
+        .unwrap()
+}
+
+fn health(tick: &Tick, owner: PlayerId) -> Health {
+    let craft = craft(tick, owner);
+    tick.updates
+        .iter()
+        .find_map(|component| match component {
+            Component::Health(handle, health) if *handle == craft.health => {
+                Some(health.clone())
+            }
+            _ => None,
+        })
+        .unwrap()
+}
+
+fn projectiles(tick: &Tick) -> usize {
+    tick.updates
+        .iter()
+        .filter(|component| matches!(component, Component::Projectile(..)))
+        .count()
+}